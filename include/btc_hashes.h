@@ -0,0 +1,87 @@
+/*
+ * C interface of the btc-hashes crate, built with the ffi feature:
+ *      cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Contexts are opaque and created with the *_new functions, fed with *_update,
+ * and released with *_free. *_finalize writes the digest and resets the context
+ * so it can hash another message. Data pointers may be NULL when their length is 0.
+ *
+ * The PBKDF2 functions return BTC_OK, or one of the BTC_ERR_* codes without
+ * writing to out.
+ */
+
+#ifndef BTC_HASHES_H
+#define BTC_HASHES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BTC_SHA256_DIGEST_LENGTH 32
+#define BTC_SHA512_DIGEST_LENGTH 64
+#define BTC_SHA1_DIGEST_LENGTH 20
+#define BTC_RIPEMD160_DIGEST_LENGTH 20
+#define BTC_SHA256D_DIGEST_LENGTH 32
+#define BTC_HASH160_DIGEST_LENGTH 20
+
+#define BTC_OK 0
+#define BTC_ERR_ITERATIONS (-1)      /* the iteration count is zero */
+#define BTC_ERR_OUTPUT_TOO_LONG (-2) /* out_len is more than (2^32 - 1) digests */
+
+typedef struct btc_sha256_ctx btc_sha256_ctx;
+typedef struct btc_sha512_ctx btc_sha512_ctx;
+typedef struct btc_sha1_ctx btc_sha1_ctx;
+typedef struct btc_ripemd160_ctx btc_ripemd160_ctx;
+typedef struct btc_sha256d_ctx btc_sha256d_ctx;
+typedef struct btc_hash160_ctx btc_hash160_ctx;
+
+btc_sha256_ctx *btc_sha256_new(void);
+void btc_sha256_update(btc_sha256_ctx *ctx, const uint8_t *data, size_t len);
+void btc_sha256_finalize(btc_sha256_ctx *ctx, uint8_t out[BTC_SHA256_DIGEST_LENGTH]);
+void btc_sha256_free(btc_sha256_ctx *ctx);
+void btc_sha256(const uint8_t *data, size_t len, uint8_t out[BTC_SHA256_DIGEST_LENGTH]);
+
+btc_sha512_ctx *btc_sha512_new(void);
+void btc_sha512_update(btc_sha512_ctx *ctx, const uint8_t *data, size_t len);
+void btc_sha512_finalize(btc_sha512_ctx *ctx, uint8_t out[BTC_SHA512_DIGEST_LENGTH]);
+void btc_sha512_free(btc_sha512_ctx *ctx);
+void btc_sha512(const uint8_t *data, size_t len, uint8_t out[BTC_SHA512_DIGEST_LENGTH]);
+
+btc_sha1_ctx *btc_sha1_new(void);
+void btc_sha1_update(btc_sha1_ctx *ctx, const uint8_t *data, size_t len);
+void btc_sha1_finalize(btc_sha1_ctx *ctx, uint8_t out[BTC_SHA1_DIGEST_LENGTH]);
+void btc_sha1_free(btc_sha1_ctx *ctx);
+void btc_sha1(const uint8_t *data, size_t len, uint8_t out[BTC_SHA1_DIGEST_LENGTH]);
+
+btc_ripemd160_ctx *btc_ripemd160_new(void);
+void btc_ripemd160_update(btc_ripemd160_ctx *ctx, const uint8_t *data, size_t len);
+void btc_ripemd160_finalize(btc_ripemd160_ctx *ctx, uint8_t out[BTC_RIPEMD160_DIGEST_LENGTH]);
+void btc_ripemd160_free(btc_ripemd160_ctx *ctx);
+void btc_ripemd160(const uint8_t *data, size_t len, uint8_t out[BTC_RIPEMD160_DIGEST_LENGTH]);
+
+btc_sha256d_ctx *btc_sha256d_new(void);
+void btc_sha256d_update(btc_sha256d_ctx *ctx, const uint8_t *data, size_t len);
+void btc_sha256d_finalize(btc_sha256d_ctx *ctx, uint8_t out[BTC_SHA256D_DIGEST_LENGTH]);
+void btc_sha256d_free(btc_sha256d_ctx *ctx);
+void btc_sha256d(const uint8_t *data, size_t len, uint8_t out[BTC_SHA256D_DIGEST_LENGTH]);
+
+btc_hash160_ctx *btc_hash160_new(void);
+void btc_hash160_update(btc_hash160_ctx *ctx, const uint8_t *data, size_t len);
+void btc_hash160_finalize(btc_hash160_ctx *ctx, uint8_t out[BTC_HASH160_DIGEST_LENGTH]);
+void btc_hash160_free(btc_hash160_ctx *ctx);
+void btc_hash160(const uint8_t *data, size_t len, uint8_t out[BTC_HASH160_DIGEST_LENGTH]);
+
+void btc_hmac_sha256(const uint8_t *key, size_t key_len, const uint8_t *data, size_t len, uint8_t out[BTC_SHA256_DIGEST_LENGTH]);
+void btc_hmac_sha512(const uint8_t *key, size_t key_len, const uint8_t *data, size_t len, uint8_t out[BTC_SHA512_DIGEST_LENGTH]);
+
+int btc_pbkdf2_hmac_sha256(const uint8_t *password, size_t password_len, const uint8_t *salt, size_t salt_len, uint32_t iterations, uint8_t *out, size_t out_len);
+int btc_pbkdf2_hmac_sha512(const uint8_t *password, size_t password_len, const uint8_t *salt, size_t salt_len, uint32_t iterations, uint8_t *out, size_t out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
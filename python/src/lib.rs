@@ -0,0 +1,201 @@
+// Python bindings
+//
+// Exposes the engines as hashlib style objects with update(), digest(), hexdigest()
+// and copy(), along with HMAC and PBKDF2. Build the extension module with
+//      maturin build --release -m python/Cargo.toml
+
+use btc_hashes::{
+    HashEngine,
+    KeyBasedHashEngine,
+    Hmac,
+    Sha256,
+    Sha512,
+    Sha1,
+    Ripemd160,
+    Sha256d,
+    Hash160,
+    pbkdf2
+};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::PyBytes
+};
+
+/// Lower case hex of a digest
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Define a hashlib style class for an engine
+macro_rules! py_engine {
+    ($engine: ty, $class: ident, $name: literal) => {
+        #[pyclass(name = $name, skip_from_py_object)]
+        #[derive(Clone)]
+        struct $class {
+            engine: $engine
+        }
+
+        #[pymethods]
+        impl $class {
+            #[new]
+            #[pyo3(signature = (data = None))]
+            fn new(data: Option<&[u8]>) -> Self {
+                let mut engine = <$engine>::default();
+                engine.input(data.unwrap_or_default());
+                Self { engine }
+            }
+
+            #[getter]
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            #[getter]
+            fn digest_size(&self) -> usize {
+                <$engine>::OUTPUT_SIZE
+            }
+
+            #[getter]
+            fn block_size(&self) -> usize {
+                <$engine>::BLOCKSIZE
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.engine.input(data);
+            }
+
+            /// Digest of the data so far, more data can still be added
+            fn digest<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+                PyBytes::new(py, self.engine.finalise_copy().as_ref())
+            }
+
+            fn hexdigest(&self) -> String {
+                hex(self.engine.finalise_copy().as_ref())
+            }
+
+            fn copy(&self) -> Self {
+                self.clone()
+            }
+        }
+    };
+}
+
+py_engine!(Sha256, PySha256, "sha256");
+py_engine!(Sha512, PySha512, "sha512");
+py_engine!(Sha1, PySha1, "sha1");
+py_engine!(Ripemd160, PyRipemd160, "ripemd160");
+py_engine!(Sha256d, PySha256d, "sha256d");
+py_engine!(Hash160, PyHash160, "hash160");
+
+/// HMAC engine with one of the hash functions HMAC is commonly used with
+///
+/// The engine always lives inside a heap allocated Python object
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+enum HmacEngine {
+    Sha256(Hmac<Sha256>),
+    Sha512(Hmac<Sha512>),
+    Sha1(Hmac<Sha1>)
+}
+
+/// HMAC object in the style of Python's hmac module
+#[pyclass(name = "HMAC", skip_from_py_object)]
+#[derive(Clone)]
+struct PyHmac {
+    engine: HmacEngine,
+    name: &'static str
+}
+
+#[pymethods]
+impl PyHmac {
+    #[new]
+    #[pyo3(signature = (key, msg = None, digestmod = "sha256"))]
+    fn new(key: &[u8], msg: Option<&[u8]>, digestmod: &str) -> PyResult<Self> {
+        let (engine, name) = match digestmod {
+            "sha256" => (HmacEngine::Sha256(Hmac::new_with_key(key)), "hmac-sha256"),
+            "sha512" => (HmacEngine::Sha512(Hmac::new_with_key(key)), "hmac-sha512"),
+            "sha1" => (HmacEngine::Sha1(Hmac::new_with_key(key)), "hmac-sha1"),
+            _ => return Err(PyValueError::new_err(format!("unsupported digestmod {}", digestmod)))
+        };
+
+        let mut hmac = Self { engine, name };
+        hmac.update(msg.unwrap_or_default());
+        Ok(hmac)
+    }
+
+    #[getter]
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn update(&mut self, msg: &[u8]) {
+        match &mut self.engine {
+            HmacEngine::Sha256(engine) => engine.input(msg),
+            HmacEngine::Sha512(engine) => engine.input(msg),
+            HmacEngine::Sha1(engine) => engine.input(msg)
+        }
+    }
+
+    /// Tag of the message so far, more data can still be added
+    fn digest<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.tag())
+    }
+
+    fn hexdigest(&self) -> String {
+        hex(&self.tag())
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl PyHmac {
+    fn tag(&self) -> Vec<u8> {
+        match &self.engine {
+            HmacEngine::Sha256(engine) => engine.clone().finalise().into(),
+            HmacEngine::Sha512(engine) => engine.clone().finalise().into(),
+            HmacEngine::Sha1(engine) => engine.clone().finalise().into()
+        }
+    }
+}
+
+/// Derive a key with PBKDF2, with the same signature as hashlib.pbkdf2_hmac
+#[pyfunction]
+#[pyo3(signature = (hash_name, password, salt, iterations, dklen = None))]
+fn pbkdf2_hmac<'py>(py: Python<'py>, hash_name: &str, password: &[u8], salt: &[u8], iterations: usize, dklen: Option<usize>) -> PyResult<Bound<'py, PyBytes>> {
+    if iterations == 0 {
+        return Err(PyValueError::new_err("iterations must be at least one"));
+    }
+
+    let derive = match hash_name {
+        "sha256" => pbkdf2::<Hmac<Sha256>>,
+        "sha512" => pbkdf2::<Hmac<Sha512>>,
+        "sha1" => pbkdf2::<Hmac<Sha1>>,
+        _ => return Err(PyValueError::new_err(format!("unsupported hash {}", hash_name)))
+    };
+    let default_len = match hash_name {
+        "sha256" => Sha256::OUTPUT_SIZE,
+        "sha512" => Sha512::OUTPUT_SIZE,
+        _ => Sha1::OUTPUT_SIZE
+    };
+
+    let mut key = vec![0; dklen.unwrap_or(default_len)];
+    py.detach(|| derive(password, salt, iterations, &mut key))
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PyBytes::new(py, &key))
+}
+
+#[pymodule(name = "btc_hashes")]
+fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySha256>()?;
+    m.add_class::<PySha512>()?;
+    m.add_class::<PySha1>()?;
+    m.add_class::<PyRipemd160>()?;
+    m.add_class::<PySha256d>()?;
+    m.add_class::<PyHash160>()?;
+    m.add_class::<PyHmac>()?;
+    m.add_function(wrap_pyfunction!(pbkdf2_hmac, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+// ARMv8 cryptographic extensions
+//
+// SHA256 compression using the ARMv8 SHA2 instructions and SHA512 compression
+// using the ARMv8.2 SHA512 instructions. Both keep the state in vector registers
+// for the whole block.
+
+use ::core::arch::aarch64::*;
+use crate::constants::{
+    SHA256_ROUND_CONSTANTS,
+    SHA512_ROUND_CONSTANTS
+};
+
+/// Compress a single SHA256 block into the state.
+/// 
+/// Safety: the caller must check the CPU supports the SHA2 instructions first.
+#[target_feature(enable = "sha2")]
+pub(super) unsafe fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let abcd_save = vld1q_u32(state.as_ptr());
+    let efgh_save = vld1q_u32(state.as_ptr().add(4));
+    let (mut abcd, mut efgh) = (abcd_save, efgh_save);
+
+    // Load the block as big endian words
+    let mut w = [vdupq_n_u32(0); 4];
+    for (i, word) in w.iter_mut().enumerate() {
+        *word = vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block.as_ptr().add(i*16))));
+    }
+
+    // Each iteration runs four rounds, extending the schedule in place after the first sixteen words
+    for i in 0..16 {
+        if i >= 4 {
+            w[i%4] = vsha256su1q_u32(vsha256su0q_u32(w[i%4], w[(i+1)%4]), w[(i+2)%4], w[(i+3)%4]);
+        }
+
+        let t = vaddq_u32(w[i%4], vld1q_u32(SHA256_ROUND_CONSTANTS.as_ptr().add(i*4)));
+        let abcd_prev = abcd;
+        abcd = vsha256hq_u32(abcd_prev, efgh, t);
+        efgh = vsha256h2q_u32(efgh, abcd_prev, t);
+    }
+
+    vst1q_u32(state.as_mut_ptr(), vaddq_u32(abcd, abcd_save));
+    vst1q_u32(state.as_mut_ptr().add(4), vaddq_u32(efgh, efgh_save));
+}
+
+/// Compress a single SHA512 block into the state.
+/// 
+/// Safety: the caller must check the CPU supports the SHA512 instructions first.
+#[target_feature(enable = "sha3")]
+pub(super) unsafe fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) {
+    // The state is held as the register pairs AB, CD, EF and GH
+    let mut save = [vdupq_n_u64(0); 4];
+    for (i, pair) in save.iter_mut().enumerate() {
+        *pair = vld1q_u64(state.as_ptr().add(i*2));
+    }
+    let mut r = save;
+
+    // Load the block as big endian words
+    let mut w = [vdupq_n_u64(0); 8];
+    for (i, word) in w.iter_mut().enumerate() {
+        *word = vreinterpretq_u64_u8(vrev64q_u8(vld1q_u8(block.as_ptr().add(i*16))));
+    }
+
+    // Each iteration runs two rounds. The roles of the register pairs rotate every iteration,
+    // so AB, CD, EF and GH are picked out of the array by offset.
+    for i in 0..40 {
+        if i >= 8 {
+            w[i%8] = vsha512su1q_u64(
+                vsha512su0q_u64(w[i%8], w[(i+1)%8]),
+                w[(i+7)%8],
+                vextq_u64::<1>(w[(i+4)%8], w[(i+5)%8])
+            );
+        }
+
+        let (ab, cd, ef, gh) = ((4-i%4)%4, (5-i%4)%4, (6-i%4)%4, (7-i%4)%4);
+        let k = vaddq_u64(w[i%8], vld1q_u64(SHA512_ROUND_CONSTANTS.as_ptr().add(i*2)));
+        let sum = vaddq_u64(vextq_u64::<1>(k, k), r[gh]);
+        let t = vsha512hq_u64(sum, vextq_u64::<1>(r[ef], r[gh]), vextq_u64::<1>(r[cd], r[ef]));
+        r[gh] = vsha512h2q_u64(t, r[cd], r[ab]);
+        r[cd] = vaddq_u64(r[cd], t);
+    }
+
+    for (i, (pair, saved)) in r.iter().zip(save.iter()).enumerate() {
+        vst1q_u64(state.as_mut_ptr().add(i*2), vaddq_u64(*pair, *saved));
+    }
+}
@@ -0,0 +1,98 @@
+// Architecture module
+//
+// Hardware accelerated compression functions. The cpu module says which
+// instructions are available and each function reports back when it could not
+// run, so the engines can fall back to the portable implementation.
+//
+// With the portable-simd feature (nightly only), core::simd is used when there
+// is no dedicated backend for the target.
+
+use crate::cpu;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+#[cfg(feature = "portable-simd")]
+mod simd;
+
+/// Compress a SHA256 block with hardware instructions if the CPU supports them.
+/// 
+/// Returns false without touching the state if no accelerated backend is available.
+#[allow(unused_variables)]
+pub(crate) fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) -> bool {
+    let features = cpu::features();
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if features.sha_ni {
+        // Safety: the CPU has been checked for the required instructions
+        unsafe { x86::sha256_compress(state, block) };
+        return true;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if features.sha2 {
+        // Safety: the CPU has been checked for the required instructions
+        unsafe { aarch64::sha256_compress(state, block) };
+        return true;
+    }
+
+    false
+}
+
+/// Compress one block from each of four independent SHA256 states side by side.
+/// 
+/// Returns false without touching the states if there is no multi-buffer backend, or
+/// if single block hardware compression is available and would be faster.
+#[allow(unused_variables)]
+pub(crate) fn sha256_compress4(states: &mut [[u32; 8]; 4], blocks: [&[u8; 64]; 4]) -> bool {
+    let features = cpu::features();
+    if features.sha_ni || features.sha2 {
+        return false;
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if features.sse41 {
+        // Safety: the CPU has been checked for the required instructions
+        unsafe { x86::sha256_compress4(states, blocks) };
+        return true;
+    }
+
+    #[cfg(feature = "portable-simd")]
+    if !cpu::portable_forced() {
+        simd::sha256_compress4(states, blocks);
+        return true;
+    }
+
+    false
+}
+
+/// Compress a SHA512 block with hardware instructions if the CPU supports them.
+/// 
+/// Returns false without touching the state if no accelerated backend is available.
+#[allow(unused_variables)]
+pub(crate) fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) -> bool {
+    let features = cpu::features();
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if features.avx2 {
+        // Safety: the CPU has been checked for the required instructions
+        unsafe { x86::sha512_compress(state, block) };
+        return true;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if features.sha512 {
+        // Safety: the CPU has been checked for the required instructions
+        unsafe { aarch64::sha512_compress(state, block) };
+        return true;
+    }
+
+    #[cfg(feature = "portable-simd")]
+    if !cpu::portable_forced() {
+        simd::sha512_compress(state, block);
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,149 @@
+// Portable SIMD
+//
+// Vectorized compression using core::simd for targets without dedicated
+// backends, such as wasm with SIMD128 or RISC-V V. Four SHA256 blocks are
+// compressed side by side, one message per lane, and the SHA512 message
+// schedule is computed four words at a time.
+
+use ::core::simd::{
+    u32x4,
+    u64x4,
+    simd_swizzle
+};
+use crate::{
+    core::functions::sha2::*,
+    constants::{
+        SHA256_ROUND_CONSTANTS,
+        SHA512_ROUND_CONSTANTS
+    }
+};
+
+// Rotate each lane right
+macro_rules! rotr {
+    ($x: expr, $n: expr, $ty: ident, $bits: expr) => {
+        ($x >> $ty::splat($n)) | ($x << $ty::splat($bits-$n))
+    };
+}
+
+/// Compress one block from each of four independent SHA256 states
+pub(super) fn sha256_compress4(states: &mut [[u32; 8]; 4], blocks: [&[u8; 64]; 4]) {
+    let mut v = [u32x4::splat(0); 8];
+    for (i, reg) in v.iter_mut().enumerate() {
+        *reg = u32x4::from_array([states[0][i], states[1][i], states[2][i], states[3][i]]);
+    }
+    let save = v;
+
+    let mut w = [u32x4::splat(0); 16];
+    for (i, word) in w.iter_mut().enumerate() {
+        let lane = |l: usize| u32::from_be_bytes([blocks[l][i*4], blocks[l][i*4+1], blocks[l][i*4+2], blocks[l][i*4+3]]);
+        *word = u32x4::from_array([lane(0), lane(1), lane(2), lane(3)]);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = v;
+    for i in 0..64 {
+        // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
+        if i >= 16 {
+            let (w2, w15) = (w[(i-2)%16], w[(i-15)%16]);
+            let s1 = rotr!(w2, 17, u32x4, 32) ^ rotr!(w2, 19, u32x4, 32) ^ (w2 >> u32x4::splat(10));
+            let s0 = rotr!(w15, 7, u32x4, 32) ^ rotr!(w15, 18, u32x4, 32) ^ (w15 >> u32x4::splat(3));
+            w[i%16] = s1 + w[(i-7)%16] + s0 + w[i%16];
+        }
+
+        let usigma1 = rotr!(e, 6, u32x4, 32) ^ rotr!(e, 11, u32x4, 32) ^ rotr!(e, 25, u32x4, 32);
+        let choice = (e & f) ^ (!e & g);
+        let t1 = h + usigma1 + choice + u32x4::splat(SHA256_ROUND_CONSTANTS[i]) + w[i%16];
+
+        let usigma0 = rotr!(a, 2, u32x4, 32) ^ rotr!(a, 13, u32x4, 32) ^ rotr!(a, 22, u32x4, 32);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = usigma0 + majority;
+
+        h = g;
+        g = f;
+        f = e;
+        e = d + t1;
+        d = c;
+        c = b;
+        b = a;
+        a = t1 + t2;
+    }
+    v = [a, b, c, d, e, f, g, h];
+
+    // Add the compressed block to each state and transpose back
+    for (i, (reg, saved)) in v.iter().zip(save.iter()).enumerate() {
+        for (state, x) in states.iter_mut().zip((*reg + *saved).to_array().iter()) {
+            state[i] = *x;
+        }
+    }
+}
+
+/// Compress a single SHA512 block into the state.
+/// 
+/// W[i+2] and W[i+3] depend on W[i] and W[i+1] through σ1, so the upper two lanes of
+/// each group of four schedule words are finished once the lower two are known.
+pub(super) fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) {
+    let mut w = [0u64; 80];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(8)) {
+        *word = u64::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]);
+    }
+
+    let load = |w: &[u64; 80], i: usize| u64x4::from_slice(&w[i..i+4]);
+    let lsigma0 = |x: u64x4| rotr!(x, 1, u64x4, 64) ^ rotr!(x, 8, u64x4, 64) ^ (x >> u64x4::splat(7));
+    let lsigma1 = |x: u64x4| rotr!(x, 19, u64x4, 64) ^ rotr!(x, 61, u64x4, 64) ^ (x >> u64x4::splat(6));
+    for i in (16..80).step_by(4) {
+        // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
+        let partial = load(&w, i-16) + lsigma0(load(&w, i-15)) + load(&w, i-7);
+
+        // The upper lanes of W[i-2..i+2] are not computed yet and are still zero
+        let lower = partial + lsigma1(load(&w, i-2));
+        let upper = partial + lsigma1(simd_swizzle!(lower, [0, 0, 0, 1]));
+        let (lower, upper) = (lower.to_array(), upper.to_array());
+        w[i..i+4].copy_from_slice(&[lower[0], lower[1], upper[2], upper[3]]);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in (0..80).step_by(4) {
+        let wk = (load(&w, i) + u64x4::from_slice(&SHA512_ROUND_CONSTANTS[i..i+4])).to_array();
+        for wk in wk.iter() {
+            let t1 = h.wrapping_add(u64::usigma1(e))
+                .wrapping_add(choice(e, f, g))
+                .wrapping_add(*wk);
+            let t2 = u64::usigma0(a).wrapping_add(majority(a, b, c));
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+    }
+
+    for (reg, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *reg = reg.wrapping_add(*x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashEngine, Sha256, Sha512};
+    use crate::constants::{SHA256_INITIAL_CONSTANTS, SHA512_INITIAL_CONSTANTS};
+
+    #[test]
+    fn portable_simd() {
+        // Each lane must match the midstate of its block hashed on its own
+        let blocks: [[u8; 64]; 4] = [[0x00; 64], [0x61; 64], [0xff; 64], [0x5a; 64]];
+        let mut states = [SHA256_INITIAL_CONSTANTS; 4];
+        sha256_compress4(&mut states, [&blocks[0], &blocks[1], &blocks[2], &blocks[3]]);
+        for (state, block) in states.iter().zip(blocks.iter()) {
+            assert_eq!(*state, Sha256::new().chain(block).midstate().registers());
+        }
+
+        let block = [0x61; 128];
+        let mut state = SHA512_INITIAL_CONSTANTS;
+        sha512_compress(&mut state, &block);
+        assert_eq!(state, Sha512::new().chain(block).midstate().registers());
+    }
+}
@@ -0,0 +1,286 @@
+// x86 SHA extensions and SIMD
+//
+// SHA256 compression using the SHA-NI instructions. Each sha256rnds2 instruction
+// runs two rounds with the state split across two registers as ABEF and CDGH.
+//
+// For CPUs without SHA-NI, four independent SHA256 blocks can be compressed
+// at once with SSE, one message per 32 bit lane.
+//
+// SHA512 uses AVX2 to compute the message schedule four words at a time,
+// which takes the schedule off the critical path of the rounds.
+
+#[cfg(target_arch = "x86")]
+use ::core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use ::core::arch::x86_64::*;
+use crate::{
+    core::functions::sha2::*,
+    constants::{
+        SHA256_ROUND_CONSTANTS,
+        SHA512_ROUND_CONSTANTS
+    }
+};
+
+// Run four rounds with the next four schedule words
+macro_rules! rounds4 {
+    ($abef: ident, $cdgh: ident, $w: expr, $i: expr) => {
+        let k = _mm_loadu_si128(SHA256_ROUND_CONSTANTS.as_ptr().add($i*4) as *const __m128i);
+        let t = _mm_add_epi32($w, k);
+        $cdgh = _mm_sha256rnds2_epu32($cdgh, $abef, t);
+        $abef = _mm_sha256rnds2_epu32($abef, $cdgh, _mm_shuffle_epi32(t, 0x0e));
+    };
+}
+
+// Compute the next four schedule words from the previous sixteen, then run four rounds with them
+macro_rules! schedule_rounds4 {
+    ($abef: ident, $cdgh: ident, $w0: ident, $w1: ident, $w2: ident, $w3: ident, $w4: ident, $i: expr) => {
+        $w4 = _mm_sha256msg2_epu32(
+            _mm_add_epi32(_mm_sha256msg1_epu32($w0, $w1), _mm_alignr_epi8($w3, $w2, 4)),
+            $w3
+        );
+        rounds4!($abef, $cdgh, $w4, $i);
+    };
+}
+
+/// Compress a single block into the state.
+/// 
+/// Safety: the caller must check the CPU supports SHA-NI and SSE4.1 first.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub(super) unsafe fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    // Byte shuffle to load the big endian words of the block
+    let mask = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+
+    // Rearrange the state from ABCD EFGH into ABEF CDGH
+    let dcba = _mm_loadu_si128(state.as_ptr() as *const __m128i);
+    let hgfe = _mm_loadu_si128(state.as_ptr().add(4) as *const __m128i);
+    let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+    let (abef_save, cdgh_save) = (abef, cdgh);
+
+    let data = block.as_ptr() as *const __m128i;
+    let mut w0 = _mm_shuffle_epi8(_mm_loadu_si128(data), mask);
+    let mut w1 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(1)), mask);
+    let mut w2 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(2)), mask);
+    let mut w3 = _mm_shuffle_epi8(_mm_loadu_si128(data.add(3)), mask);
+    let mut w4;
+
+    rounds4!(abef, cdgh, w0, 0);
+    rounds4!(abef, cdgh, w1, 1);
+    rounds4!(abef, cdgh, w2, 2);
+    rounds4!(abef, cdgh, w3, 3);
+    schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 4);
+    schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 5);
+    schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 6);
+    schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 7);
+    schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 8);
+    schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 9);
+    schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 10);
+    schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 11);
+    schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 12);
+    schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 13);
+    schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 14);
+    schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 15);
+
+    abef = _mm_add_epi32(abef, abef_save);
+    cdgh = _mm_add_epi32(cdgh, cdgh_save);
+
+    // Rearrange ABEF CDGH back into ABCD EFGH
+    let feba = _mm_shuffle_epi32(abef, 0x1b);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+    let hgef = _mm_alignr_epi8(dchg, feba, 8);
+    _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, dcba);
+    _mm_storeu_si128(state.as_mut_ptr().add(4) as *mut __m128i, hgef);
+}
+
+// Rotate each 32 bit lane right
+macro_rules! rotr {
+    ($x: expr, $n: expr) => {
+        _mm_or_si128(_mm_srli_epi32($x, $n), _mm_slli_epi32($x, 32-$n))
+    };
+}
+
+// Add four vectors lane wise
+macro_rules! add4 {
+    ($a: expr, $b: expr, $c: expr, $d: expr) => {
+        _mm_add_epi32(_mm_add_epi32($a, $b), _mm_add_epi32($c, $d))
+    };
+}
+
+/// Compress one block from each of four independent states.
+/// 
+/// The states and blocks are transposed so that each register holds the same word from
+/// all four messages, then the regular SHA256 rounds are run on every lane at once.
+/// 
+/// Safety: the caller must check the CPU supports SSE4.1 first.
+#[target_feature(enable = "sse2,sse4.1")]
+pub(super) unsafe fn sha256_compress4(states: &mut [[u32; 8]; 4], blocks: [&[u8; 64]; 4]) {
+    let mut v = [_mm_setzero_si128(); 8];
+    for (i, reg) in v.iter_mut().enumerate() {
+        *reg = _mm_set_epi32(states[3][i] as i32, states[2][i] as i32, states[1][i] as i32, states[0][i] as i32);
+    }
+    let save = v;
+
+    let mut w = [_mm_setzero_si128(); 16];
+    for (i, word) in w.iter_mut().enumerate() {
+        let lane = |l: usize| i32::from_be_bytes([blocks[l][i*4], blocks[l][i*4+1], blocks[l][i*4+2], blocks[l][i*4+3]]);
+        *word = _mm_set_epi32(lane(3), lane(2), lane(1), lane(0));
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = v;
+    for i in 0..64 {
+        // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
+        if i >= 16 {
+            let (w2, w15) = (w[(i-2)%16], w[(i-15)%16]);
+            let s1 = _mm_xor_si128(_mm_xor_si128(rotr!(w2, 17), rotr!(w2, 19)), _mm_srli_epi32(w2, 10));
+            let s0 = _mm_xor_si128(_mm_xor_si128(rotr!(w15, 7), rotr!(w15, 18)), _mm_srli_epi32(w15, 3));
+            w[i%16] = add4!(s1, w[(i-7)%16], s0, w[i%16]);
+        }
+
+        let usigma1 = _mm_xor_si128(_mm_xor_si128(rotr!(e, 6), rotr!(e, 11)), rotr!(e, 25));
+        let choice = _mm_xor_si128(_mm_and_si128(e, f), _mm_andnot_si128(e, g));
+        let k = _mm_set1_epi32(SHA256_ROUND_CONSTANTS[i] as i32);
+        let t1 = _mm_add_epi32(add4!(h, usigma1, choice, k), w[i%16]);
+
+        let usigma0 = _mm_xor_si128(_mm_xor_si128(rotr!(a, 2), rotr!(a, 13)), rotr!(a, 22));
+        let majority = _mm_xor_si128(_mm_xor_si128(_mm_and_si128(a, b), _mm_and_si128(a, c)), _mm_and_si128(b, c));
+        let t2 = _mm_add_epi32(usigma0, majority);
+
+        h = g;
+        g = f;
+        f = e;
+        e = _mm_add_epi32(d, t1);
+        d = c;
+        c = b;
+        b = a;
+        a = _mm_add_epi32(t1, t2);
+    }
+    v = [a, b, c, d, e, f, g, h];
+
+    // Add the compressed block to each state and transpose back
+    for (i, (reg, saved)) in v.iter().zip(save.iter()).enumerate() {
+        let reg = _mm_add_epi32(*reg, *saved);
+        states[0][i] = _mm_extract_epi32(reg, 0) as u32;
+        states[1][i] = _mm_extract_epi32(reg, 1) as u32;
+        states[2][i] = _mm_extract_epi32(reg, 2) as u32;
+        states[3][i] = _mm_extract_epi32(reg, 3) as u32;
+    }
+}
+
+// Rotate each 64 bit lane right
+macro_rules! rotr64 {
+    ($x: expr, $n: expr) => {
+        _mm256_or_si256(_mm256_srli_epi64($x, $n), _mm256_slli_epi64($x, 64-$n))
+    };
+}
+
+// σ0 and σ1 of each 64 bit lane
+macro_rules! lsigma0 {
+    ($x: expr) => {
+        _mm256_xor_si256(_mm256_xor_si256(rotr64!($x, 1), rotr64!($x, 8)), _mm256_srli_epi64($x, 7))
+    };
+}
+macro_rules! lsigma1 {
+    ($x: expr) => {
+        _mm256_xor_si256(_mm256_xor_si256(rotr64!($x, 19), rotr64!($x, 61)), _mm256_srli_epi64($x, 6))
+    };
+}
+
+/// Compress a single SHA512 block into the state.
+/// 
+/// The schedule is computed four words at a time. W[i+2] and W[i+3] depend on W[i] and W[i+1]
+/// through σ1, so the upper two lanes are finished in a second step once the lower two are known.
+/// The round constants are added to the schedule in the same pass and the rounds are run on the result.
+/// 
+/// Safety: the caller must check the CPU supports AVX2 first.
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) {
+    let mut w = [0u64; 80];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(8)) {
+        *word = u64::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]);
+    }
+
+    let load = |w: &[u64; 80], i: usize| _mm256_loadu_si256(w.as_ptr().add(i) as *const __m256i);
+    for i in (16..80).step_by(4) {
+        // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
+        let partial = _mm256_add_epi64(_mm256_add_epi64(load(&w, i-16), lsigma0!(load(&w, i-15))), load(&w, i-7));
+
+        // The upper lanes of W[i-2..i+2] are not computed yet and are still zero
+        let lower = _mm256_add_epi64(partial, lsigma1!(load(&w, i-2)));
+        let upper = _mm256_add_epi64(partial, lsigma1!(_mm256_permute4x64_epi64(lower, 0x40)));
+        _mm256_storeu_si256(w.as_mut_ptr().add(i) as *mut __m256i, _mm256_blend_epi32(lower, upper, 0xf0));
+    }
+
+    let mut wk = [0u64; 80];
+    for i in (0..80).step_by(4) {
+        let k = _mm256_loadu_si256(SHA512_ROUND_CONSTANTS.as_ptr().add(i) as *const __m256i);
+        _mm256_storeu_si256(wk.as_mut_ptr().add(i) as *mut __m256i, _mm256_add_epi64(load(&w, i), k));
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for wk in wk.iter() {
+        let t1 = h.wrapping_add(u64::usigma1(e))
+            .wrapping_add(choice(e, f, g))
+            .wrapping_add(*wk);
+        let t2 = u64::usigma0(a).wrapping_add(majority(a, b, c));
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (reg, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *reg = reg.wrapping_add(*x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashEngine, Sha256, Sha512};
+    use crate::constants::{SHA256_INITIAL_CONSTANTS, SHA512_INITIAL_CONSTANTS};
+    use crate::core::State;
+
+    #[test]
+    fn sse41_four_lanes() {
+        if !crate::cpu::features().sse41 {
+            return;
+        }
+
+        // Each lane must match the midstate of its block hashed on its own
+        let blocks: [[u8; 64]; 4] = [[0x00; 64], [0x61; 64], [0xff; 64], [0x5a; 64]];
+        let mut states = [SHA256_INITIAL_CONSTANTS; 4];
+        unsafe { sha256_compress4(&mut states, [&blocks[0], &blocks[1], &blocks[2], &blocks[3]]) };
+        for (state, block) in states.iter().zip(blocks.iter()) {
+            assert_eq!(*state, Sha256::new().chain(block).midstate().registers());
+        }
+    }
+
+    #[test]
+    fn avx2_sha512() {
+        if !crate::cpu::features().avx2 {
+            return;
+        }
+
+        // Chain several different blocks so every lane of the schedule and a non-initial state are covered
+        let mut state = SHA512_INITIAL_CONSTANTS;
+        let mut portable = State::init(SHA512_INITIAL_CONSTANTS);
+        for seed in 0..8u8 {
+            let mut block = [0u8; 128];
+            for (i, byte) in block.iter_mut().enumerate() {
+                *byte = (i as u8).wrapping_mul(31).wrapping_add(seed.wrapping_mul(97)) ^ seed;
+            }
+
+            unsafe { sha512_compress(&mut state, &block) };
+            Sha512::compress(&mut portable, &block);
+            assert_eq!(state, portable.read());
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Backend module
+//
+// Traits for plugging a custom compression function into the SHA2 engines.
+// The engine keeps doing the buffering, padding and midstate handling and only
+// hands whole blocks to the backend, so compression can be delegated to an HSM,
+// an FPGA or a vendor library.
+
+use crate::{
+    core::State,
+    sha2::{Sha256, Sha512}
+};
+
+/// SHA256 (and SHA224) block compression
+pub trait Sha256Backend {
+    /// Compress a single block into the state registers
+    fn compress(state: &mut [u32; 8], block: &[u8; 64]);
+}
+
+/// SHA512 (and SHA384) block compression
+pub trait Sha512Backend {
+    /// Compress a single block into the state registers
+    fn compress(state: &mut [u64; 8], block: &[u8; 128]);
+}
+
+/// Pure Rust compression backend
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Portable;
+
+impl Sha256Backend for Portable {
+    fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+        let mut s = State::init(*state);
+        Sha256::compress(&mut s, block);
+        *state = s.read();
+    }
+}
+
+impl Sha512Backend for Portable {
+    fn compress(state: &mut [u64; 8], block: &[u8; 128]) {
+        let mut s = State::init(*state);
+        Sha512::compress(&mut s, block);
+        *state = s.read();
+    }
+}
@@ -0,0 +1,257 @@
+// btc-hashes command line tool
+//
+// Works like sha256sum for every engine in the crate, including the Bitcoin
+// specific sha256d, hash160 and BIP340 tagged hashes. Built with the cli feature:
+//      cargo install --path . --features cli
+
+use std::{
+    env,
+    fs::File,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Read
+    },
+    process
+};
+use btc_hashes::{
+    HashEngine,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_256,
+    Ripemd160,
+    Sha256d,
+    Hash160,
+    tag_midstate
+};
+
+const USAGE: &str = "\
+Usage: btc-hashes [OPTIONS] [FILE]...
+Print or check digests of each FILE, or standard input when FILE is - or missing.
+
+Options:
+  -a, --algorithm NAME   sha1, sha224, sha256 (default), sha384, sha512, sha3-256,
+                         ripemd160, sha256d or hash160
+  -t, --tag TAG          BIP340 tagged SHA256 with the given tag
+  -r, --reverse          print digests in reverse byte order, as used for txids
+  -c, --check            read digests from the FILEs and check them
+  -h, --help             print this help";
+
+/// Hash function selected on the command line
+enum Algorithm {
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_256,
+    Ripemd160,
+    Sha256d,
+    Hash160,
+    Tagged(Vec<u8>)
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        let algorithm = match name {
+            "sha1" => Self::Sha1,
+            "sha224" => Self::Sha224,
+            "sha256" => Self::Sha256,
+            "sha384" => Self::Sha384,
+            "sha512" => Self::Sha512,
+            "sha3-256" => Self::Sha3_256,
+            "ripemd160" => Self::Ripemd160,
+            "sha256d" => Self::Sha256d,
+            "hash160" => Self::Hash160,
+            _ => return None
+        };
+
+        Some(algorithm)
+    }
+
+    /// Digest of everything in the reader
+    fn hash<R: Read>(&self, reader: R) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Sha1 => hash_reader(Sha1::new(), reader),
+            Self::Sha224 => hash_reader(Sha224::new(), reader),
+            Self::Sha256 => hash_reader(Sha256::new(), reader),
+            Self::Sha384 => hash_reader(Sha384::new(), reader),
+            Self::Sha512 => hash_reader(Sha512::new(), reader),
+            Self::Sha3_256 => hash_reader(Sha3_256::default(), reader),
+            Self::Ripemd160 => hash_reader(Ripemd160::new(), reader),
+            Self::Sha256d => hash_reader(Sha256d::default(), reader),
+            Self::Hash160 => hash_reader(Hash160::default(), reader),
+            Self::Tagged(tag) => {
+                let mut engine = Sha256::new();
+                engine.from_midstate(tag_midstate(tag));
+                hash_reader(engine, reader)
+            }
+        }
+    }
+}
+
+struct Options {
+    algorithm: Algorithm,
+    reverse: bool,
+    check: bool,
+    files: Vec<String>
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Self {
+            algorithm: Algorithm::Sha256,
+            reverse: false,
+            check: false,
+            files: Vec::new()
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-a" | "--algorithm" => {
+                    let name = args.next().ok_or("missing algorithm name")?;
+                    options.algorithm = Algorithm::from_name(&name).ok_or(format!("unknown algorithm {}", name))?;
+                },
+                "-t" | "--tag" => {
+                    let tag = args.next().ok_or("missing tag")?;
+                    options.algorithm = Algorithm::Tagged(tag.into_bytes());
+                },
+                "-r" | "--reverse" => options.reverse = true,
+                "-c" | "--check" => options.check = true,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                "--" => {
+                    options.files.extend(args);
+                    break;
+                },
+                _ if arg.len() > 1 && arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+                _ => options.files.push(arg)
+            }
+        }
+
+        if options.files.is_empty() {
+            options.files.push("-".to_string());
+        }
+        Ok(options)
+    }
+
+    /// Hex encoding of a digest in the requested byte order
+    fn to_hex(&self, digest: &[u8]) -> String {
+        let hex = |b: &u8| format!("{:02x}", b);
+        if self.reverse {
+            digest.iter().rev().map(hex).collect()
+        } else {
+            digest.iter().map(hex).collect()
+        }
+    }
+}
+
+fn hash_reader<T: HashEngine, R: Read>(mut engine: T, reader: R) -> io::Result<Vec<u8>> {
+    engine.input_from_reader(reader)?;
+    Ok(engine.finalise().into())
+}
+
+/// Open a file, or standard input for -
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        Ok(Box::new(io::stdin()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// Print the digest of each file, returning whether all of them could be read
+fn print_digests(options: &Options) -> bool {
+    let mut ok = true;
+    for path in &options.files {
+        match open(path).and_then(|reader| options.algorithm.hash(reader)) {
+            Ok(digest) => println!("{}  {}", options.to_hex(&digest), path),
+            Err(e) => {
+                eprintln!("btc-hashes: {}: {}", path, e);
+                ok = false;
+            }
+        }
+    }
+
+    ok
+}
+
+/// Check the digests listed in each file, returning whether all of them matched
+fn check_digests(options: &Options) -> bool {
+    let mut ok = true;
+    for list in &options.files {
+        let reader = match open(list) {
+            Ok(reader) => BufReader::new(reader),
+            Err(e) => {
+                eprintln!("btc-hashes: {}: {}", list, e);
+                ok = false;
+                continue;
+            }
+        };
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("btc-hashes: {}: {}", list, e);
+                    ok = false;
+                    break;
+                }
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            // Lines are "<digest>  <file>", or "<digest> *<file>" for binary mode
+            let (expected, path) = match line.split_once(' ') {
+                Some((expected, path)) => (expected, path.strip_prefix(|c| c == ' ' || c == '*').unwrap_or(path)),
+                None => {
+                    eprintln!("btc-hashes: {}: line {} is improperly formatted", list, i + 1);
+                    ok = false;
+                    continue;
+                }
+            };
+
+            match open(path).and_then(|reader| options.algorithm.hash(reader)) {
+                Ok(digest) if options.to_hex(&digest).eq_ignore_ascii_case(expected) => println!("{}: OK", path),
+                Ok(_) => {
+                    println!("{}: FAILED", path);
+                    ok = false;
+                },
+                Err(e) => {
+                    eprintln!("btc-hashes: {}: {}", path, e);
+                    println!("{}: FAILED open or read", path);
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    ok
+}
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("btc-hashes: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let ok = if options.check {
+        check_digests(&options)
+    } else {
+        print_digests(&options)
+    };
+
+    if !ok {
+        process::exit(1);
+    }
+}
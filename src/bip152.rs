@@ -0,0 +1,43 @@
+// BIP152 module
+//
+// Compact blocks identify transactions by 6 byte short IDs: SipHash-2-4 of the
+// wtxid, keyed from the sha256 of the block header and a random nonce.
+
+use crate::{
+    HashEngine,
+    Sha256,
+    siphash::SipHash24
+};
+
+/// Derive the SipHash key for a compact block from its header and nonce
+pub fn shortid_key(header: &[u8; 80], nonce: u64) -> (u64, u64) {
+    let digest = Sha256::new().chain(header).chain(nonce.to_le_bytes()).finalise();
+    let mut k0 = [0u8; 8];
+    let mut k1 = [0u8; 8];
+    k0.copy_from_slice(&digest.0[..8]);
+    k1.copy_from_slice(&digest.0[8..16]);
+    (u64::from_le_bytes(k0), u64::from_le_bytes(k1))
+}
+
+/// Compute the 6 byte short ID of a transaction, in the low bits of the result
+pub fn shortid(wtxid: &[u8; 32], siphash_key: (u64, u64)) -> u64 {
+    SipHash24::hash(siphash_key.0, siphash_key.1, wtxid) & 0xffff_ffff_ffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn genesis_shortid() {
+        let header: [u8; 80] = hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c");
+
+        let key = shortid_key(&header, 0x0123456789abcdef);
+        assert_eq!(key, (0x52f1458bc3816130, 0xd82182c249af75c6));
+
+        let coinbase = Sha256Digest::from_hex_reversed("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b").unwrap();
+        assert_eq!(shortid(&coinbase.0, key), 0xd97c3183bddd);
+    }
+}
@@ -0,0 +1,47 @@
+// BIP158 module
+//
+// Compact block filters hash every item into [0, N * M) with SipHash-2-4, keyed from
+// the first 16 bytes of the block hash. The 64-bit hash is mapped into the range with
+// a 128-bit multiply instead of a modulo. Golomb-Rice coding of the sorted values is
+// left to the filter builder.
+
+use crate::siphash::SipHash24;
+
+/// Inverse false positive rate for basic filters
+pub const BIP158_M: u64 = 784931;
+
+/// Golomb-Rice parameter for basic filters
+pub const BIP158_P: u8 = 19;
+
+/// Derive the SipHash key for a block's filter from its block hash, in internal byte order
+pub fn filter_key(block_hash: &[u8; 32]) -> (u64, u64) {
+    let mut k0 = [0u8; 8];
+    let mut k1 = [0u8; 8];
+    k0.copy_from_slice(&block_hash[..8]);
+    k1.copy_from_slice(&block_hash[8..16]);
+    (u64::from_le_bytes(k0), u64::from_le_bytes(k1))
+}
+
+/// Map an item into [0, f) where f is the number of items times M
+pub fn hash_to_range<I: AsRef<[u8]>>(item: I, key: (u64, u64), f: u64) -> u64 {
+    let hash = SipHash24::hash(key.0, key.1, item);
+    ((hash as u128 * f as u128) >> 64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn genesis_coinbase_output() {
+        let block_hash = Sha256Digest::from_hex_reversed("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+        let key = filter_key(&block_hash.0);
+        assert_eq!(key, (0x72b3f1b60a8ce26f, 0x4ff763ae46a2a6c1));
+
+        let script: [u8; 67] = hex("4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac");
+        assert_eq!(hash_to_range(script, key, BIP158_M), 522888);
+        assert_eq!(hash_to_range(script, key, 10 * BIP158_M), 5228881);
+    }
+}
@@ -0,0 +1,56 @@
+// BIP32 module
+//
+// The hashing half of BIP32 hierarchical deterministic key derivation. Turning the
+// left half of each output into a key (and checking it is valid for secp256k1) is
+// left to the caller's curve library.
+
+use crate::{
+    hmac_sha512,
+    HashEngine,
+    HmacSha512,
+    KeyBasedHashEngine
+};
+
+/// HMAC key used to derive the master key from a seed
+pub const BIP32_SEED_KEY: &[u8] = b"Bitcoin seed";
+
+/// Offset added to child indexes to mark hardened derivation
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// Derive the master secret key (left) and chain code (right) from a seed
+pub fn master_key_hash<S: AsRef<[u8]>>(seed: S) -> ([u8; 32], [u8; 32]) {
+    hmac_sha512(BIP32_SEED_KEY, seed).split()
+}
+
+/// Child key derivation hash, returning the key tweak (left) and child chain code (right).
+/// 
+/// `parent_data` is the parent's compressed public key for normal children, or a zero
+/// byte followed by the parent's secret key for hardened children.
+pub fn ckd_hash(chain_code: &[u8; 32], parent_data: &[u8; 33], index: u32) -> ([u8; 32], [u8; 32]) {
+    let mut hmac = HmacSha512::new_with_key(chain_code);
+    hmac.input(parent_data);
+    hmac.input(index.to_be_bytes());
+    hmac.finalise().split()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn bip32_vector_1() {
+        // https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki#test-vector-1
+        let seed: Vec<u8> = (0u8..16).collect();
+        let (key, chain_code) = master_key_hash(&seed);
+        assert_eq!(key, hex("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"));
+        assert_eq!(chain_code, hex("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"));
+
+        // m/0H
+        let mut data = [0u8; 33];
+        data[1..].copy_from_slice(&key);
+        let (tweak, chain_code) = ckd_hash(&chain_code, &data, HARDENED);
+        assert_eq!(tweak, hex("04bfb2dd60fa8921c2a4085ec15507a921f49cdc839f27f0f280e9c1495d44b5"));
+        assert_eq!(chain_code, hex("47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141"));
+    }
+}
@@ -0,0 +1,100 @@
+// BIP38 module
+//
+// Scrypt derivations used to encrypt private keys with a passphrase. Passphrases
+// must already be NFC normalised UTF-8. The AES encryption of the key halves and
+// the elliptic curve steps are left to the caller.
+
+use crate::{
+    HashEngine,
+    Sha256,
+    scrypt
+};
+
+/// Scrypt cost used for passphrase derivations
+pub const BIP38_N: u64 = 16384;
+/// Scrypt block size used for passphrase derivations
+pub const BIP38_R: u32 = 8;
+/// Scrypt parallelism used for passphrase derivations
+pub const BIP38_P: u32 = 8;
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::new().chain(data).finalise().sha256d().0
+}
+
+/// Derive with scrypt, the parameters here are all valid so this cannot fail
+fn derive(password: &[u8], salt: &[u8], n: u64, r: u32, p: u32, out: &mut [u8]) {
+    scrypt(password, salt, n, r, p, out).expect("valid scrypt parameters")
+}
+
+/// First four bytes of the sha256d of the address string, used as the salt
+pub fn bip38_address_hash(address: &str) -> [u8; 4] {
+    let mut hash = [0u8; 4];
+    hash.copy_from_slice(&sha256d(address.as_bytes())[..4]);
+    hash
+}
+
+/// Derive the 64 bytes used to encrypt a key without EC multiplication.
+/// 
+/// The left half is XORed with the private key and the right half is the AES key.
+pub fn bip38_derive_key<P: AsRef<[u8]>>(passphrase: P, address_hash: &[u8; 4]) -> [u8; 64] {
+    let mut derived = [0u8; 64];
+    derive(passphrase.as_ref(), address_hash, BIP38_N, BIP38_R, BIP38_P, &mut derived);
+    derived
+}
+
+/// First step of the EC multiply mode: the passfactor whose public point is the passpoint.
+/// 
+/// With lot and sequence numbers, only the first four bytes of the owner entropy are the salt
+/// and the result is hashed again with the whole owner entropy.
+pub fn bip38_passfactor<P: AsRef<[u8]>>(passphrase: P, owner_entropy: &[u8; 8], lot_sequence: bool) -> [u8; 32] {
+    let mut prefactor = [0u8; 32];
+    if !lot_sequence {
+        derive(passphrase.as_ref(), owner_entropy, BIP38_N, BIP38_R, BIP38_P, &mut prefactor);
+        return prefactor;
+    }
+
+    derive(passphrase.as_ref(), &owner_entropy[..4], BIP38_N, BIP38_R, BIP38_P, &mut prefactor);
+    let mut data = [0u8; 40];
+    data[..32].copy_from_slice(&prefactor);
+    data[32..].copy_from_slice(owner_entropy);
+    sha256d(&data)
+}
+
+/// Second step of the EC multiply mode: derive the 64 bytes used to encrypt seedb from the passpoint
+pub fn bip38_derive_ec_key(passpoint: &[u8; 33], address_hash: &[u8; 4], owner_entropy: &[u8; 8]) -> [u8; 64] {
+    let mut salt = [0u8; 12];
+    salt[..4].copy_from_slice(address_hash);
+    salt[4..].copy_from_slice(owner_entropy);
+
+    let mut derived = [0u8; 64];
+    derive(passpoint, &salt, 1024, 1, 1, &mut derived);
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn no_ec_multiply() {
+        // https://github.com/bitcoin/bips/blob/master/bip-0038.mediawiki#no-compression-no-ec-multiply
+        let address_hash = bip38_address_hash("1Jq6MksXQVWzrznvZzxkV6oY57oWXD9TXB");
+        assert_eq!(address_hash, [0xe9, 0x57, 0xa2, 0x4a]);
+
+        let derived = bip38_derive_key("TestingOneTwoThree", &address_hash);
+        assert_eq!(derived, hex("f87648a6b42fdd86ef6837a249cde15318f264d43a859b610e78ea63d51cb2d3e60bf44bfb29d543bba24afcccfadbfc6ef9312fcccf589fa5ea1366ec21e4c0"));
+    }
+
+    #[test]
+    fn ec_multiply() {
+        // https://github.com/bitcoin/bips/blob/master/bip-0038.mediawiki#ec-multiply-no-compression-no-lotsequence-numbers
+        let owner_entropy = [0xa5, 0x0d, 0xba, 0x67, 0x72, 0xcb, 0x93, 0x83];
+        let passfactor = bip38_passfactor("TestingOneTwoThree", &owner_entropy, false);
+        assert_eq!(passfactor, hex("c8ff7a1c8c8898a0361e477fa8f0f05c00d07c5d9626f00b03c0140a307c98f4"));
+
+        let passpoint = hex("020eac136e97ce6bf3e2bceb65d906742f7317b6518c54c64353c43dcc36688c47");
+        let derived = bip38_derive_ec_key(&passpoint, &[0x62, 0xb5, 0xb7, 0x22], &owner_entropy);
+        assert_eq!(derived, hex("da2d320e2ca088575369601e94dd71f210fc69c047a3d0f48bdbaab595916dc7b8d083ea2678b5a71558c0fb0efa58b565227d05adf0c25fa0b9a74755477827"));
+    }
+}
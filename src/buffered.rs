@@ -0,0 +1,114 @@
+// Buffered hasher module
+//
+// Hashing a serialization field by field makes many inputs of a few bytes each,
+// and every call goes through the engine's generic input loop. BufHasher gathers
+// small inputs into a block sized buffer and passes them on a block at a time.
+
+use crate::HashEngine;
+
+const MAX_BUFFER: usize = 136; // Largest blocksize of the supported hash engines
+
+/// Wrapper that coalesces small inputs into block sized writes to the engine
+#[derive(Clone)]
+pub struct BufHasher<H: HashEngine> {
+    engine: H,
+    buffer: [u8; MAX_BUFFER],
+    len: usize
+}
+
+impl<H: HashEngine> BufHasher<H> {
+    pub fn new(engine: H) -> Self {
+        Self {
+            engine,
+            buffer: [0; MAX_BUFFER],
+            len: 0
+        }
+    }
+
+    // Engines with larger blocks are buffered up to MAX_BUFFER bytes
+    fn capacity() -> usize {
+        H::BLOCKSIZE.min(MAX_BUFFER)
+    }
+
+    pub fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        let data = data.as_ref();
+        if self.len + data.len() < Self::capacity() {
+            self.buffer[self.len..self.len + data.len()].copy_from_slice(data);
+            self.len += data.len();
+            return
+        }
+
+        // Top up the buffer to a full block, then pass whole blocks straight through
+        let take = Self::capacity() - self.len;
+        self.buffer[self.len..Self::capacity()].copy_from_slice(&data[..take]);
+        self.engine.input(&self.buffer[..Self::capacity()]);
+        self.len = 0;
+
+        let rest = &data[take..];
+        let whole = rest.len() - rest.len() % Self::capacity();
+        self.engine.input(&rest[..whole]);
+        self.buffer[..rest.len() - whole].copy_from_slice(&rest[whole..]);
+        self.len = rest.len() - whole;
+    }
+
+    /// Pass any buffered bytes on to the engine
+    pub fn flush(&mut self) {
+        self.engine.input(&self.buffer[..self.len]);
+        self.len = 0;
+    }
+
+    /// Reference to the engine, which may not have seen the buffered bytes yet
+    pub fn get_ref(&self) -> &H {
+        &self.engine
+    }
+
+    /// Flush the buffer and return the engine
+    pub fn into_inner(mut self) -> H {
+        self.flush();
+        self.engine
+    }
+
+    pub fn finalise(self) -> H::Digest {
+        self.into_inner().finalise()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: HashEngine> std::io::Write for BufHasher<H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        BufHasher::flush(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Sha256, Sha512};
+
+    #[test]
+    fn buf_hasher() {
+        let data: Vec<u8> = (0..=255).collect();
+
+        let mut hasher = BufHasher::new(Sha256::new());
+        for chunk in data.chunks(3) {
+            hasher.input(chunk);
+        }
+        hasher.input(&data[..100]);
+        assert_eq!(hasher.get_ref().bytes_hashed(), 320);
+        hasher.flush();
+        assert_eq!(hasher.get_ref().bytes_hashed(), 356);
+        assert_eq!(hasher.finalise(), Sha256::new().chain(&data).chain(&data[..100]).finalise());
+
+        let mut hasher = BufHasher::new(Sha512::new());
+        hasher.input(&data[..5]);
+        hasher.input(&data[5..]);
+        assert_eq!(hasher.into_inner().finalise(), Sha512::new().chain(&data).finalise());
+    }
+}
@@ -0,0 +1,82 @@
+// Commitment module
+//
+// Salted commit/reveal commitments. The committer publishes H(salt || message) and
+// later reveals the message and salt. The random salt keeps small message spaces,
+// such as the sides of a coin, from being brute forced before the reveal.
+
+use crate::{
+    core::ct_eq,
+    HashEngine
+};
+
+/// Size of the salt in bytes
+pub const COMMITMENT_SALT_SIZE: usize = 32;
+
+/// A binding and hiding commitment to a message
+///
+/// The engine the commitment was made with is kept so that tagged or prefixed
+/// engines are verified the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct Commitment<T: HashEngine + Clone> {
+    engine: T,
+    digest: T::Digest
+}
+
+/// Commit to a message with a random salt, which must be kept until the reveal
+pub fn commit<T, M>(engine: T, message: M, salt: &[u8; COMMITMENT_SALT_SIZE]) -> Commitment<T>
+where T: HashEngine + Clone, M: AsRef<[u8]> {
+    let digest = engine.clone().chain(salt).chain(message).finalise();
+    Commitment {
+        engine,
+        digest
+    }
+}
+
+/// Commit to a message with a salt drawn from the rng, returning the salt to reveal later
+#[cfg(feature = "rand")]
+pub fn commit_random<T, M, R>(engine: T, message: M, rng: &mut R) -> (Commitment<T>, [u8; COMMITMENT_SALT_SIZE])
+where T: HashEngine + Clone, M: AsRef<[u8]>, R: rand_core::RngCore + rand_core::CryptoRng {
+    let mut salt = [0u8; COMMITMENT_SALT_SIZE];
+    rng.fill_bytes(&mut salt);
+    (commit(engine, message, &salt), salt)
+}
+
+impl<T: HashEngine + Clone> Commitment<T> {
+    /// A commitment received from the committer, made with the given engine
+    pub fn from_digest(engine: T, digest: T::Digest) -> Self {
+        Self {
+            engine,
+            digest
+        }
+    }
+
+    /// The digest to publish
+    pub fn digest(&self) -> T::Digest {
+        self.digest
+    }
+
+    /// Check a revealed message and salt against the commitment in constant time
+    pub fn verify<M>(&self, message: M, salt: &[u8; COMMITMENT_SALT_SIZE]) -> bool
+    where M: AsRef<[u8]> {
+        let digest = self.engine.clone().chain(salt).chain(message).finalise();
+        ct_eq(digest.as_ref(), self.digest.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    #[test]
+    fn commit_reveal() {
+        let salt = [0x11; COMMITMENT_SALT_SIZE];
+        let commitment = commit(Sha256::new(), b"heads", &salt);
+        assert_eq!(commitment.digest().to_string(), "19b981867ef96190288c4cd0316385db403ca4ffaf34aa2cd1e71df69e3c9686");
+
+        let received = Commitment::from_digest(Sha256::new(), commitment.digest());
+        assert!(received.verify(b"heads", &salt));
+        assert!(!received.verify(b"tails", &salt));
+        assert!(!received.verify(b"heads", &[0x12; COMMITMENT_SALT_SIZE]));
+    }
+}
@@ -0,0 +1,401 @@
+// bitcoin_hashes compatibility module
+//
+// Mirrors the surface of the bitcoin_hashes crate, such as sha256::Hash::hash(),
+// engine()/from_engine() and sha256t_hash_newtype!, on top of this crate's engines.
+// Projects can switch by replacing `bitcoin_hashes` with `btc_hashes::compat` in
+// their imports and compare the two backends' results.
+
+use ::core::fmt;
+use crate::error::HexError;
+
+/// A hash engine, as in bitcoin_hashes
+///
+/// Implemented for every engine of this crate.
+pub trait HashEngine: Clone + Default {
+    /// The midstate of the engine
+    type MidState;
+
+    /// Length of the blocks the engine processes
+    const BLOCK_SIZE: usize;
+
+    /// Add data to the engine
+    fn input(&mut self, data: &[u8]);
+
+    /// Amount of bytes input so far
+    fn n_bytes_hashed(&self) -> usize;
+
+    /// The midstate after the last whole block
+    fn midstate(&self) -> Self::MidState;
+}
+
+impl<T: crate::HashEngine + Clone> HashEngine for T {
+    type MidState = T::Midstate;
+    const BLOCK_SIZE: usize = T::BLOCKSIZE;
+
+    fn input(&mut self, data: &[u8]) {
+        crate::HashEngine::input(self, data);
+    }
+
+    fn n_bytes_hashed(&self) -> usize {
+        crate::HashEngine::bytes_hashed(self) as usize
+    }
+
+    fn midstate(&self) -> Self::MidState {
+        crate::HashEngine::midstate(self)
+    }
+}
+
+/// A hash digest type, as in bitcoin_hashes
+pub trait Hash:
+    Copy + Clone + PartialEq + Eq + PartialOrd + Ord + ::core::hash::Hash +
+    fmt::Debug + fmt::Display + fmt::LowerHex + ::core::str::FromStr + AsRef<[u8]>
+{
+    /// The engine that computes the hash
+    type Engine: HashEngine;
+
+    /// The digest as a byte array
+    type Bytes: Copy;
+
+    /// Length of the digest in bytes
+    const LEN: usize;
+
+    /// Whether the digest is displayed with its bytes reversed, like txids
+    const DISPLAY_BACKWARD: bool = false;
+
+    /// A fresh engine
+    fn engine() -> Self::Engine {
+        Self::Engine::default()
+    }
+
+    /// Finalise an engine into a digest
+    fn from_engine(e: Self::Engine) -> Self;
+
+    /// Hash data in one call
+    fn hash(data: &[u8]) -> Self {
+        let mut engine = Self::engine();
+        engine.input(data);
+        Self::from_engine(engine)
+    }
+
+    /// Copy a digest out of a slice of exactly LEN bytes
+    fn from_slice(sl: &[u8]) -> Result<Self, FromSliceError>;
+
+    fn to_byte_array(self) -> Self::Bytes;
+
+    fn as_byte_array(&self) -> &Self::Bytes;
+
+    fn from_byte_array(bytes: Self::Bytes) -> Self;
+
+    /// The all zeros digest, as used for the coinbase prevout
+    fn all_zeros() -> Self;
+}
+
+/// A slice of the wrong length was passed to Hash::from_slice()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FromSliceError {
+    pub expected: usize,
+    pub got: usize
+}
+
+impl fmt::Display for FromSliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} bytes, got {}", self.expected, self.got)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromSliceError { }
+
+/// Hex encode a digest in the given byte order
+#[doc(hidden)]
+pub fn fmt_hex(bytes: &[u8], backward: bool, f: &mut fmt::Formatter) -> fmt::Result {
+    if backward {
+        bytes.iter().rev().try_for_each(|byte| write!(f, "{:02x}", byte))
+    } else {
+        bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Parse a hex digest written in the given byte order
+#[doc(hidden)]
+pub fn parse_hex(s: &str, backward: bool, out: &mut [u8]) -> Result<(), HexError> {
+    crate::core::decode_hex(s, out)?;
+    if backward {
+        out.reverse();
+    }
+    Ok(())
+}
+
+/// Define a bitcoin_hashes style digest type computed by an engine of this crate
+#[doc(hidden)]
+#[macro_export]
+macro_rules! compat_hash_type {
+    ($(#[$attr: meta])* $vis: vis struct $name: ident($engine: ty, $len: expr, $backward: expr);) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name([u8; $len]);
+
+        impl $name {
+            /// A fresh engine
+            pub fn engine() -> $engine {
+                <$engine>::default()
+            }
+
+            /// Finalise an engine into a digest
+            pub fn from_engine(e: $engine) -> Self {
+                <Self as $crate::compat::Hash>::from_engine(e)
+            }
+
+            /// Hash data in one call
+            #[allow(clippy::self_named_constructors)]
+            pub fn hash(data: &[u8]) -> Self {
+                <Self as $crate::compat::Hash>::hash(data)
+            }
+
+            pub fn to_byte_array(self) -> [u8; $len] {
+                self.0
+            }
+
+            pub fn as_byte_array(&self) -> &[u8; $len] {
+                &self.0
+            }
+
+            pub fn from_byte_array(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl $crate::compat::Hash for $name {
+            type Engine = $engine;
+            type Bytes = [u8; $len];
+            const LEN: usize = $len;
+            const DISPLAY_BACKWARD: bool = $backward;
+
+            fn from_engine(mut e: $engine) -> Self {
+                let digest = $crate::HashEngine::finalise(&mut e);
+                let mut bytes = [0u8; $len];
+                bytes.copy_from_slice(digest.as_ref());
+                Self(bytes)
+            }
+
+            fn from_slice(sl: &[u8]) -> Result<Self, $crate::compat::FromSliceError> {
+                let mut bytes = [0u8; $len];
+                if sl.len() != $len {
+                    return Err($crate::compat::FromSliceError { expected: $len, got: sl.len() });
+                }
+                bytes.copy_from_slice(sl);
+                Ok(Self(bytes))
+            }
+
+            fn to_byte_array(self) -> [u8; $len] {
+                self.0
+            }
+
+            fn as_byte_array(&self) -> &[u8; $len] {
+                &self.0
+            }
+
+            fn from_byte_array(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+
+            fn all_zeros() -> Self {
+                Self([0; $len])
+            }
+        }
+
+        impl ::core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                $crate::compat::fmt_hex(&self.0, $backward, f)
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::LowerHex::fmt(self, f)
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::LowerHex::fmt(self, f)
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::HexError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut bytes = [0u8; $len];
+                $crate::compat::parse_hex(s, $backward, &mut bytes)?;
+                Ok(Self(bytes))
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl ::core::borrow::Borrow<[u8]> for $name {
+            fn borrow(&self) -> &[u8] {
+                &self.0
+            }
+        }
+    };
+}
+
+/// Declare a tagged hash type and its tag, as in bitcoin_hashes.
+///
+/// The digest is displayed forwards unless `#[hash_newtype(backward)]` is given.
+///
+/// ```
+/// use btc_hashes::sha256t_hash_newtype;
+///
+/// sha256t_hash_newtype! {
+///     pub struct TapLeafTag = hash_str("TapLeaf");
+///
+///     /// Taproot leaf hash
+///     pub struct TapLeafHash(_);
+/// }
+///
+/// let leaf = TapLeafHash::hash(&[0xc0, 0x01, 0x51]);
+/// assert_eq!(leaf.to_string(), "a85b2107f791b26a84e7586c28cec7cb61202ed3d01944d832500f363782d675");
+/// ```
+#[macro_export]
+macro_rules! sha256t_hash_newtype {
+    (
+        $(#[$tag_attr: meta])* $tag_vis: vis struct $tag: ident = hash_str($tag_str: expr);
+        #[hash_newtype(backward)]
+        $(#[$attr: meta])* $vis: vis struct $name: ident(_);
+    ) => {
+        $crate::sha256t_hash_newtype!(@define $(#[$tag_attr])* $tag_vis $tag $tag_str, $(#[$attr])* $vis $name, true);
+    };
+    (
+        $(#[$tag_attr: meta])* $tag_vis: vis struct $tag: ident = hash_str($tag_str: expr);
+        #[hash_newtype(forward)]
+        $(#[$attr: meta])* $vis: vis struct $name: ident(_);
+    ) => {
+        $crate::sha256t_hash_newtype!(@define $(#[$tag_attr])* $tag_vis $tag $tag_str, $(#[$attr])* $vis $name, false);
+    };
+    (
+        $(#[$tag_attr: meta])* $tag_vis: vis struct $tag: ident = hash_str($tag_str: expr);
+        $(#[$attr: meta])* $vis: vis struct $name: ident(_);
+    ) => {
+        $crate::sha256t_hash_newtype!(@define $(#[$tag_attr])* $tag_vis $tag $tag_str, $(#[$attr])* $vis $name, false);
+    };
+    (@define $(#[$tag_attr: meta])* $tag_vis: vis $tag: ident $tag_str: expr, $(#[$attr: meta])* $vis: vis $name: ident, $backward: expr) => {
+        $crate::hash_tag! {
+            $(#[$tag_attr])*
+            $tag_vis struct $tag = $tag_str;
+        }
+
+        $crate::compat_hash_type! {
+            $(#[$attr])*
+            $vis struct $name($crate::TaggedHash<$tag>, 32, $backward);
+        }
+    };
+}
+
+/// SHA1
+pub mod sha1 {
+    pub type HashEngine = crate::Sha1;
+
+    compat_hash_type! {
+        /// A SHA1 digest
+        pub struct Hash(crate::Sha1, 20, false);
+    }
+}
+
+/// SHA256
+pub mod sha256 {
+    pub type HashEngine = crate::Sha256;
+
+    compat_hash_type! {
+        /// A SHA256 digest
+        pub struct Hash(crate::Sha256, 32, false);
+    }
+}
+
+/// SHA256 applied twice, displayed backwards like txids
+pub mod sha256d {
+    pub type HashEngine = crate::Sha256d;
+
+    compat_hash_type! {
+        /// A double SHA256 digest
+        pub struct Hash(crate::Sha256d, 32, true);
+    }
+}
+
+/// SHA384
+pub mod sha384 {
+    pub type HashEngine = crate::Sha384;
+
+    compat_hash_type! {
+        /// A SHA384 digest
+        pub struct Hash(crate::Sha384, 48, false);
+    }
+}
+
+/// SHA512
+pub mod sha512 {
+    pub type HashEngine = crate::Sha512;
+
+    compat_hash_type! {
+        /// A SHA512 digest
+        pub struct Hash(crate::Sha512, 64, false);
+    }
+}
+
+/// RIPEMD160
+pub mod ripemd160 {
+    pub type HashEngine = crate::Ripemd160;
+
+    compat_hash_type! {
+        /// A RIPEMD160 digest
+        pub struct Hash(crate::Ripemd160, 20, false);
+    }
+}
+
+/// RIPEMD160 of SHA256
+pub mod hash160 {
+    pub type HashEngine = crate::Hash160;
+
+    compat_hash_type! {
+        /// A RIPEMD160 of SHA256 digest
+        pub struct Hash(crate::Hash160, 20, false);
+    }
+}
+
+/// Tagged SHA256, see sha256t_hash_newtype!
+pub mod sha256t {
+    pub use crate::Tag;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sha256, sha256d, hash160, Hash, HashEngine, FromSliceError};
+
+    #[test]
+    fn bitcoin_hashes_api() {
+        let digest = sha256::Hash::hash(b"abc");
+        assert_eq!(digest.to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(b"ab");
+        engine.input(b"c");
+        assert_eq!(engine.n_bytes_hashed(), 3);
+        assert_eq!(sha256::Hash::from_engine(engine), digest);
+        assert_eq!(sha256::Hash::from_slice(digest.as_ref()), Ok(digest));
+        assert_eq!(sha256::Hash::from_slice(&[0; 31]), Err(FromSliceError { expected: 32, got: 31 }));
+
+        let txid = sha256d::Hash::hash(b"abc");
+        assert_eq!(txid.to_string(), "58636c3ec08c12d55aedda056d602d5bcca72d8df6a69b519b72d32dc2428b4f");
+        assert_eq!(txid.to_string().parse::<sha256d::Hash>(), Ok(txid));
+        assert_eq!(txid.to_byte_array()[0], 0x4f);
+
+        assert_eq!(hash160::Hash::hash(b"abc").to_string(), "bb1be98c142444d7a56aa3981c3942a978e4dc33");
+        assert_eq!(<hash160::Hash as Hash>::all_zeros().as_byte_array(), &[0; 20]);
+    }
+}
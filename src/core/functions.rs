@@ -68,6 +68,15 @@ pub mod sha2 {
     }
 }
 
+pub mod sha1 {
+    pub use super::sha2::{choice, majority};
+
+    /// Parity
+    pub fn parity(x: u32, y: u32, z: u32) -> u32 {
+        x ^ y ^ z
+    }
+}
+
 pub mod ripemd160 {
     pub fn f(x: u32, y: u32, z: u32) -> u32 {
         x ^ y ^ z
@@ -88,4 +97,47 @@ pub mod ripemd160 {
     pub fn j(x: u32, y: u32, z: u32) -> u32 {
         x ^ (y | !z)
     }
-}
\ No newline at end of file
+}
+pub mod keccak {
+    use crate::constants::KECCAK_ROUND_CONSTANTS;
+
+    // Rotation offsets and lane order of the combined rho and pi steps
+    const RHO: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+    const PI: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+    /// The Keccak-f[1600] permutation over 25 lanes, where lane (x, y) is at index x + 5y
+    pub fn keccak_f1600(a: &mut [u64; 25]) {
+        for rc in KECCAK_ROUND_CONSTANTS.iter() {
+            // Theta
+            let mut c = [0u64; 5];
+            for x in 0..5 {
+                c[x] = a[x] ^ a[x+5] ^ a[x+10] ^ a[x+15] ^ a[x+20];
+            }
+            for x in 0..5 {
+                let d = c[(x+4)%5] ^ c[(x+1)%5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5*y] ^= d;
+                }
+            }
+
+            // Rho and pi
+            let mut last = a[1];
+            for (rho, pi) in RHO.iter().zip(PI.iter()) {
+                let next = a[*pi];
+                a[*pi] = last.rotate_left(*rho);
+                last = next;
+            }
+
+            // Chi
+            for y in 0..5 {
+                let row = [a[5*y], a[5*y+1], a[5*y+2], a[5*y+3], a[5*y+4]];
+                for x in 0..5 {
+                    a[x + 5*y] = row[x] ^ (!row[(x+1)%5] & row[(x+2)%5]);
+                }
+            }
+
+            // Iota
+            a[0] ^= rc;
+        }
+    }
+}
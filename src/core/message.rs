@@ -1,31 +1,19 @@
 // The message module
 //
-// Sha2 hash functions operate on messages and message blocks.
-// Messages are created by padding the intput data and message
-// blocks are created by splitting the message into 512 or 1024
-// bit blocks.
-
-
-use crate::core::{
-    functions::sha2::SigmaFunctions,
-    Primitive
+// Sha2 hash functions operate on message blocks of 512 or 1024 bits.
+// Blocks are borrowed from the input data or the engine buffer when they are
+// processed. The final blocks of a message are created by padding the remaining
+// input data on the stack, so no part of the hashing path allocates.
+
+
+use crate::{
+    core::{
+        functions::sha2::SigmaFunctions,
+        Primitive
+    },
+    error::Error
 };
-
-
-/// Message struct
-///
-/// The message is the original data followed by padding.
-/// Generics:
-///     N: The length of the message mod N should be zero.
-#[derive(Debug)]
-pub struct Message<const N: usize>(pub Vec<u8>);
-
-impl<const N: usize> Message<N> {
-    pub fn new(message: Vec<u8>) -> Message<N> {
-        assert_eq!(message.len()%N,  0);
-        Message(message)
-    }
-}
+use ::core::convert::TryFrom;
 
 
 /// Message block struct.
@@ -37,24 +25,43 @@ impl<const N: usize> Message<N> {
 pub struct MessageBlock<const N: usize>(pub [u8; N]);
 
 impl<const N: usize> MessageBlock<N> {
-    /// Create message blocks from a message.
-    /// Message blocks are split into N byte chunks where N is either 64 or 128
-    /// depending on the hash function being used.
-    pub fn from_message(message: Message<N>) -> Vec<MessageBlock<N>> {
-        message.0
-            .chunks(N)
-            .into_iter()
-            .map(|chunk| MessageBlock::from(chunk))
-            .collect()
+    /// Pad the final data of a message into one or two blocks on the stack.
+    /// 
+    /// The data is followed by a single '1' bit, zeroes and then the encoded message
+    /// length at the end of the last block. Returns the blocks and how many are used.
+    pub fn pad(data: &[u8], length: &[u8]) -> ([MessageBlock<N>; 2], usize) {
+        Self::pad_bits(data, 0, 0, length)
+    }
+
+    /// Pad the final data of a message that ends with the top `bits` bits of a partial byte.
+    /// 
+    /// The '1' bit follows straight after the partial byte's bits instead of a whole byte.
+    pub fn pad_bits(data: &[u8], partial: u8, bits: u8, length: &[u8]) -> ([MessageBlock<N>; 2], usize) {
+        assert!(data.len() < N && bits < 8);
+
+        let mut blocks = [MessageBlock([0u8; N]); 2];
+        blocks[0].0[..data.len()].copy_from_slice(data);
+        blocks[0].0[data.len()] = (partial & !(0xff >> bits)) | (0x80 >> bits);   // append single '1' bit
+
+        // The length goes in a second block if it does not fit after the '1' bit
+        let count = if data.len() + 1 + length.len() <= N { 1 } else { 2 };
+        blocks[count-1].0[N-length.len()..].copy_from_slice(length);
+
+        (blocks, count)
     }
 }
 
-impl<const N: usize> From<&[u8]> for MessageBlock<N> {
-    fn from(slice: &[u8]) -> MessageBlock<N> {
+impl<const N: usize> TryFrom<&[u8]> for MessageBlock<N> {
+    type Error = Error;
+
+    fn try_from(slice: &[u8]) -> Result<MessageBlock<N>, Self::Error> {
+        if slice.len() != N {
+            return Err(Error::InvalidBlockLength);
+        }
         let mut a = [0u8; N];
         a.copy_from_slice(slice);
 
-        MessageBlock(a)
+        Ok(MessageBlock(a))
     }
 }
 
@@ -69,19 +76,15 @@ impl<const N: usize> From<&[u8]> for MessageBlock<N> {
 pub struct MessageSchedule<T: Primitive, const N: usize>(pub [Word<T>; N]);
 
 // 32 bit message schedule
-impl<const N: usize, const W: usize> From<MessageBlock<N>> for MessageSchedule<u32, W> {
-    fn from(block: MessageBlock<N>) -> MessageSchedule<u32, W> {
+impl<const N: usize, const W: usize> From<&[u8; N]> for MessageSchedule<u32, W> {
+    fn from(block: &[u8; N]) -> MessageSchedule<u32, W> {
         // Create the initial 16 words from the message block
-        let mut words: Vec<Word<u32>> = block.0
-            .chunks(4)
-            .into_iter()
-            .map(|chnk_slc| { //Words are big endian.
-                let mut chunk = [0u8; 4];
-                chunk.copy_from_slice(chnk_slc);
-                chunk.reverse();
-                Word::new(unsafe { std::mem::transmute(chunk) })
-            })
-            .collect();
+        let mut words = [Word::new(0u32); W];
+        for (word, chnk_slc) in words.iter_mut().zip(block.chunks(4)) { //Words are big endian, whatever the target's endianness.
+            let mut chunk = [0u8; 4];
+            chunk.copy_from_slice(chnk_slc);
+            *word = Word::new(u32::from_be_bytes(chunk));
+        }
 
         // Extend the intial schedule to 64 words
         // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
@@ -96,31 +99,24 @@ impl<const N: usize, const W: usize> From<MessageBlock<N>> for MessageSchedule<u
                 ) % 2u64.pow(32)
             ) as u32;
             
-            words.push(Word::new(value));
+            words[i] = Word::new(value);
         }
 
-        assert_eq!(words.len(), W);
-        let mut schedule_words = [Word::new(0); W];
-        schedule_words.copy_from_slice(&words);
-        MessageSchedule(schedule_words)
+        MessageSchedule(words)
     }
 }
 
 
 // 64 bit message schedule
-impl<const N: usize, const W: usize> From<MessageBlock<N>> for MessageSchedule<u64, W> {
-    fn from(block: MessageBlock<N>) -> MessageSchedule<u64, W> {
+impl<const N: usize, const W: usize> From<&[u8; N]> for MessageSchedule<u64, W> {
+    fn from(block: &[u8; N]) -> MessageSchedule<u64, W> {
         // Create the initial 16 words from the message block
-        let mut words: Vec<Word<u64>> = block.0
-            .chunks(8)
-            .into_iter()
-            .map(|chnk_slc| {
-                let mut chunk = [0u8; 8];
-                chunk.copy_from_slice(chnk_slc);
-                chunk.reverse();
-                Word::new(unsafe { std::mem::transmute(chunk) })
-            })
-            .collect();
+        let mut words = [Word::new(0u64); W];
+        for (word, chnk_slc) in words.iter_mut().zip(block.chunks(8)) {
+            let mut chunk = [0u8; 8];
+            chunk.copy_from_slice(chnk_slc);
+            *word = Word::new(u64::from_be_bytes(chunk));
+        }
 
         // Extend the intial schedule to 64 words
         // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16]
@@ -135,23 +131,25 @@ impl<const N: usize, const W: usize> From<MessageBlock<N>> for MessageSchedule<u
                 ) % 2u128.pow(64)
             ) as u64;
             
-            words.push(Word::new(value));
+            words[i] = Word::new(value);
         }
 
-        assert_eq!(words.len(), W);
-        let mut schedule_words = [Word::new(0); W];
-        schedule_words.copy_from_slice(&words);
-        MessageSchedule(schedule_words)
+        MessageSchedule(words)
     }
 }
 
-impl<const N: usize> MessageSchedule<u32, N> {
-    /// Reverse the endian ness for each word in the schedule.
+impl<const W: usize> MessageSchedule<u32, W> {
+    /// Read the words of a block little endian, without extending the schedule.
     /// Used in RIPEMD160
-    pub fn reverse_words(&mut self) {        
-        for word in self.0.iter_mut() {
-            *word = Word::new(word.value.swap_bytes());
+    pub fn from_le<const N: usize>(block: &[u8; N]) -> Self {
+        let mut words = [Word::new(0u32); W];
+        for (word, chnk_slc) in words.iter_mut().zip(block.chunks(4)) {
+            let mut chunk = [0u8; 4];
+            chunk.copy_from_slice(chnk_slc);
+            *word = Word::new(u32::from_le_bytes(chunk));
         }
+
+        MessageSchedule(words)
     }
 }
 
@@ -168,4 +166,29 @@ impl<T: Primitive> Word<T> {
     pub fn new(value: T) -> Word<T> {
         Word { value }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_order() {
+        // Words are read with an explicit byte order, so these hold on big endian targets too
+        let mut block = [0u8; 128];
+        for (i, byte) in block.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let schedule = MessageSchedule::<u32, 16>::from(&block);
+        assert_eq!(schedule.0[0].value, 0x00010203);
+        assert_eq!(schedule.0[15].value, 0x3c3d3e3f);
+
+        let schedule = MessageSchedule::<u64, 16>::from(&block);
+        assert_eq!(schedule.0[1].value, 0x08090a0b0c0d0e0f);
+
+        let schedule = MessageSchedule::<u32, 16>::from_le(&block);
+        assert_eq!(schedule.0[0].value, 0x03020100);
+        assert_eq!(schedule.0[15].value, 0x3f3e3d3c);
+    }
+}
@@ -6,26 +6,216 @@
 
 pub mod message;
 pub mod functions;
-use std::ops::{
+#[cfg(feature = "serde")]
+pub mod serde_impl;
+use ::core::ops::{
     Add, Rem, BitXor, BitAnd, Not, Shr
 };
-use std::convert::TryFrom;
+use ::core::convert::TryInto;
+#[cfg(feature = "alloc")]
+use ::core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::io::{
+    self,
+    IoSlice,
+    Read
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use crate::error::{Error, HexError};
 
 
 pub trait HashEngine: Default {
+    /// Digests also convert to and from byte vectors with the alloc feature
+    #[cfg(feature = "alloc")]
     type Digest: Into<Vec<u8>> + IntoIterator<Item=u8> + TryFrom<Vec<u8>> + AsRef<[u8]> + Copy;
+    #[cfg(not(feature = "alloc"))]
+    type Digest: IntoIterator<Item=u8> + AsRef<[u8]> + Copy;
     type Midstate: Copy;
     const BLOCKSIZE: usize;
+    /// Length of the digest in bytes
+    const OUTPUT_SIZE: usize;
+
+    /// Length of the digest in bytes
+    fn output_size() -> usize {
+        Self::OUTPUT_SIZE
+    }
 
+    /// Input message data.
+    /// 
+    /// Panics if the message would become longer than the hash function can encode
+    /// in its padding, see try_input().
     fn input<I>(&mut self, data: I) where I: AsRef<[u8]>;
 
+    /// Input message data, failing instead of panicking if the message would become
+    /// longer than the hash function can encode in its padding.
+    fn try_input<I>(&mut self, data: I) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        self.input(data);
+        Ok(())
+    }
+
+    /// Input data and return the engine so hashes can be built in a single expression
+    fn chain<I>(mut self, data: I) -> Self
+    where I: AsRef<[u8]>, Self: Sized {
+        self.input(data);
+        self
+    }
+
+    /// Input data that is scattered across several buffers, as if they were concatenated
+    #[cfg(feature = "std")]
+    fn input_vectored(&mut self, bufs: &[IoSlice]) {
+        for buf in bufs {
+            self.input(&**buf);
+        }
+    }
+
+    /// Input a sequence of chunks, such as lazily encoded fields, without collecting them first
+    fn input_iter<I>(&mut self, chunks: I)
+    where I: IntoIterator, I::Item: AsRef<[u8]> {
+        for chunk in chunks {
+            self.input(chunk);
+        }
+    }
+
+    /// Input a u32 in little endian byte order
+    fn input_u32_le(&mut self, n: u32) {
+        self.input(n.to_le_bytes());
+    }
+
+    /// Input a u64 in little endian byte order
+    fn input_u64_le(&mut self, n: u64) {
+        self.input(n.to_le_bytes());
+    }
+
+    /// Input a compact size ("varint") as used by Bitcoin's consensus serialization
+    fn input_compact_size(&mut self, n: u64) {
+        match n {
+            0..=0xfc => self.input([n as u8]),
+            0xfd..=0xffff => {
+                self.input([0xfd]);
+                self.input((n as u16).to_le_bytes());
+            },
+            0x10000..=0xffffffff => {
+                self.input([0xfe]);
+                self.input((n as u32).to_le_bytes());
+            },
+            _ => {
+                self.input([0xff]);
+                self.input(n.to_le_bytes());
+            }
+        }
+    }
+
+    /// Input data prefixed with its length as a compact size, such as a script
+    fn input_slice_with_len(&mut self, data: &[u8]) {
+        self.input_compact_size(data.len() as u64);
+        self.input(data);
+    }
+
+    /// Input everything from a reader in fixed size chunks until EOF, returning the amount of bytes read
+    #[cfg(feature = "std")]
+    fn input_from_reader<R: Read>(&mut self, mut reader: R) -> io::Result<u64> {
+        let mut buffer = [0u8; 8192];
+        let mut total: u64 = 0;
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(total),
+                Ok(n) => {
+                    self.input(&buffer[..n]);
+                    total += n as u64;
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
     fn reset(&mut self);
 
     fn midstate(&self) -> Self::Midstate;
 
-    fn from_midstate(&mut self, midstate: Self::Midstate, length: usize);
+    /// Restore the engine from a midstate, including the amount of data it had processed.
+    /// 
+    /// Panics if the midstate cannot be restored, see try_from_midstate().
+    #[allow(clippy::wrong_self_convention)]
+    fn from_midstate(&mut self, midstate: Self::Midstate) {
+        if let Err(e) = self.try_from_midstate(midstate) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Restore the engine from a midstate, failing if its length is not block aligned
+    fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), Error>;
+
+    /// Data that has been input but not yet processed into the midstate
+    fn pending(&self) -> &[u8];
+
+    /// Amount of bytes input so far, including pending data.
+    /// 
+    /// Like midstate lengths, this includes any block an engine prefixes the message
+    /// with, such as the HMAC key or a tagged hash's tag.
+    fn bytes_hashed(&self) -> u64;
+
+    /// Whether all input has been processed, so the midstate captures the whole message
+    fn is_block_aligned(&self) -> bool {
+        self.pending().is_empty()
+    }
+
+    /// Restore the engine from a midstate and the pending data that followed it,
+    /// so a hash captured at any offset can be resumed.
+    /// 
+    /// Panics if the midstate cannot be restored, see try_from_midstate_with_buffer().
+    #[allow(clippy::wrong_self_convention)]
+    fn from_midstate_with_buffer(&mut self, midstate: Self::Midstate, pending: &[u8]) {
+        if let Err(e) = self.try_from_midstate_with_buffer(midstate, pending) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Restore the engine from a midstate and the pending data that followed it,
+    /// failing if the pending data is a block or longer.
+    fn try_from_midstate_with_buffer(&mut self, midstate: Self::Midstate, pending: &[u8]) -> Result<(), Error> {
+        if pending.len() >= Self::BLOCKSIZE {
+            return Err(Error::PendingTooLong);
+        }
+
+        self.try_from_midstate(midstate)?;
+        self.input(pending);
+        Ok(())
+    }
 
     fn finalise(&mut self) -> Self::Digest;
+
+    /// Wipe any secret data held by the engine, such as buffered message bytes or keyed state.
+    /// 
+    /// Wrapper engines call this on the engines they hold when they are reset or dropped.
+    /// Does nothing by default, the crate's own engines zeroize themselves when the zeroize
+    /// feature is enabled.
+    fn wipe(&mut self) { }
+
+    /// Compute the digest of the data input so far without changing the engine,
+    /// so more data can still be input afterwards.
+    fn finalise_copy(&self) -> Self::Digest
+    where Self: Clone {
+        self.clone().finalise()
+    }
+
+    /// Compute the digest and write it into the start of the output buffer.
+    /// 
+    /// Panics if the buffer is shorter than the digest.
+    fn finalise_into(&mut self, out: &mut [u8]) {
+        let digest = self.finalise();
+        let digest = digest.as_ref();
+        out[..digest.len()].copy_from_slice(digest);
+    }
+
+    /// Compute the digest and reset the engine to its initial state, ready for the next message
+    fn finalise_reset(&mut self) -> Self::Digest {
+        let digest = self.finalise();
+        self.reset();
+        digest
+    }
 }
 
 
@@ -35,7 +225,35 @@ pub trait KeyBasedHashEngine: HashEngine {
     where I: AsRef<[u8]>;
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Message authentication code trait
+/// 
+/// Kept separate from HashEngine so that MACs only need to expose keyed
+/// input and tag generation without midstate or blocksize semantics.
+pub trait Mac {
+    type Tag: AsRef<[u8]>;
+
+    /// Input message data
+    fn update<I>(&mut self, data: I) where I: AsRef<[u8]>;
+
+    /// Compute the authentication tag
+    fn finalise_tag(&mut self) -> Self::Tag;
+
+    /// Compute the authentication tag and compare it against the given tag in constant time
+    fn verify(&mut self, tag: &[u8]) -> bool {
+        ct_eq(self.finalise_tag().as_ref(), tag)
+    }
+}
+
+/// Key derivation function trait
+/// 
+/// Lets applications be generic over the KDF they use in the same way they
+/// are generic over HashEngine.
+pub trait Kdf {
+    /// Derive a key filling the entire output buffer
+    fn derive(&self, out: &mut [u8]) -> Result<(), Error>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct State<T: Copy, const N: usize> {
     registers: [T; N]
 }
@@ -58,6 +276,201 @@ impl<T: Copy, const N: usize> State<T, N> {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<T: Copy + zeroize::Zeroize, const N: usize> zeroize::Zeroize for State<T, N> {
+    fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.registers);
+    }
+}
+
+/// Midstate of a hash engine
+/// 
+/// The registers of the engine after processing some amount of whole blocks,
+/// along with the amount of bytes that were processed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Midstate<T: Copy, const N: usize> {
+    registers: [T; N],
+    length: u64
+}
+
+impl<T: Copy, const N: usize> Midstate<T, N> {
+    pub fn new(registers: [T; N], length: u64) -> Self {
+        Self {
+            registers,
+            length
+        }
+    }
+
+    /// The state registers
+    pub fn registers(&self) -> [T; N] {
+        self.registers
+    }
+
+    /// The amount of bytes processed to reach this midstate
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: Copy + zeroize::Zeroize, const N: usize> zeroize::Zeroize for Midstate<T, N> {
+    fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.registers);
+        zeroize::Zeroize::zeroize(&mut self.length);
+    }
+}
+
+/// Byte serialization of midstates.
+/// The registers are written big endian followed by the 8 byte big endian length.
+macro_rules! midstate_bytes {
+    ($word: ty) => {
+        impl<const N: usize> Midstate<$word, N> {
+            /// Serialize the registers and length
+            #[cfg(feature = "alloc")]
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes: Vec<u8> = self.registers
+                    .iter()
+                    .flat_map(|x| x.to_be_bytes())
+                    .collect();
+                bytes.extend(self.length.to_be_bytes());
+                bytes
+            }
+
+            /// Deserialize a midstate created with to_bytes()
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+                const WORD: usize = ::core::mem::size_of::<$word>();
+                if bytes.len() != N*WORD + 8 {
+                    return Err(Error::InvalidMidstate);
+                }
+
+                let mut registers = [0; N];
+                for (r, chunk) in registers.iter_mut().zip(bytes.chunks(WORD)) {
+                    *r = <$word>::from_be_bytes(chunk.try_into().unwrap());
+                }
+                let length = u64::from_be_bytes(bytes[N*WORD..].try_into().unwrap());
+
+                Ok(Self::new(registers, length))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<const N: usize> serde::Serialize for Midstate<$word, N> {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_bytes(&self.to_bytes())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, const N: usize> serde::Deserialize<'de> for Midstate<$word, N> {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                let bytes = <Vec<u8> as serde::Deserialize>::deserialize(d)?;
+                Self::from_bytes(&bytes).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+midstate_bytes!(u32);
+midstate_bytes!(u64);
+
+
+/// Input bytes from an iterator, gathering them into small chunks rather than
+/// inputting them one at a time.
+pub(crate) fn input_bytes<H, I>(engine: &mut H, bytes: I)
+where H: HashEngine, I: IntoIterator<Item=u8> {
+    let mut chunk = [0u8; 64];
+    let mut len = 0;
+    for byte in bytes {
+        chunk[len] = byte;
+        len += 1;
+        if len == chunk.len() {
+            engine.input(chunk);
+            len = 0;
+        }
+    }
+    engine.input(&chunk[..len]);
+}
+
+/// Overwrite a value that may hold secrets with writes the compiler cannot optimise away,
+/// for generic state that is not known to implement Zeroize.
+#[cfg(feature = "zeroize")]
+pub(crate) fn overwrite<T: Copy>(target: &mut T, value: T) {
+    // Safety: the target is a valid, aligned and exclusive reference and T has no drop glue
+    unsafe { ::core::ptr::write_volatile(target, value) };
+    ::core::sync::atomic::compiler_fence(::core::sync::atomic::Ordering::SeqCst);
+}
+
+/// Compare two byte slices in constant time.
+/// 
+/// Only the contents are kept secret, slices of different lengths return early.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter()
+        .zip(b)
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    ::core::hint::black_box(diff) == 0
+}
+
+/// Hex formatter that writes the bytes of a digest in reverse order,
+/// as Bitcoin does for txids and block hashes.
+pub struct DisplayBackwards<'a>(pub(crate) &'a [u8]);
+
+impl ::core::fmt::LowerHex for DisplayBackwards<'_> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        for byte in self.0.iter().rev() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl ::core::fmt::UpperHex for DisplayBackwards<'_> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        for byte in self.0.iter().rev() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl ::core::fmt::Display for DisplayBackwards<'_> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        ::core::fmt::LowerHex::fmt(self, f)
+    }
+}
+
+/// Decode a hex string into a fixed size buffer
+pub fn decode_hex(s: &str, out: &mut [u8]) -> Result<(), HexError> {
+    let chars = s.chars().count();
+    if chars != out.len()*2 {
+        return Err(HexError::InvalidLength { expected: out.len()*2, got: chars });
+    }
+
+    // Every byte before an invalid one is an ASCII hex digit, so the invalid byte
+    // starts a character within the first out.len()*2 bytes and can be reported whole
+    let nibble = |i: usize| match s.as_bytes()[i] {
+        c @ b'0'..=b'9' => Ok(c - b'0'),
+        c @ b'a'..=b'f' => Ok(c - b'a' + 10),
+        c @ b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(HexError::InvalidChar(s[i..].chars().next().expect("invalid byte starts a character")))
+    };
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = nibble(2*i)? << 4 | nibble(2*i + 1)?;
+    }
+
+    Ok(())
+}
+
+/// Decode a hex test vector of a known length
+#[cfg(test)]
+pub(crate) fn hex<const N: usize>(s: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    decode_hex(s, &mut bytes).expect("test vector is valid hex");
+    bytes
+}
+
 
 /// Primitive trait
 pub trait Primitive:
@@ -72,51 +485,268 @@ pub trait Primitive:
     Copy
 {
     fn rotr(&self, bits: usize) -> Self;
-
-    fn to_bytes(&self) -> Vec<u8>;
 }
 
 impl Primitive for u32 { 
     fn rotr(&self, bits: usize) -> Self {
         self.rotate_right(bits as u32)
     }
-
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
-    }
 }
 
 impl Primitive for u64{
     fn rotr(&self, bits: usize) -> Self {
         self.rotate_right(bits as u32)
     }
-
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
-    }
 }
 
 
 
-/// Macro to create a new struct
+/// Macro to create a digest newtype for an engine
+/// 
+/// Digests are formatted as hex with Display, LowerHex, UpperHex and Debug.
+macro_rules! digest_struct {
+    ($name: ident, $len: expr) => {
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub [u8; $len]);
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(digest: $name) -> [u8; $len] {
+                digest.0
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl From<$name> for alloc::vec::Vec<u8> {
+            fn from(digest: $name) -> alloc::vec::Vec<u8> {
+                digest.0.to_vec()
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl ::core::convert::TryFrom<alloc::vec::Vec<u8>> for $name {
+            type Error = alloc::vec::Vec<u8>;
+
+            fn try_from(bytes: alloc::vec::Vec<u8>) -> Result<Self, Self::Error> {
+                Ok(Self(<[u8; $len]>::try_from(bytes)?))
+            }
+        }
+
+        impl IntoIterator for $name {
+            type Item = u8;
+            type IntoIter = ::core::array::IntoIter<u8, $len>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                IntoIterator::into_iter(self.0)
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsRef<[u8; $len]> for $name {
+            fn as_ref(&self) -> &[u8; $len] {
+                &self.0
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = [u8; $len];
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                for byte in self.0.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl ::core::fmt::UpperHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                for byte in self.0.iter() {
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::LowerHex::fmt(self, f)
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::LowerHex::fmt(self, f)
+            }
+        }
+
+        impl $name {
+            /// The digest bytes
+            pub fn into_inner(self) -> [u8; $len] {
+                self.0
+            }
+
+            /// Parse a digest from a hex string in either case
+            pub fn from_hex(s: &str) -> Result<Self, crate::error::HexError> {
+                let mut bytes = [0; $len];
+                crate::core::decode_hex(s, &mut bytes)?;
+                Ok(Self(bytes))
+            }
+
+            /// Parse a digest from a hex string written in reverse byte order
+            pub fn from_hex_reversed(s: &str) -> Result<Self, crate::error::HexError> {
+                let mut digest = Self::from_hex(s)?;
+                digest.0.reverse();
+                Ok(digest)
+            }
+
+            /// Hash the digest with a fresh engine of another (or the same) hash function
+            pub fn hash_again<H: crate::HashEngine>(&self) -> H::Digest {
+                H::default().chain(self.0).finalise()
+            }
+
+            /// Format the digest with its bytes reversed, as used for txids and block hashes
+            pub fn display_backwards(&self) -> crate::core::DisplayBackwards<'_> {
+                crate::core::DisplayBackwards(&self.0)
+            }
+
+            /// Hex encode the digest with its bytes reversed
+            #[cfg(feature = "alloc")]
+            pub fn to_hex_reversed(&self) -> alloc::string::String {
+                alloc::string::ToString::to_string(&self.display_backwards())
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = crate::error::HexError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::from_hex(s)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        crate::core::serde_impl::impl_serde!($name);
+
+        #[cfg(feature = "zeroize")]
+        impl zeroize::Zeroize for $name {
+            fn zeroize(&mut self) {
+                zeroize::Zeroize::zeroize(&mut self.0);
+            }
+        }
+    };
+}
+
+/// Macro to create a new struct.
+/// 
+/// Engines generic over a compression backend are written as `Name<B: Backend>`,
+/// the backend is then held as a PhantomData and places no bounds on the engine.
 macro_rules! hash_struct {
-    ($name: ident, $block_size: expr, $length: ty, $state: ty, $state_len: expr) => {
-        #[derive(Clone, Copy, Debug)]
-        pub struct $name {
+    ($name: ident $(<$gen: ident: $bound: path>)?, $block_size: expr, $length: ty, $state: ty, $state_len: expr) => {
+        #[derive(Debug)]
+        pub struct $name $(<$gen: $bound>)? {
             buffer: [u8; $block_size],
             length: $length,                 // The length here is in bytes.
-            state: State<$state, $state_len>
+            state: State<$state, $state_len>,
+            iv: [$state; $state_len],        // Registers the engine starts from and resets to.
+            bits: u8,                        // Bits of a partial final byte after the length, see input_bits().
+            $(backend: ::core::marker::PhantomData<$gen>,)?
+        }
+
+        impl $(<$gen: $bound>)? Clone for $name $(<$gen>)? {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl $(<$gen: $bound>)? Copy for $name $(<$gen>)? { }
+
+        #[cfg(feature = "zeroize")]
+        impl $(<$gen: $bound>)? zeroize::Zeroize for $name $(<$gen>)? {
+            fn zeroize(&mut self) {
+                zeroize::Zeroize::zeroize(&mut self.buffer);
+                zeroize::Zeroize::zeroize(&mut self.length);
+                zeroize::Zeroize::zeroize(&mut self.state);
+                zeroize::Zeroize::zeroize(&mut self.bits);
+                // The IV is public and kept so the engine can still be reset
+            }
+        }
+
+        /// Engines are equal when they have processed the same state and hold the same
+        /// pending data, so they will produce the same digest from here on.
+        impl $(<$gen: $bound>)? PartialEq for $name $(<$gen>)? {
+            fn eq(&self, other: &Self) -> bool {
+                self.state == other.state &&
+                self.length == other.length &&
+                self.bits == other.bits &&
+                self.iv == other.iv &&
+                self.pending_bytes() == other.pending_bytes()
+            }
+        }
+
+        impl $(<$gen: $bound>)? Eq for $name $(<$gen>)? { }
+
+        impl $(<$gen: $bound>)? $name $(<$gen>)? {
+            /// Buffered bytes including a partial final byte, stale buffer contents are ignored
+            fn pending_bytes(&self) -> &[u8] {
+                let end = (self.length%$block_size as $length) as usize + (self.bits != 0) as usize;
+                &self.buffer[..end]
+            }
+
+            /// Create an engine that starts from, and resets to, custom initial registers
+            /// instead of the standard constants.
+            pub fn with_iv(iv: [$state; $state_len]) -> Self {
+                Self {
+                    buffer: [0; $block_size],
+                    length: 0,
+                    state: State::init(iv),
+                    iv,
+                    bits: 0,
+                    $(backend: ::core::marker::PhantomData::<$gen>,)?
+                }
+            }
+
+            /// The initial registers of the engine
+            pub fn iv(&self) -> [$state; $state_len] {
+                self.iv
+            }
         }
     };
 }
 
-/// Macro to implement functions that require initial constants.
+/// Macro to implement resetting an engine to its initial registers.
 macro_rules! iconst_funcs {
-    ($iconsts: expr, $block_size: expr) => {
+    ($block_size: expr) => {
+        /// Zeroize the buffered data and registers, leaving the engine reset to its IV
+        #[cfg(feature = "zeroize")]
+        fn wipe(&mut self) {
+            self.reset();
+        }
+
         fn reset(&mut self) {
+            #[cfg(feature = "zeroize")]
+            zeroize::Zeroize::zeroize(self);
+
             self.buffer = [0; $block_size];
             self.length = 0;
-            self.state = State::init($iconsts)
+            self.state = State::init(self.iv);
+            self.bits = 0;
         }
     };
 }
@@ -125,35 +755,102 @@ macro_rules! iconst_funcs {
 macro_rules! midstate_funcs {
     ($length_ty: ty) => {
         fn midstate(&self) -> Self::Midstate {
-            self.state.read() // extracting the entire state without omitting registers
+            // extracting the entire state without omitting registers.
+            // Data still in the buffer has not been processed into the state.
+            let processed = self.length - self.length%(Self::BLOCKSIZE as $length_ty);
+            Midstate::new(self.state.read(), processed as u64)
         }
     
-        fn from_midstate(&mut self, midstate: Self::Midstate, length: usize) {
-            // If the length mod blocksize is not zero, panic.
+        fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), crate::error::Error> {
+            // If the length mod blocksize is not zero, fail.
             // This is done because, the hasher has no way of knowing whether there was any
             // data in the hasher's buffer that is unaccounted for in the given state.
-            assert_eq!(length%Self::BLOCKSIZE, 0);
+            if midstate.length()%Self::BLOCKSIZE as u64 != 0 {
+                return Err(crate::error::Error::UnalignedMidstate);
+            }
+            if midstate.length() as $length_ty > <$length_ty>::MAX/8 {
+                return Err(crate::error::Error::MessageTooLong);
+            }
+
+            // A partial byte from input_bits() came after the replaced state, so it is dropped too
+            self.length = midstate.length() as $length_ty;
+            self.state.update(midstate.registers());
+            self.bits = 0;
+            Ok(())
+        }
+
+        fn pending(&self) -> &[u8] {
+            &self.buffer[..(self.length%Self::BLOCKSIZE as $length_ty) as usize]
+        }
 
-            self.length = length as $length_ty;
-            self.state.update(midstate);
-        } 
+        fn bytes_hashed(&self) -> u64 {
+            self.length as u64
+        }
     }
 }
 
+/// Macro to implement inputting messages that are not a whole number of bytes
+macro_rules! bit_input_func {
+    () => {
+        /// Input the first nbits bits of data, most significant bit first.
+        /// 
+        /// If nbits is not a multiple of 8 the trailing bits are held until finalisation,
+        /// so this must be the last input. The midstate and pending data do not include them.
+        /// 
+        /// Panics if data is shorter than nbits or a partial byte has already been input.
+        pub fn input_bits(&mut self, data: &[u8], nbits: usize) {
+            assert!(self.bits == 0, "no input may follow a partial byte");
+            assert!(nbits <= data.len()*8, "data is shorter than nbits");
+
+            let (bytes, bits) = (nbits/8, nbits%8);
+            self.input(&data[..bytes]);
+            if bits != 0 {
+                self.buffer[self.pending().len()] = data[bytes];
+                self.bits = bits as u8;
+            }
+        }
+    };
+}
+
 /// Macro to implement hash function data inputting
 macro_rules! input_func {
     (
         $length_ty: ty
     ) => {
+        fn try_input<I>(&mut self, data: I) -> Result<(), crate::error::Error>
+        where I: AsRef<[u8]> {
+            // The message length in bits is encoded in a counter of the same type as the byte length
+            if data.as_ref().len() as $length_ty > <$length_ty>::MAX/8 - self.length {
+                return Err(crate::error::Error::MessageTooLong);
+            }
+
+            self.input(data);
+            Ok(())
+        }
+
         fn input<I>(&mut self, data: I) //Code for this function was sourced from bitcoin-hashes crate and adapted to this library. Thanks :)
         where I: AsRef<[u8]> {
             let mut input = data.as_ref();
+            assert!(self.bits == 0 || input.is_empty(), "no input may follow a partial byte");
+            assert!(input.len() as $length_ty <= <$length_ty>::MAX/8 - self.length, "message is too long for the hash function");
             
             //while there is still data in the input slice...
             while input.len() != 0 {
-                let buffer_index = self.length as usize%Self::BLOCKSIZE;   // Get the current index of the buffer
+                let buffer_index = (self.length%Self::BLOCKSIZE as $length_ty) as usize;   // Get the current index of the buffer
+
+                // If the buffer is empty, compress whole blocks straight from the input and only buffer the remainder
+                if buffer_index == 0 && input.len() >= Self::BLOCKSIZE {
+                    let mut blocks = input.chunks_exact(Self::BLOCKSIZE);
+                    for block in &mut blocks {
+                        Self::process_block(&mut self.state, ::core::convert::TryInto::try_into(block).expect("chunk is block sized"));
+                    }
+                    self.length += (input.len() - blocks.remainder().len()) as $length_ty;
+                    input = blocks.remainder();
+                    continue;
+                }
+
                 let r = Self::BLOCKSIZE - buffer_index;                    // Get the remaining length of the buffer until BLOCKSIZE
-                let to_write = std::cmp::min(r, input.len());              // Get the length of the data to copy into the buffer (which ever is smaller, remaining length of the buffer or the remaining length of the input.)
+                let to_write = ::core::cmp::min(r, input.len());              // Get the length of the data to copy into the buffer (which ever is smaller, remaining length of the buffer or the remaining length of the input.)
 
                 // Insert the required amount of input data into the buffer
                 self.buffer[buffer_index..buffer_index+to_write].copy_from_slice(&input[..to_write]);
@@ -162,9 +859,7 @@ macro_rules! input_func {
                 // If the total length mod BLOCKSIZE is zero, that means we have enough new data in the buffer
                 // to process a block.   (if buffer_index+to_write == Self::BLOCKSIZE)
                 if self.length%(Self::BLOCKSIZE as $length_ty) == 0 {
-                    let blocks: Vec<MessageBlock<{Self::BLOCKSIZE}>> = MessageBlock::from_message(Message::new(self.buffer[..Self::BLOCKSIZE].to_vec()));
-                    assert_eq!(blocks.len(), 1);
-                    Self::process_block(&mut self.state, blocks[0]);
+                    Self::process_block(&mut self.state, &self.buffer);
                 }
                 input = &input[to_write..]; // Remove the data we placed into the buffer from the input
             }
@@ -173,21 +868,153 @@ macro_rules! input_func {
 }
 
 macro_rules! impl_default {
-    ($name: ident, $iconsts: expr, $block_size: expr) => {
-        impl Default for $name {
+    ($name: ident $(<$gen: ident: $bound: path>)?, $iconsts: expr, $block_size: expr) => {
+        impl $(<$gen: $bound>)? Default for $name $(<$gen>)? {
             fn default() -> Self {
-                Self {
-                    buffer: [0; $block_size],
-                    length: 0,
-                    state: State::init($iconsts)
+                Self::with_iv($iconsts)
+            }
+        }
+    };
+}
+
+/// Macro to implement std::io::Write by feeding written data into the engine
+macro_rules! impl_write {
+    ($name: ident $(<$gen: ident: $bound: path>)?) => {
+        #[cfg(feature = "std")]
+        impl $(<$gen: $bound>)? std::io::Write for $name $(<$gen>)? {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.input(buf);
+                Ok(buf.len())
+            }
+
+            fn write_vectored(&mut self, bufs: &[std::io::IoSlice]) -> std::io::Result<usize> {
+                self.input_vectored(bufs);
+                Ok(bufs.iter().map(|buf| buf.len()).sum())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Macro to implement serialization of the complete engine state
+macro_rules! impl_state_bytes {
+    ($name: ident $(<$gen: ident: $bound: path>)?, $length_ty: ty, $state_ty: ty, $state_len: expr) => {
+        impl_state_bytes!($name $(<$gen: $bound>)?, $length_ty as $length_ty, $state_ty, $state_len);
+    };
+    // The length is serialized as $encoded_ty, which may be wider than the engine's length
+    ($name: ident $(<$gen: ident: $bound: path>)?, $length_ty: ty as $encoded_ty: ty, $state_ty: ty, $state_len: expr) => {
+        impl $(<$gen: $bound>)? $name $(<$gen>)? {
+            /// Serialize the complete engine state so that hashing can be suspended and resumed later.
+            /// 
+            /// The state registers, the initial registers the engine resets to and the length are
+            /// written big endian, then the number of bits in a partial final byte from input_bits(),
+            /// followed by the data in the buffer that has not been processed yet including that byte.
+            #[cfg(feature = "alloc")]
+            pub fn to_state_bytes(&self) -> alloc::vec::Vec<u8> {
+                let mut bytes: alloc::vec::Vec<u8> = self.state.read()
+                    .iter()
+                    .chain(self.iv.iter())
+                    .flat_map(|x| x.to_be_bytes())
+                    .collect();
+                bytes.extend(<$encoded_ty>::from(self.length).to_be_bytes());
+                bytes.push(self.bits);
+                bytes.extend(self.pending_bytes());
+                bytes
+            }
+
+            /// Restore an engine from bytes created with to_state_bytes()
+            pub fn from_state_bytes(bytes: &[u8]) -> Result<Self, crate::error::Error> {
+                const WORD: usize = ::core::mem::size_of::<$state_ty>();
+                const REGISTERS: usize = WORD*$state_len*2;
+                const HEADER: usize = REGISTERS + ::core::mem::size_of::<$encoded_ty>() + 1;
+                if bytes.len() < HEADER {
+                    return Err(crate::error::Error::InvalidState);
+                }
+
+                let (mut registers, mut iv) = ([0; $state_len], [0; $state_len]);
+                for (r, chunk) in registers.iter_mut().chain(iv.iter_mut()).zip(bytes.chunks(WORD)) {
+                    *r = <$state_ty>::from_be_bytes(::core::convert::TryInto::try_into(chunk).unwrap());
+                }
+                let length = <$encoded_ty>::from_be_bytes(::core::convert::TryInto::try_into(&bytes[REGISTERS..HEADER-1]).unwrap());
+                let length = match <$length_ty as ::core::convert::TryFrom<$encoded_ty>>::try_from(length) {
+                    Ok(length) if length <= <$length_ty>::MAX/8 => length,
+                    _ => return Err(crate::error::Error::InvalidState)
+                };
+                let bits = bytes[HEADER-1];
+                let pending = &bytes[HEADER..];
+                if bits >= 8 || pending.len() as $length_ty != length%Self::BLOCKSIZE as $length_ty + (bits != 0) as $length_ty {
+                    return Err(crate::error::Error::InvalidState);
+                }
+
+                let mut engine = Self::with_iv(iv);
+                engine.state.update(registers);
+                engine.length = length;
+                engine.bits = bits;
+                engine.buffer[..pending.len()].copy_from_slice(pending);
+                Ok(engine)
+            }
+        }
+    };
+}
+
+/// Macro to implement Extend<u8> by feeding the bytes into the engine
+macro_rules! impl_extend {
+    ($name: ident $(<$gen: ident: $bound: path>)?) => {
+        impl $(<$gen: $bound>)? Extend<u8> for $name $(<$gen>)? {
+            fn extend<I: IntoIterator<Item=u8>>(&mut self, iter: I) {
+                crate::core::input_bytes(self, iter);
+            }
+        }
+    };
+}
+
+/// Macro to implement fmt::Write by feeding the UTF-8 bytes of formatted text into the engine
+macro_rules! impl_fmt_write {
+    ($name: ident $(<$gen: ident: $bound: path>)?) => {
+        impl $(<$gen: $bound>)? ::core::fmt::Write for $name $(<$gen>)? {
+            fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                self.input(s);
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Macro to implement hashing a batch of independent messages
+macro_rules! impl_hash_batch {
+    ($name: ident) => {
+        impl $name {
+            /// Hash a batch of independent messages, returning the digests in the same order.
+            /// 
+            /// With the rayon feature the messages are spread across the rayon thread pool.
+            #[cfg(feature = "alloc")]
+            pub fn hash_batch<I>(msgs: &[I]) -> alloc::vec::Vec<<Self as HashEngine>::Digest>
+            where I: AsRef<[u8]> + Sync {
+                #[cfg(feature = "rayon")]
+                {
+                    use rayon::prelude::*;
+                    msgs.par_iter().map(|msg| Self::new().chain(msg).finalise()).collect()
                 }
+
+                #[cfg(not(feature = "rayon"))]
+                msgs.iter().map(|msg| Self::new().chain(msg).finalise()).collect()
             }
         }
     };
 }
 
+pub(crate) use digest_struct;
 pub(crate) use hash_struct;
 pub(crate) use iconst_funcs;
 pub(crate) use midstate_funcs;
 pub(crate) use input_func;
-pub(crate) use impl_default;
\ No newline at end of file
+pub(crate) use bit_input_func;
+pub(crate) use impl_default;
+pub(crate) use impl_write;
+pub(crate) use impl_extend;
+pub(crate) use impl_fmt_write;
+pub(crate) use impl_hash_batch;
+pub(crate) use impl_state_bytes;
\ No newline at end of file
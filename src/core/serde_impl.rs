@@ -0,0 +1,61 @@
+// Serde module
+//
+// Serialization of digest types. Human readable formats get a hex string,
+// binary formats get the raw digest bytes.
+
+use ::core::{
+    convert::TryFrom,
+    fmt,
+    marker::PhantomData,
+    str::FromStr
+};
+use alloc::vec::Vec;
+use serde::de;
+
+
+/// Visitor that accepts a digest as either a hex string or raw bytes
+pub struct DigestVisitor<T>(pub PhantomData<T>);
+
+impl<'de, T> de::Visitor<'de> for DigestVisitor<T>
+where T: FromStr + TryFrom<Vec<u8>>, <T as FromStr>::Err: fmt::Display {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a hex encoded digest or its raw bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        T::try_from(v.to_vec()).map_err(|_| E::invalid_length(v.len(), &self))
+    }
+}
+
+/// Serde impls for types created by `digest_struct!`
+macro_rules! impl_serde {
+    ($name: ident) => {
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                if s.is_human_readable() {
+                    s.collect_str(self)
+                } else {
+                    s.serialize_bytes(&self.0)
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                let visitor = crate::core::serde_impl::DigestVisitor(::core::marker::PhantomData);
+                if d.is_human_readable() {
+                    d.deserialize_str(visitor)
+                } else {
+                    d.deserialize_bytes(visitor)
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_serde;
@@ -0,0 +1,196 @@
+// CPU module
+//
+// Detects the instruction set extensions used by the accelerated backends.
+// Detection runs once and is cached in an atomic, so choosing a backend for
+// each block is a single load. The portable path can be forced with
+// force_portable(), or by setting the BTC_HASHES_PORTABLE environment variable
+// before the first hash is computed. The variable is read once, on the first
+// check, and force_portable() takes precedence over it.
+
+use ::core::sync::atomic::{AtomicU8, Ordering};
+
+const DETECTED: u8 = 1 << 7;
+const SHA_NI: u8 = 1 << 0;
+const SSE41: u8 = 1 << 1;
+const AVX2: u8 = 1 << 2;
+const SHA2: u8 = 1 << 3;
+const SHA512: u8 = 1 << 4;
+
+// States of FORCE_PORTABLE, unset until the environment has been read or force_portable() is called
+const UNSET: u8 = 0;
+const ACCELERATED: u8 = 1;
+const PORTABLE: u8 = 2;
+
+static FEATURES: AtomicU8 = AtomicU8::new(0);
+static FORCE_PORTABLE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// CPU features used by the accelerated backends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Features {
+    /// x86 SHA extensions
+    pub sha_ni: bool,
+    /// x86 SSE4.1, used for four lane SHA256
+    pub sse41: bool,
+    /// x86 AVX2, used for the SHA512 schedule
+    pub avx2: bool,
+    /// ARMv8 SHA256 instructions
+    pub sha2: bool,
+    /// ARMv8.2 SHA512 instructions
+    pub sha512: bool
+}
+
+/// Features available to the backends.
+/// 
+/// Every feature is reported as missing while the portable path is forced.
+pub fn features() -> Features {
+    if portable_forced() {
+        return Features::default();
+    }
+
+    let mut bits = FEATURES.load(Ordering::Relaxed);
+    if bits & DETECTED == 0 {
+        bits = detect() | DETECTED;
+        FEATURES.store(bits, Ordering::Relaxed);
+    }
+
+    Features {
+        sha_ni: bits & SHA_NI != 0,
+        sse41: bits & SSE41 != 0,
+        avx2: bits & AVX2 != 0,
+        sha2: bits & SHA2 != 0,
+        sha512: bits & SHA512 != 0
+    }
+}
+
+/// Force every engine onto the portable compression, or return to using the best available backend
+pub fn force_portable(force: bool) {
+    FORCE_PORTABLE.store(if force { PORTABLE } else { ACCELERATED }, Ordering::Relaxed);
+}
+
+/// Check if the portable compression is being forced
+pub fn portable_forced() -> bool {
+    let mut mode = FORCE_PORTABLE.load(Ordering::Relaxed);
+    if mode == UNSET {
+        let env = if env_forces_portable() { PORTABLE } else { ACCELERATED };
+        // A concurrent force_portable() call wins over the environment
+        mode = match FORCE_PORTABLE.compare_exchange(UNSET, env, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => env,
+            Err(current) => current
+        };
+    }
+
+    mode == PORTABLE
+}
+
+#[cfg(feature = "std")]
+fn env_forces_portable() -> bool {
+    std::env::var_os("BTC_HASHES_PORTABLE").is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+#[cfg(not(feature = "std"))]
+fn env_forces_portable() -> bool {
+    false
+}
+
+#[cfg(feature = "std")]
+fn detect() -> u8 {
+    // Other targets, such as wasm32, have no runtime detection
+    #[cfg_attr(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")), allow(unused_mut))]
+    let mut bits = 0;
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let sse = is_x86_feature_detected!("sse2") && is_x86_feature_detected!("sse4.1");
+        if sse && is_x86_feature_detected!("ssse3") && is_x86_feature_detected!("sha") {
+            bits |= SHA_NI;
+        }
+        if sse {
+            bits |= SSE41;
+        }
+        if is_x86_feature_detected!("avx2") {
+            bits |= AVX2;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("sha2") {
+            bits |= SHA2;
+        }
+        if std::arch::is_aarch64_feature_detected!("sha3") {
+            bits |= SHA512;
+        }
+    }
+
+    bits
+}
+
+// Without std the features can only be known at compile time
+#[cfg(not(feature = "std"))]
+fn detect() -> u8 {
+    let mut bits = 0;
+    if cfg!(all(target_feature = "sha", target_feature = "sse2", target_feature = "ssse3", target_feature = "sse4.1")) {
+        bits |= SHA_NI;
+    }
+    if cfg!(all(target_feature = "sse2", target_feature = "sse4.1")) {
+        bits |= SSE41;
+    }
+    if cfg!(target_feature = "avx2") {
+        bits |= AVX2;
+    }
+    if cfg!(all(target_arch = "aarch64", target_feature = "sha2")) {
+        bits |= SHA2;
+    }
+    if cfg!(all(target_arch = "aarch64", target_feature = "sha3")) {
+        bits |= SHA512;
+    }
+
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use super::*;
+    use crate::{HashEngine, Portable, Sha256, Sha256With, Sha512, Sha512With};
+
+    #[test]
+    fn portable_backend() {
+        // Engines can pick the portable compression for themselves without the global override
+        let data = [0x61; 1000];
+        assert_eq!(Sha256With::<Portable>::new().chain(data).finalise(), Sha256::new().chain(data).finalise());
+        assert_eq!(Sha512With::<Portable>::new().chain(data).finalise(), Sha512::new().chain(data).finalise());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn force_portable_path() {
+        // The override is global, so it is tested in a process of its own where no other
+        // test can observe it, started with the environment variable set
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["cpu::tests::forced_in_child", "--exact", "--ignored", "--quiet"])
+            .env("BTC_HASHES_PORTABLE", "1")
+            .stdout(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[ignore = "run in its own process by force_portable_path"]
+    fn forced_in_child() {
+        if std::env::var_os("BTC_HASHES_PORTABLE").is_none() {
+            return
+        }
+
+        // The environment is honoured by the very first check
+        let data = [0x61; 1000];
+        assert!(portable_forced());
+        assert_eq!(features(), Features::default());
+        assert_eq!(Sha256::new().chain(data).finalise(), Sha256With::<Portable>::new().chain(data).finalise());
+        assert_eq!(Sha512::new().chain(data).finalise(), Sha512With::<Portable>::new().chain(data).finalise());
+
+        force_portable(false);
+        assert!(!portable_forced());
+        assert_eq!(Sha256::new().chain(data).finalise(), Sha256With::<Portable>::new().chain(data).finalise());
+    }
+}
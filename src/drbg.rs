@@ -0,0 +1,446 @@
+// DRBG Module
+//
+// Deterministic random bit generators built on top of the hash engines.
+//
+// HmacDrbg follows the HMAC_DRBG construction from NIST SP 800-90A which is also
+// the generator used by RFC 6979 for deterministic ECDSA/Schnorr nonces.
+// To derive an RFC 6979 nonce, instantiate with int2octets(x) || bits2octets(h1)
+// as the entropy input and call generate() until a valid candidate is produced.
+//
+// HashDrbg follows the Hash_DRBG construction from NIST SP 800-90A using one of
+// the SHA2 engines directly.
+//
+// Both enforce the SP 800-90A limits of 2^48 requests between reseeds and 2^19
+// bits per request. generate() panics once a limit is hit, try_generate() returns
+// the error instead.
+
+
+use crate::{
+    core::{
+        HashEngine,
+        KeyBasedHashEngine
+    },
+    error::Error,
+    hmac::Hmac,
+    Sha224, Sha256, Sha384, Sha512,
+    Sha224With, Sha256With, Sha384With, Sha512With,
+    Sha256Backend, Sha512Backend
+};
+use ::core::marker::PhantomData;
+use alloc::{
+    vec,
+    vec::Vec
+};
+
+/// Amount of generate requests allowed between reseeds (SP 800-90A, table 2)
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Maximum amount of bytes a single generate request may produce, 2^19 bits
+const MAX_REQUEST_BYTES: usize = (1 << 19) / 8;
+
+/// Check a generate request against the SP 800-90A limits
+fn check_request(reseed_counter: u64, len: usize) -> Result<(), Error> {
+    if reseed_counter > RESEED_INTERVAL {
+        return Err(Error::ReseedRequired);
+    }
+    if len > MAX_REQUEST_BYTES {
+        return Err(Error::RequestTooLong);
+    }
+    Ok(())
+}
+
+/// HMAC_DRBG (SP 800-90A, section 10.1.2)
+pub struct HmacDrbg<T: HashEngine+Copy> {
+    k: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    hash: PhantomData<T>
+}
+
+impl<T: HashEngine+Copy> HmacDrbg<T> {
+    /// Instantiate a new generator from entropy, a nonce and a personalization string.
+    pub fn new<E, N, P>(entropy: E, nonce: N, personalization: P) -> Self
+    where E: AsRef<[u8]>, N: AsRef<[u8]>, P: AsRef<[u8]> {
+        let mut drbg = Self {
+            k: vec![0x00; T::OUTPUT_SIZE],
+            v: vec![0x01; T::OUTPUT_SIZE],
+            reseed_counter: 1,
+            hash: PhantomData::<T>
+        };
+        drbg.update(&[entropy.as_ref(), nonce.as_ref(), personalization.as_ref()]);
+
+        drbg
+    }
+
+    /// Reseed the generator with fresh entropy and optional additional input.
+    pub fn reseed<E, A>(&mut self, entropy: E, additional: A)
+    where E: AsRef<[u8]>, A: AsRef<[u8]> {
+        self.update(&[entropy.as_ref(), additional.as_ref()]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fill the output buffer with pseudorandom bytes.
+    /// 
+    /// Panics if the generator must be reseeded or the buffer is too long, see try_generate().
+    pub fn generate(&mut self, out: &mut [u8]) {
+        self.generate_with_additional(out, []);
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, mixing in additional input.
+    /// 
+    /// Panics if the generator must be reseeded or the buffer is too long, see try_generate().
+    pub fn generate_with_additional<A>(&mut self, out: &mut [u8], additional: A)
+    where A: AsRef<[u8]> {
+        if let Err(e) = self.try_generate_with_additional(out, additional) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, failing if the generator must be
+    /// reseeded first or the buffer is longer than 2^19 bits.
+    pub fn try_generate(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        self.try_generate_with_additional(out, [])
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, mixing in additional input,
+    /// failing if the generator must be reseeded first or the buffer is longer than 2^19 bits.
+    pub fn try_generate_with_additional<A>(&mut self, out: &mut [u8], additional: A) -> Result<(), Error>
+    where A: AsRef<[u8]> {
+        check_request(self.reseed_counter, out.len())?;
+
+        let additional = additional.as_ref();
+        if !additional.is_empty() {
+            self.update(&[additional]);
+        }
+
+        for chunk in out.chunks_mut(self.v.len()) {
+            self.v = self.hmac(&[&self.v]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+
+        self.update(&[additional]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// HMAC_DRBG_Update
+    /// K = HMAC(K, V || 0x00 || data), V = HMAC(K, V)
+    /// If data is not empty, repeat the above with 0x01 in place of 0x00.
+    fn update(&mut self, data: &[&[u8]]) {
+        self.rekey(0x00, data);
+        if data.iter().any(|x| !x.is_empty()) {
+            self.rekey(0x01, data);
+        }
+    }
+
+    fn rekey(&mut self, sep: u8, data: &[&[u8]]) {
+        let sep = [sep];
+        let mut parts: Vec<&[u8]> = vec![&self.v, &sep];
+        parts.extend_from_slice(data);
+        self.k = self.hmac(&parts);
+        self.v = self.hmac(&[&self.v]);
+    }
+
+    /// Compute the HMAC of the concatenated parts under the current key.
+    fn hmac(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let mut engine: Hmac<T> = Hmac::new_with_key(&self.k);
+        for part in parts {
+            engine.input(part);
+        }
+        engine.finalise().into()
+    }
+}
+
+
+/// Hash functions approved for Hash_DRBG, with their seed length from SP 800-90A table 2
+pub trait HashDrbgEngine: HashEngine {
+    /// Seed length in bytes
+    const SEEDLEN: usize;
+}
+
+impl HashDrbgEngine for Sha224 { const SEEDLEN: usize = 440/8; }
+impl HashDrbgEngine for Sha256 { const SEEDLEN: usize = 440/8; }
+impl HashDrbgEngine for Sha384 { const SEEDLEN: usize = 888/8; }
+impl HashDrbgEngine for Sha512 { const SEEDLEN: usize = 888/8; }
+impl<B: Sha256Backend> HashDrbgEngine for Sha224With<B> { const SEEDLEN: usize = 440/8; }
+impl<B: Sha256Backend> HashDrbgEngine for Sha256With<B> { const SEEDLEN: usize = 440/8; }
+impl<B: Sha512Backend> HashDrbgEngine for Sha384With<B> { const SEEDLEN: usize = 888/8; }
+impl<B: Sha512Backend> HashDrbgEngine for Sha512With<B> { const SEEDLEN: usize = 888/8; }
+
+/// Hash_DRBG (SP 800-90A, section 10.1.1)
+pub struct HashDrbg<T: HashDrbgEngine> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    hash: PhantomData<T>
+}
+
+impl<T: HashDrbgEngine> HashDrbg<T> {
+    /// Instantiate a new generator from entropy, a nonce and a personalization string.
+    pub fn new<E, N, P>(entropy: E, nonce: N, personalization: P) -> Self
+    where E: AsRef<[u8]>, N: AsRef<[u8]>, P: AsRef<[u8]> {
+        let v = Self::hash_df(&[entropy.as_ref(), nonce.as_ref(), personalization.as_ref()]);
+        let c = Self::hash_df(&[&[0x00], &v]);
+
+        Self {
+            v,
+            c,
+            reseed_counter: 1,
+            hash: PhantomData::<T>
+        }
+    }
+
+    /// Reseed the generator with fresh entropy and optional additional input.
+    pub fn reseed<E, A>(&mut self, entropy: E, additional: A)
+    where E: AsRef<[u8]>, A: AsRef<[u8]> {
+        self.v = Self::hash_df(&[&[0x01], &self.v, entropy.as_ref(), additional.as_ref()]);
+        self.c = Self::hash_df(&[&[0x00], &self.v]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fill the output buffer with pseudorandom bytes.
+    /// 
+    /// Panics if the generator must be reseeded or the buffer is too long, see try_generate().
+    pub fn generate(&mut self, out: &mut [u8]) {
+        self.generate_with_additional(out, []);
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, mixing in additional input.
+    /// 
+    /// Panics if the generator must be reseeded or the buffer is too long, see try_generate().
+    pub fn generate_with_additional<A>(&mut self, out: &mut [u8], additional: A)
+    where A: AsRef<[u8]> {
+        if let Err(e) = self.try_generate_with_additional(out, additional) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, failing if the generator must be
+    /// reseeded first or the buffer is longer than 2^19 bits.
+    pub fn try_generate(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        self.try_generate_with_additional(out, [])
+    }
+
+    /// Fill the output buffer with pseudorandom bytes, mixing in additional input,
+    /// failing if the generator must be reseeded first or the buffer is longer than 2^19 bits.
+    pub fn try_generate_with_additional<A>(&mut self, out: &mut [u8], additional: A) -> Result<(), Error>
+    where A: AsRef<[u8]> {
+        check_request(self.reseed_counter, out.len())?;
+
+        let additional = additional.as_ref();
+        if !additional.is_empty() {
+            let w = Self::hash(&[&[0x02], &self.v, additional]);
+            add_be(&mut self.v, &w);
+        }
+
+        // Hashgen
+        let mut data = self.v.clone();
+        for chunk in out.chunks_mut(T::OUTPUT_SIZE) {
+            let w = Self::hash(&[&data]);
+            chunk.copy_from_slice(&w[..chunk.len()]);
+            add_be(&mut data, &[0x01]);
+        }
+
+        // V = (V + H + C + reseed_counter) mod 2^seedlen
+        let h = Self::hash(&[&[0x03], &self.v]);
+        let c = self.c.clone();
+        add_be(&mut self.v, &h);
+        add_be(&mut self.v, &c);
+        add_be(&mut self.v, &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// Hash the concatenated parts
+    fn hash(parts: &[&[u8]]) -> Vec<u8> {
+        let mut engine = T::default();
+        for part in parts {
+            engine.input(part);
+        }
+        engine.finalise().into()
+    }
+
+    /// Hash_df, the hash based derivation function.
+    /// Derives seedlen bytes from the concatenated parts.
+    fn hash_df(parts: &[&[u8]]) -> Vec<u8> {
+        let seedlen = T::SEEDLEN;
+        let bits = (seedlen as u32 * 8).to_be_bytes();
+        let mut temp: Vec<u8> = vec![];
+        let mut counter: u8 = 1;
+        while temp.len() < seedlen {
+            let mut engine = T::default();
+            engine.input([counter]);
+            engine.input(bits);
+            for part in parts {
+                engine.input(part);
+            }
+            temp.extend(engine.finalise().as_ref());
+            counter += 1;
+        }
+        temp.truncate(seedlen);
+
+        temp
+    }
+}
+
+/// Add two big endian integers, storing the result in the first modulo its length.
+fn add_be(acc: &mut [u8], x: &[u8]) {
+    let mut carry: u16 = 0;
+    let mut x = x.iter().rev();
+    for byte in acc.iter_mut().rev() {
+        let sum = *byte as u16 + *x.next().unwrap_or(&0) as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+
+/// Implement rand_core's RngCore and CryptoRng for a DRBG
+#[cfg(feature = "rand")]
+macro_rules! impl_rng {
+    ($name: ident, $($bound: path),*) => {
+        impl<T: $($bound+)*> rand_core::RngCore for $name<T> {
+            fn next_u32(&mut self) -> u32 {
+                rand_core::impls::next_u32_via_fill(self)
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                rand_core::impls::next_u64_via_fill(self)
+            }
+
+            // Long buffers are filled with as many requests as the per request limit needs
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+                    self.generate(chunk);
+                }
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+                    self.try_generate(chunk).map_err(|_| {
+                        let code = ::core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START).expect("code is not zero");
+                        rand_core::Error::from(code)
+                    })?;
+                }
+                Ok(())
+            }
+        }
+
+        impl<T: $($bound+)*> rand_core::CryptoRng for $name<T> { }
+    };
+}
+
+#[cfg(feature = "rand")]
+impl_rng!(HmacDrbg, HashEngine, Copy);
+#[cfg(feature = "rand")]
+impl_rng!(HashDrbg, HashDrbgEngine);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::{Sha256, Sha512};
+
+    #[test]
+    fn rfc6979_p256_sha256() {
+        // RFC 6979 A.2.5, key generation for message "sample".
+        // bits2octets(h1) equals h1 here since h1 is smaller than the curve order.
+        let x = hex::<32>("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let mut h1 = Sha256::new();
+        h1.input(b"sample");
+
+        let mut drbg: HmacDrbg<Sha256> = HmacDrbg::new(x, h1.finalise(), []);
+        let mut k = [0u8; 32];
+        drbg.generate(&mut k);
+        assert_eq!(k, hex("a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60"));
+    }
+
+    #[test]
+    fn rfc6979_p256_sha512() {
+        // RFC 6979 A.2.5, key generation for message "sample" using SHA-512.
+        // bits2octets(h1) is the leftmost 32 bytes of h1 which are smaller than the curve order.
+        let x = hex::<32>("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let mut h1 = Sha512::new();
+        h1.input(b"sample");
+
+        let mut drbg: HmacDrbg<Sha512> = HmacDrbg::new(x, &h1.finalise()[..32], []);
+        let mut k = [0u8; 32];
+        drbg.generate(&mut k);
+        assert_eq!(k, hex("5fa81c63109badb88c1f367b47da606da28cad69aa22c4fe6ad7df73a7173aa5"));
+    }
+
+    #[test]
+    fn hash_drbg() {
+        // Reference values computed with an independent implementation of SP 800-90A.
+        let mut drbg: HashDrbg<Sha256> = HashDrbg::new(b"entropy input", b"nonce", []);
+        let mut out = [0u8; 64];
+        drbg.generate(&mut out);
+        drbg.generate(&mut out);
+        assert_eq!(out, hex("b6d7ac73c8c74384350fbbb2df6022bde9bd49adb6d45d89cf06a540b56a6770587dc0b35a61f9461a421531aac0bb3f5fb06f3dbd032e8f433f41db7ea6d578"));
+
+        let mut drbg: HashDrbg<Sha512> = HashDrbg::new(b"entropy input", b"nonce", b"personalization");
+        let mut out = [0u8; 100];
+        drbg.generate_with_additional(&mut out, b"additional");
+        drbg.generate_with_additional(&mut out, b"additional");
+        assert_eq!(out, hex("154401da684a1581aa430fb00276f035d493ab1221bfe1c1417606c138cad8706717073bcd23a6d6f3745c8b85772a95df4863f07d0d6be5252acb34aeb2a3a999b12ff5d6a2228f1093960555f0d13aa4ab85b2fc29a534b7df123b693f5c8eb957ea18"));
+    }
+
+    #[test]
+    fn hash_drbg_cavp() {
+        // NIST CAVP Hash_DRBG.rsp, SHA-256 without prediction resistance, COUNT = 0.
+        // The output of the second generate call is the returned bits.
+        let entropy = hex::<32>("a65ad0f345db4e0effe875c3a2e71f42c7129d620ff5c119a9ef55f05185e0fb");
+        let nonce = hex::<16>("8581f9317517276e06e9607ddbcbcc2e");
+        let mut drbg: HashDrbg<Sha256> = HashDrbg::new(entropy, nonce, []);
+        let mut out = [0u8; 128];
+        drbg.generate(&mut out);
+        drbg.generate(&mut out);
+        assert_eq!(out, hex("d3e160c35b99f340b2628264d1751060e0045da383ff57a57d73a673d2b8d80daaf6a6c35a91bb4579d73fd0c8fed111b0391306828adfed528f018121b3febdc343e797b87dbb63db1333ded9d1ece177cfa6b71fe8ab1da46624ed6415e51ccde2c7ca86e283990eeaeb91120415528b2295910281b02dd431f4c9f70427df"));
+    }
+
+    #[test]
+    fn request_limits() {
+        let mut drbg: HashDrbg<Sha256> = HashDrbg::new(b"entropy input", b"nonce", []);
+        let mut out = vec![0u8; MAX_REQUEST_BYTES + 1];
+        assert_eq!(drbg.try_generate(&mut out), Err(Error::RequestTooLong));
+        assert_eq!(drbg.try_generate(&mut out[..MAX_REQUEST_BYTES]), Ok(()));
+
+        // The last request before the reseed interval is allowed, the next one needs a reseed
+        drbg.reseed_counter = RESEED_INTERVAL;
+        assert_eq!(drbg.try_generate(&mut out[..32]), Ok(()));
+        assert_eq!(drbg.try_generate(&mut out[..32]), Err(Error::ReseedRequired));
+        assert!(::std::panic::catch_unwind(move || drbg.generate(&mut [0; 32])).is_err());
+
+        let mut drbg: HmacDrbg<Sha256> = HmacDrbg::new(b"entropy input", b"nonce", []);
+        assert_eq!(drbg.try_generate(&mut out), Err(Error::RequestTooLong));
+        drbg.reseed_counter = RESEED_INTERVAL + 1;
+        assert_eq!(drbg.try_generate(&mut out[..32]), Err(Error::ReseedRequired));
+        drbg.reseed(b"more entropy", []);
+        assert_eq!(drbg.try_generate(&mut out[..32]), Ok(()));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn rng_core() {
+        use rand_core::RngCore;
+
+        // Output through RngCore must match the output of generate()
+        let mut a: HmacDrbg<Sha256> = HmacDrbg::new(b"entropy", b"nonce", []);
+        let mut b: HmacDrbg<Sha256> = HmacDrbg::new(b"entropy", b"nonce", []);
+        let mut expected = [0u8; 8];
+        a.generate(&mut expected);
+        assert_eq!(b.next_u64(), u64::from_le_bytes(expected));
+
+        let mut a: HashDrbg<Sha512> = HashDrbg::new(b"entropy", b"nonce", []);
+        let mut b: HashDrbg<Sha512> = HashDrbg::new(b"entropy", b"nonce", []);
+        let mut expected = [0u8; 100];
+        let mut out = [0u8; 100];
+        a.generate(&mut expected);
+        b.fill_bytes(&mut out);
+        assert_eq!(out, expected);
+
+        // Buffers past the per request limit are filled with several requests
+        let mut out = vec![0u8; MAX_REQUEST_BYTES + 1];
+        assert!(b.try_fill_bytes(&mut out).is_ok());
+    }
+}
@@ -0,0 +1,33 @@
+// Electrum module
+//
+// The Electrum protocol indexes addresses by the SHA256 of their scriptPubKey, sent
+// as hex with the bytes reversed like a txid.
+
+use crate::{
+    HashEngine,
+    Sha256
+};
+
+/// Compute the Electrum script hash of a scriptPubKey, already byte reversed for the protocol's hex form
+pub fn electrum_scripthash<S: AsRef<[u8]>>(script: S) -> [u8; 32] {
+    let mut digest = Sha256::new().chain(script).finalise().0;
+    digest.reverse();
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn genesis_address() {
+        // https://electrumx.readthedocs.io/en/latest/protocol-basics.html#script-hashes
+        let script = [
+            0x76, 0xa9, 0x14, 0x62, 0xe9, 0x07, 0xb1, 0x5c, 0xbf, 0x27, 0xd5, 0x42, 0x53,
+            0x99, 0xeb, 0xf6, 0xf0, 0xfb, 0x50, 0xeb, 0xb8, 0x8f, 0x18, 0x88, 0xac
+        ];
+        let scripthash = Sha256Digest(electrum_scripthash(script));
+        assert_eq!(scripthash.to_string(), "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161");
+    }
+}
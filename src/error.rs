@@ -0,0 +1,76 @@
+// Error module
+//
+// Errors returned by the fallible parts of the crate's API.
+
+use ::core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The requested derived key is longer than the KDF can produce
+    DerivedKeyTooLong,
+    /// The iteration count must be at least one
+    InvalidIterationCount,
+    /// Serialized midstate bytes have the wrong length
+    InvalidMidstate,
+    /// Serialized engine state bytes are malformed
+    InvalidState,
+    /// The midstate length is not a multiple of the engine's blocksize
+    UnalignedMidstate,
+    /// A message block was created from a slice that is not exactly one block long
+    InvalidBlockLength,
+    /// Pending data restored alongside a midstate must be shorter than a block
+    PendingTooLong,
+    /// A received shachain secret does not derive the secrets received before it
+    InvalidSecret,
+    /// Scrypt parameters are out of range or the cost is not a power of two
+    InvalidCost,
+    /// The message is longer than the hash function can encode in its padding
+    MessageTooLong,
+    /// The DRBG has reached its reseed interval and must be reseeded before generating more output
+    ReseedRequired,
+    /// More output was requested from a DRBG than a single request may produce
+    RequestTooLong
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DerivedKeyTooLong => write!(f, "derived key too long"),
+            Error::InvalidIterationCount => write!(f, "iteration count must be at least one"),
+            Error::InvalidMidstate => write!(f, "invalid serialized midstate"),
+            Error::InvalidState => write!(f, "invalid serialized engine state"),
+            Error::UnalignedMidstate => write!(f, "midstate length is not a multiple of the blocksize"),
+            Error::InvalidBlockLength => write!(f, "slice is not exactly one block long"),
+            Error::PendingTooLong => write!(f, "pending data must be shorter than the blocksize"),
+            Error::InvalidSecret => write!(f, "secret does not derive the previously received secrets"),
+            Error::InvalidCost => write!(f, "invalid scrypt cost parameters"),
+            Error::MessageTooLong => write!(f, "message is too long for the hash function"),
+            Error::ReseedRequired => write!(f, "the generator must be reseeded"),
+            Error::RequestTooLong => write!(f, "too many bytes requested from the generator")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error { }
+
+/// Errors from parsing a digest out of a hex string
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexError {
+    /// The string does not encode the expected number of bytes
+    InvalidLength { expected: usize, got: usize },
+    /// The string contains a character that is not a hex digit
+    InvalidChar(char)
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HexError::InvalidLength { expected, got } => write!(f, "expected {} hex characters, got {}", expected, got),
+            HexError::InvalidChar(c) => write!(f, "invalid hex character {:?}", c)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError { }
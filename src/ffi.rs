@@ -0,0 +1,189 @@
+// FFI module
+//
+// C bindings for the main engines, declared in include/btc_hashes.h. Each engine is
+// an opaque heap allocated context with new/update/finalize/free functions, and every
+// engine has a one shot function as well. Build the shared library with
+//      cargo rustc --lib --release --features ffi --crate-type cdylib
+//
+// The cdylib crate type is not declared in Cargo.toml, as it would then be linked in
+// every build, and a cdylib cannot be linked without std.
+
+use alloc::boxed::Box;
+use ::core::{
+    ffi::c_int,
+    slice
+};
+use crate::{
+    Error,
+    HashEngine,
+    Hmac,
+    Sha256,
+    Sha512,
+    Sha1,
+    Ripemd160,
+    Sha256d,
+    Hash160,
+    pbkdf2_hmac_sha256,
+    pbkdf2_hmac_sha512
+};
+
+/// The call succeeded
+pub const BTC_OK: c_int = 0;
+/// The iteration count was zero
+pub const BTC_ERR_ITERATIONS: c_int = -1;
+/// The requested output is longer than the function can produce
+pub const BTC_ERR_OUTPUT_TOO_LONG: c_int = -2;
+
+/// Status code returned to C for the result of a call
+fn status(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => BTC_OK,
+        Err(Error::InvalidIterationCount) => BTC_ERR_ITERATIONS,
+        Err(_) => BTC_ERR_OUTPUT_TOO_LONG
+    }
+}
+
+/// Borrow a C buffer, allowing a null pointer for an empty buffer
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+/// Copy a digest into a C buffer of the digest length
+unsafe fn write_digest<D: AsRef<[u8]>>(digest: D, out: *mut u8) {
+    let digest = digest.as_ref();
+    slice::from_raw_parts_mut(out, digest.len()).copy_from_slice(digest);
+}
+
+/// Define the C functions of an engine
+macro_rules! ffi_engine {
+    ($engine: ty, $new: ident, $update: ident, $finalize: ident, $free: ident, $oneshot: ident) => {
+        /// Create a context, to be released with the matching free function
+        #[no_mangle]
+        pub extern "C" fn $new() -> *mut $engine {
+            Box::into_raw(Box::new(<$engine>::default()))
+        }
+
+        /// Input data into a context
+        /// 
+        /// # Safety
+        /// ctx must come from the matching new function and data must be valid for len bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn $update(ctx: *mut $engine, data: *const u8, len: usize) {
+            (*ctx).input(bytes(data, len));
+        }
+
+        /// Write the digest to out and reset the context for a new message
+        /// 
+        /// # Safety
+        /// ctx must come from the matching new function and out must be valid for the digest length.
+        #[no_mangle]
+        pub unsafe extern "C" fn $finalize(ctx: *mut $engine, out: *mut u8) {
+            write_digest((*ctx).finalise_reset(), out);
+        }
+
+        /// Release a context, null is ignored
+        /// 
+        /// # Safety
+        /// ctx must come from the matching new function and not be used afterwards.
+        #[no_mangle]
+        pub unsafe extern "C" fn $free(ctx: *mut $engine) {
+            if !ctx.is_null() {
+                drop(Box::from_raw(ctx));
+            }
+        }
+
+        /// Hash a single message into out
+        /// 
+        /// # Safety
+        /// data must be valid for len bytes and out must be valid for the digest length.
+        #[no_mangle]
+        pub unsafe extern "C" fn $oneshot(data: *const u8, len: usize, out: *mut u8) {
+            write_digest(<$engine>::default().chain(bytes(data, len)).finalise(), out);
+        }
+    };
+}
+
+ffi_engine!(Sha256, btc_sha256_new, btc_sha256_update, btc_sha256_finalize, btc_sha256_free, btc_sha256);
+ffi_engine!(Sha512, btc_sha512_new, btc_sha512_update, btc_sha512_finalize, btc_sha512_free, btc_sha512);
+ffi_engine!(Sha1, btc_sha1_new, btc_sha1_update, btc_sha1_finalize, btc_sha1_free, btc_sha1);
+ffi_engine!(Ripemd160, btc_ripemd160_new, btc_ripemd160_update, btc_ripemd160_finalize, btc_ripemd160_free, btc_ripemd160);
+ffi_engine!(Sha256d, btc_sha256d_new, btc_sha256d_update, btc_sha256d_finalize, btc_sha256d_free, btc_sha256d);
+ffi_engine!(Hash160, btc_hash160_new, btc_hash160_update, btc_hash160_finalize, btc_hash160_free, btc_hash160);
+
+/// HMAC-SHA256 of a single message, writing 32 bytes to out
+/// 
+/// # Safety
+/// key and data must be valid for their lengths and out must be valid for 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn btc_hmac_sha256(key: *const u8, key_len: usize, data: *const u8, len: usize, out: *mut u8) {
+    write_digest(Hmac::<Sha256>::mac(bytes(key, key_len), bytes(data, len)), out);
+}
+
+/// HMAC-SHA512 of a single message, writing 64 bytes to out
+/// 
+/// # Safety
+/// key and data must be valid for their lengths and out must be valid for 64 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn btc_hmac_sha512(key: *const u8, key_len: usize, data: *const u8, len: usize, out: *mut u8) {
+    write_digest(Hmac::<Sha512>::mac(bytes(key, key_len), bytes(data, len)), out);
+}
+
+/// PBKDF2-HMAC-SHA256 filling out_len bytes of out
+/// 
+/// Returns BTC_OK, or an error code without writing to out.
+/// 
+/// # Safety
+/// password, salt and out must be valid for their lengths.
+#[no_mangle]
+pub unsafe extern "C" fn btc_pbkdf2_hmac_sha256(password: *const u8, password_len: usize, salt: *const u8, salt_len: usize, iterations: u32, out: *mut u8, out_len: usize) -> c_int {
+    let out = if out_len == 0 { &mut [] } else { slice::from_raw_parts_mut(out, out_len) };
+    status(pbkdf2_hmac_sha256(bytes(password, password_len), bytes(salt, salt_len), iterations as usize, out))
+}
+
+/// PBKDF2-HMAC-SHA512 filling out_len bytes of out
+/// 
+/// Returns BTC_OK, or an error code without writing to out.
+/// 
+/// # Safety
+/// password, salt and out must be valid for their lengths.
+#[no_mangle]
+pub unsafe extern "C" fn btc_pbkdf2_hmac_sha512(password: *const u8, password_len: usize, salt: *const u8, salt_len: usize, iterations: u32, out: *mut u8, out_len: usize) -> c_int {
+    let out = if out_len == 0 { &mut [] } else { slice::from_raw_parts_mut(out, out_len) };
+    status(pbkdf2_hmac_sha512(bytes(password, password_len), bytes(salt, salt_len), iterations as usize, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::core::ptr;
+
+    #[test]
+    fn c_api() {
+        let mut out = [0u8; 32];
+        unsafe {
+            let ctx = btc_sha256_new();
+            btc_sha256_update(ctx, b"ab".as_ptr(), 2);
+            btc_sha256_update(ctx, ptr::null(), 0);
+            btc_sha256_update(ctx, b"c".as_ptr(), 1);
+            btc_sha256_finalize(ctx, out.as_mut_ptr());
+            btc_sha256_free(ctx);
+        }
+        assert_eq!(crate::Sha256Digest::from(out).to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let mut out = [0u8; 20];
+        unsafe { btc_hash160(b"abc".as_ptr(), 3, out.as_mut_ptr()) };
+        assert_eq!(crate::Ripemd160Digest::from(out).to_string(), "bb1be98c142444d7a56aa3981c3942a978e4dc33");
+
+        // Invalid parameters are reported instead of leaving out unwritten
+        let mut out = [0u8; 64];
+        let status = unsafe { btc_pbkdf2_hmac_sha512(b"password".as_ptr(), 8, b"salt".as_ptr(), 4, 2, out.as_mut_ptr(), out.len()) };
+        assert_eq!(status, BTC_OK);
+        assert_eq!(crate::Sha512Digest::from(out).to_string(), "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
+        let status = unsafe { btc_pbkdf2_hmac_sha256(b"password".as_ptr(), 8, b"salt".as_ptr(), 4, 0, out.as_mut_ptr(), out.len()) };
+        assert_eq!(status, BTC_ERR_ITERATIONS);
+    }
+}
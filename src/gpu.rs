@@ -0,0 +1,226 @@
+// GPU module
+//
+// Offloads large batches of independent sha256d computations to a GPU through wgpu,
+// for search style workloads such as nonce scanning or vanity address grinding.
+// Messages are padded on the host and each shader invocation hashes one message.
+
+use std::sync::mpsc;
+use std::fmt;
+use wgpu::util::DeviceExt;
+use crate::Sha256Digest;
+
+const WORKGROUP_SIZE: usize = 64;
+
+/// Errors from setting up or running the GPU backend
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GpuError {
+    /// No compatible GPU adapter was found
+    NoAdapter,
+    /// The adapter refused to create a device
+    Device(String),
+    /// The device failed to finish the batch or read back the digests
+    Readback(String)
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no compatible GPU adapter found"),
+            GpuError::Device(e) => write!(f, "failed to create GPU device: {}", e),
+            GpuError::Readback(e) => write!(f, "failed to read back GPU digests: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for GpuError { }
+
+/// A GPU device ready to compute batches of sha256d digests
+pub struct GpuSha256d {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    max_blocks: usize
+}
+
+impl GpuSha256d {
+    /// Connect to the preferred GPU adapter and compile the shader
+    pub fn new() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        })).map_err(|_| GpuError::NoAdapter)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("btc-hashes"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        })).map_err(|e| GpuError::Device(e.to_string()))?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("sha256d"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into())
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("sha256d"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None
+        });
+
+        let limits = device.limits();
+        let max_binding = limits.max_storage_buffer_binding_size.min(limits.max_buffer_size as _) as usize;
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            max_blocks: max_binding / 64
+        })
+    }
+
+    /// Compute sha256d for every message, in order.
+    /// 
+    /// Mirrors the CPU `hash_batch`, splitting the batch into as many dispatches as the device limits require.
+    pub fn hash_batch<I: AsRef<[u8]>>(&self, messages: &[I]) -> Result<Vec<Sha256Digest>, GpuError> {
+        let max_messages = self.device.limits().max_compute_workgroups_per_dimension as usize * WORKGROUP_SIZE;
+        let mut digests = Vec::with_capacity(messages.len());
+
+        let mut start = 0;
+        while start < messages.len() {
+            // Take as many messages as fit in one dispatch and one storage binding
+            let mut end = start;
+            let mut blocks = 0;
+            while end < messages.len() && end - start < max_messages {
+                let count = padded_blocks(messages[end].as_ref().len());
+                if blocks + count > self.max_blocks && end > start {
+                    break
+                }
+                blocks += count;
+                end += 1;
+            }
+
+            digests.extend(self.dispatch(&messages[start..end], blocks)?);
+            start = end;
+        }
+
+        Ok(digests)
+    }
+
+    fn dispatch<I: AsRef<[u8]>>(&self, messages: &[I], blocks: usize) -> Result<Vec<Sha256Digest>, GpuError> {
+        let mut words: Vec<u8> = Vec::with_capacity(blocks * 64);
+        let mut spans: Vec<u8> = Vec::with_capacity(messages.len() * 8);
+        for message in messages {
+            let message = message.as_ref();
+            spans.extend_from_slice(&((words.len() / 64) as u32).to_le_bytes());
+            spans.extend_from_slice(&(padded_blocks(message.len()) as u32).to_le_bytes());
+            pad_words(message, &mut words);
+        }
+
+        let output_size = (messages.len() * 32) as wgpu::BufferAddress;
+        let blocks_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("blocks"),
+            contents: &words,
+            usage: wgpu::BufferUsages::STORAGE
+        });
+        let spans_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("spans"),
+            contents: &spans,
+            usage: wgpu::BufferUsages::STORAGE
+        });
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("digests"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: blocks_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: spans_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: output.as_entire_binding() }
+            ]
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(messages.len().div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, output_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        staging.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).map_err(|e| GpuError::Readback(e.to_string()))?;
+        receiver.recv()
+            .map_err(|e| GpuError::Readback(e.to_string()))?
+            .map_err(|e| GpuError::Readback(e.to_string()))?;
+
+        let digests = staging.get_mapped_range(..).chunks_exact(32).map(|chunk| {
+            let mut digest = [0u8; 32];
+            for (out, word) in digest.chunks_exact_mut(4).zip(chunk.chunks_exact(4)) {
+                out.copy_from_slice(&[word[3], word[2], word[1], word[0]]);
+            }
+            Sha256Digest(digest)
+        }).collect();
+        staging.unmap();
+
+        Ok(digests)
+    }
+}
+
+/// Number of SHA256 blocks a message occupies once padded
+fn padded_blocks(len: usize) -> usize {
+    (len + 9).div_ceil(64)
+}
+
+/// Pad a message and append it as big endian words, stored in the device's little endian order
+fn pad_words(message: &[u8], words: &mut Vec<u8>) {
+    let start = words.len();
+    words.extend_from_slice(message);
+    words.push(0x80);
+    words.resize(start + padded_blocks(message.len()) * 64 - 8, 0);
+    words.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+    for word in words[start..].chunks_exact_mut(4) {
+        word.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashEngine, Sha256};
+
+    #[test]
+    fn matches_cpu() {
+        let gpu = match GpuSha256d::new() {
+            Ok(gpu) => gpu,
+            Err(e) => {
+                eprintln!("skipping GPU test: {}", e);
+                return
+            }
+        };
+
+        let messages: Vec<Vec<u8>> = (0..200usize).map(|i| vec![i as u8; i]).collect();
+        let digests = gpu.hash_batch(&messages).unwrap();
+        for (message, digest) in messages.iter().zip(digests.iter()) {
+            let first = Sha256::new().chain(message).finalise();
+            assert_eq!(*digest, Sha256::new().chain(first).finalise());
+        }
+    }
+}
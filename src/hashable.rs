@@ -0,0 +1,187 @@
+// Hashable module
+//
+// Canonical encoding of values into a hash engine, so that struct shaped preimages
+// can be hashed field by field. The encoding follows Bitcoin's consensus
+// serialization: integers are little endian (wrap them in BigEndian otherwise),
+// variable length sequences are prefixed with their compact size length and fixed
+// size arrays and tuples are their elements one after another.
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    string::String,
+    vec::Vec
+};
+use crate::HashEngine;
+
+/// A value with a canonical encoding that can be fed into a hash engine
+pub trait Hashable {
+    /// Input the encoding of the value into the engine
+    fn hash_into<H: HashEngine>(&self, engine: &mut H);
+
+    /// Input the encodings of several values, without a length prefix
+    fn hash_slice_into<H: HashEngine>(data: &[Self], engine: &mut H)
+    where Self: Sized {
+        for value in data {
+            value.hash_into(engine);
+        }
+    }
+
+    /// Digest of the value with a fresh engine
+    fn hash_with<H: HashEngine>(&self) -> H::Digest {
+        let mut engine = H::default();
+        self.hash_into(&mut engine);
+        engine.finalise()
+    }
+}
+
+/// An integer that is encoded big endian
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BigEndian<T>(pub T);
+
+macro_rules! impl_hashable_int {
+    ($($int: ty),*) => {
+        $(
+            impl Hashable for $int {
+                fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+                    engine.input(self.to_le_bytes());
+                }
+            }
+
+            impl Hashable for BigEndian<$int> {
+                fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+                    engine.input(self.0.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_hashable_int!(u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Hashable for u8 {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        engine.input([*self]);
+    }
+
+    // Bytes are input in one call rather than one at a time
+    fn hash_slice_into<H: HashEngine>(data: &[Self], engine: &mut H) {
+        engine.input(data);
+    }
+}
+
+impl Hashable for bool {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        engine.input([*self as u8]);
+    }
+}
+
+impl<T: Hashable> Hashable for [T] {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        engine.input_compact_size(self.len() as u64);
+        T::hash_slice_into(self, engine);
+    }
+}
+
+impl<T: Hashable, const N: usize> Hashable for [T; N] {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        T::hash_slice_into(self, engine);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Hashable> Hashable for Vec<T> {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        self.as_slice().hash_into(engine);
+    }
+}
+
+impl Hashable for str {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        self.as_bytes().hash_into(engine);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Hashable for String {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        self.as_bytes().hash_into(engine);
+    }
+}
+
+impl<T: Hashable + ?Sized> Hashable for &T {
+    fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+        (**self).hash_into(engine);
+    }
+}
+
+macro_rules! impl_hashable_tuple {
+    ($($name: ident),+) => {
+        impl<$($name: Hashable),+> Hashable for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn hash_into<H: HashEngine>(&self, engine: &mut H) {
+                let ($($name,)+) = self;
+                $($name.hash_into(engine);)+
+            }
+        }
+    };
+}
+
+impl_hashable_tuple!(A);
+impl_hashable_tuple!(A, B);
+impl_hashable_tuple!(A, B, C);
+impl_hashable_tuple!(A, B, C, D);
+impl_hashable_tuple!(A, B, C, D, E);
+impl_hashable_tuple!(A, B, C, D, E, F);
+
+/// Implement Hashable for a struct by hashing the listed fields in order.
+///
+/// ```
+/// use btc_hashes::{impl_hashable, Hashable, Sha256d};
+///
+/// struct OutPoint {
+///     txid: [u8; 32],
+///     vout: u32
+/// }
+///
+/// impl_hashable!(OutPoint { txid, vout });
+///
+/// let outpoint = OutPoint { txid: [0; 32], vout: 1 };
+/// let digest = outpoint.hash_with::<Sha256d>();
+/// ```
+#[macro_export]
+macro_rules! impl_hashable {
+    ($name: ident { $($field: ident),* $(,)? }) => {
+        impl $crate::Hashable for $name {
+            fn hash_into<H: $crate::HashEngine>(&self, engine: &mut H) {
+                $($crate::Hashable::hash_into(&self.$field, engine);)*
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    struct TxOut {
+        value: u64,
+        script_pubkey: &'static [u8]
+    }
+
+    impl_hashable!(TxOut { value, script_pubkey });
+
+    #[test]
+    fn hashable() {
+        let output = TxOut { value: 5000, script_pubkey: &[0x51; 3] };
+        let expected = Sha256::new()
+            .chain(5000u64.to_le_bytes())
+            .chain([3, 0x51, 0x51, 0x51])
+            .finalise();
+        assert_eq!(output.hash_with::<Sha256>(), expected);
+
+        let mut engine = Sha256::new();
+        (BigEndian(1u16), "ab", [7u8; 2], true).hash_into(&mut engine);
+        assert_eq!(engine.pending(), [0, 1, 2, b'a', b'b', 7, 7, 1]);
+    }
+}
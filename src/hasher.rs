@@ -0,0 +1,134 @@
+// Hasher module
+//
+// Adapters to core::hash so that hash maps and sets can use keyed hashing with a
+// key chosen by the caller. Peer provided data can then be deduplicated without
+// the attacker being able to predict which entries collide.
+
+use core::hash::{
+    Hasher,
+    BuildHasher
+};
+use crate::{
+    HashEngine,
+    Sha256,
+    siphash::SipHash24
+};
+
+/// A Hasher that truncates the digest of an engine to 64 bits
+///
+/// The first 8 bytes of the digest are read as a little endian integer.
+#[derive(Clone, Copy, Debug)]
+pub struct EngineHasher<T: HashEngine + Clone> {
+    engine: T
+}
+
+impl<T: HashEngine + Clone> EngineHasher<T> {
+    /// Wrap an engine, which may already have been fed a key
+    pub fn new(engine: T) -> Self {
+        Self {
+            engine
+        }
+    }
+}
+
+impl<T: HashEngine + Clone> Hasher for EngineHasher<T> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.engine.input(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.engine.finalise_copy();
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&digest.as_ref()[..8]);
+        u64::from_le_bytes(word)
+    }
+}
+
+/// Builds SipHash-2-4 hashers with a fixed key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SipHashBuilder {
+    k0: u64,
+    k1: u64
+}
+
+impl SipHashBuilder {
+    /// Create a builder from the two halves of the key
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        Self {
+            k0,
+            k1
+        }
+    }
+
+    /// Create a builder from a 16 byte key
+    pub fn new_with_key(key: &[u8; 16]) -> Self {
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&key[..8]);
+        k1.copy_from_slice(&key[8..]);
+        Self::new_with_keys(u64::from_le_bytes(k0), u64::from_le_bytes(k1))
+    }
+}
+
+impl BuildHasher for SipHashBuilder {
+    type Hasher = SipHash24;
+
+    fn build_hasher(&self) -> SipHash24 {
+        SipHash24::new_with_keys(self.k0, self.k1)
+    }
+}
+
+/// Builds truncated SHA256 hashers keyed by prefixing a secret key
+///
+/// The key is hashed once when the builder is created.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha256Builder {
+    engine: Sha256
+}
+
+impl Sha256Builder {
+    /// Create a builder from a 32 byte key
+    pub fn new_with_key(key: &[u8; 32]) -> Self {
+        Self {
+            engine: Sha256::new().chain(key)
+        }
+    }
+}
+
+impl BuildHasher for Sha256Builder {
+    type Hasher = EngineHasher<Sha256>;
+
+    fn build_hasher(&self) -> EngineHasher<Sha256> {
+        EngineHasher::new(self.engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn build_hashers() {
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let builder = Sha256Builder::new_with_key(&key);
+        let mut hasher = builder.build_hasher();
+        hasher.write(b"hello");
+        assert_eq!(hasher.finish(), 0x98cb053373c301e3);
+
+        let mut sip_key = [0u8; 16];
+        sip_key.copy_from_slice(&key[..16]);
+        let builder = SipHashBuilder::new_with_key(&sip_key);
+        assert_eq!(builder.build_hasher().finish(), 0x726fdb47dd0e0e31);
+
+        let mut map = HashMap::with_hasher(builder);
+        map.insert(b"peer".to_vec(), 1);
+        map.insert(b"peer".to_vec(), 2);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&b"peer".to_vec()], 2);
+    }
+}
@@ -0,0 +1,81 @@
+// Block header module
+//
+// Bitcoin block headers are 80 bytes, so the first 64 bytes fill exactly one
+// SHA256 block. Miners only change the last 16 bytes (the end of the merkle root,
+// time, bits and nonce), and mostly just the nonce, so the midstate after the
+// first block is computed once and every nonce only costs the remaining blocks.
+
+use crate::{
+    HashEngine,
+    Midstate,
+    Sha256,
+    Sha256Digest,
+    Sha256Fixed
+};
+
+/// Hashes a block header with many different nonces
+#[derive(Clone, Copy, Debug)]
+pub struct HeaderHasher {
+    midstate: Midstate<u32, 8>,
+    tail: [u8; 16],
+    header: Sha256Fixed,
+    digest: Sha256Fixed
+}
+
+impl HeaderHasher {
+    /// Create a header hasher from the first 76 bytes of a header, everything except the nonce
+    pub fn new(prefix: &[u8; 76]) -> Self {
+        let mut tail = [0u8; 16];
+        tail[..12].copy_from_slice(&prefix[64..]);
+
+        Self {
+            midstate: Sha256::new().chain(&prefix[..64]).midstate(),
+            tail,
+            header: Sha256Fixed::new(80),
+            digest: Sha256Fixed::new(32)
+        }
+    }
+
+    /// Double SHA256 of the header with the given nonce.
+    /// 
+    /// The digest is in internal byte order, reverse it for the usual block hash hex.
+    pub fn hash_with_nonce(&self, nonce: u32) -> Sha256Digest {
+        let mut tail = self.tail;
+        tail[12..].copy_from_slice(&nonce.to_le_bytes());
+
+        let first = self.header.hash_from_midstate(self.midstate, &tail);
+        self.digest.hash(first.as_ref())
+    }
+
+    /// Iterate over the header hashes for each of the given nonces, such as a range
+    pub fn nonces<'a, I>(&'a self, nonces: I) -> impl Iterator<Item=(u32, Sha256Digest)> + 'a
+    where I: IntoIterator<Item=u32>, I::IntoIter: 'a {
+        nonces.into_iter().map(move |nonce| (nonce, self.hash_with_nonce(nonce)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    fn genesis_prefix() -> [u8; 76] {
+        hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d")
+    }
+
+    #[test]
+    fn genesis_header() {
+        let hasher = HeaderHasher::new(&genesis_prefix());
+        let digest = hasher.hash_with_nonce(2083236893);
+        assert_eq!(digest.display_backwards().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+    }
+
+    #[test]
+    fn nonce_range() {
+        let hasher = HeaderHasher::new(&genesis_prefix());
+        let found = hasher.nonces(2083236800..2083236900)
+            .find(|(_, digest)| digest.0[28..] == [0; 4])
+            .map(|(nonce, _)| nonce);
+        assert_eq!(found, Some(2083236893));
+    }
+}
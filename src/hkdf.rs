@@ -0,0 +1,125 @@
+// HKDF module
+//
+// HMAC based extract-and-expand key derivation (RFC 5869).
+//      PRK = HMAC(salt, IKM)
+//      T(i) = HMAC(PRK, T(i-1) || info || i)
+//      OKM = T(1) || T(2) || ... truncated to L bytes
+//
+
+use crate::{
+    error::Error,
+    HashEngine,
+    KeyBasedHashEngine,
+    Hmac,
+    Sha256,
+    Sha512
+};
+
+#[derive(Clone)]
+pub struct Hkdf<T: HashEngine+Copy> {
+    prf: Hmac<T>
+}
+
+impl<T: HashEngine+Copy> Hkdf<T> {
+    /// Extract a pseudorandom key from the input keying material.
+    /// 
+    /// An empty salt is the same as a salt of OUTPUT_SIZE zero bytes.
+    pub fn extract<S, I>(salt: S, ikm: I) -> Self
+    where S: AsRef<[u8]>, I: AsRef<[u8]> {
+        Self::from_prk(Hmac::<T>::mac(salt, ikm))
+    }
+
+    /// Skip the extract step, using an existing pseudorandom key
+    pub fn from_prk<P>(prk: P) -> Self
+    where P: AsRef<[u8]> {
+        Self {
+            prf: Hmac::new_with_key(prk)
+        }
+    }
+
+    /// Expand the pseudorandom key into the output buffer, which can be at most 255 * OUTPUT_SIZE bytes
+    pub fn expand<I>(&self, info: I, out: &mut [u8]) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        if out.len() > 255 * T::OUTPUT_SIZE {
+            return Err(Error::DerivedKeyTooLong);
+        }
+
+        let mut prf = self.prf.clone();
+        let mut previous: Option<T::Digest> = None;
+        for (i, chunk) in out.chunks_mut(T::OUTPUT_SIZE).enumerate() {
+            if let Some(t) = previous {
+                prf.input(t);
+            }
+            prf.input(info.as_ref());
+            prf.input([i as u8 + 1]);
+
+            let t = prf.finalise_reset();
+            chunk.copy_from_slice(&t.as_ref()[..chunk.len()]);
+            previous = Some(t);
+        }
+
+        Ok(())
+    }
+}
+
+/// HKDF with SHA256
+pub fn hkdf_sha256<S, K, I>(salt: S, ikm: K, info: I, out: &mut [u8]) -> Result<(), Error>
+where S: AsRef<[u8]>, K: AsRef<[u8]>, I: AsRef<[u8]> {
+    Hkdf::<Sha256>::extract(salt, ikm).expand(info, out)
+}
+
+/// HKDF with SHA512
+pub fn hkdf_sha512<S, K, I>(salt: S, ikm: K, info: I, out: &mut [u8]) -> Result<(), Error>
+where S: AsRef<[u8]>, K: AsRef<[u8]>, I: AsRef<[u8]> {
+    Hkdf::<Sha512>::extract(salt, ikm).expand(info, out)
+}
+
+/// Derive the subkey with the given id and 8 byte context from a master key, in the
+/// style of libsodium's crypto_kdf_derive_from_key.
+/// 
+/// libsodium derives subkeys with BLAKE2b, which this crate does not implement yet, so
+/// the master key is used as an HKDF-SHA256 pseudorandom key with the info set to
+/// context || subkey_id (little endian). Subkeys are therefore not byte compatible with
+/// libsodium, but the key hierarchy (master key, context and id) carries over.
+pub fn derive_subkey(master_key: &[u8; 32], subkey_id: u64, context: [u8; 8], out: &mut [u8]) -> Result<(), Error> {
+    let mut info = [0u8; 16];
+    info[..8].copy_from_slice(&context);
+    info[8..].copy_from_slice(&subkey_id.to_le_bytes());
+    Hkdf::<Sha256>::from_prk(master_key).expand(info, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn hkdf_sha256_rfc5869() {
+        // RFC 5869 test case 1
+        let salt: Vec<u8> = (0u8..13).collect();
+        let info: Vec<u8> = (0xf0u8..0xfa).collect();
+        let mut okm = [0u8; 42];
+        hkdf_sha256(&salt, [0x0b; 22], &info, &mut okm).unwrap();
+
+        assert_eq!(okm, hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"));
+
+        let mut too_long = [0u8; 255 * 32 + 1];
+        assert_eq!(hkdf_sha256(&salt, [0x0b; 22], &info, &mut too_long), Err(Error::DerivedKeyTooLong));
+    }
+
+    #[test]
+    fn subkeys() {
+        let mut master_key = [0u8; 32];
+        for (i, byte) in master_key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let mut subkey = [0u8; 48];
+        let mut other = [0u8; 48];
+        derive_subkey(&master_key, 1, *b"Examples", &mut subkey).unwrap();
+        derive_subkey(&master_key, 2, *b"Examples", &mut other).unwrap();
+        assert_ne!(subkey, other);
+
+        assert_eq!(subkey, hex("cf7ca29e47a0d69e64f84e104d5527febb8a9af19ba1f8f3f88b7b36a13c309e07a83a219ecf8fed9aa10fe6b329f7b9"));
+    }
+}
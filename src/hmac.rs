@@ -1,9 +1,6 @@
 // HMAC Function Module
 //
-//
-//  Todo:
-//    - Implement midstate for HMAC by conceptually splitting the single engine into
-//      two engines. The definition for a hmac function can be generalised as 
+// The definition for a hmac function can be generalised as 
 //              HMAC(K, m) = H((K' ^ opad) || H(K'^ipad || m))
 //                  where K is the key
 //                        m is the message
@@ -11,74 +8,194 @@
 //                        ipad is a blocksized byte array of 0x36
 //                        opad is a blocksized byte array of 0x5c
 //
-//      The function can be split into two hash engines, both with their own midstate
-//      because there are two hash functions. The inner and the outer.
+// The function is split into two hash engines, both with their own midstate
+// because there are two hash functions. The inner and the outer.
+// Keys are primed in a stack buffer so no part of the engine allocates.
 //
 
 
-use crate::core::{
-    HashEngine,
-    KeyBasedHashEngine
+use ::core::fmt;
+use crate::{
+    core::{
+        HashEngine,
+        KeyBasedHashEngine,
+        Mac,
+        ct_eq,
+        input_bytes
+    },
+    Sha256,
+    Sha512,
+    Sha256Digest,
+    Sha512Digest,
+    Error
+};
+#[cfg(feature = "std")]
+use std::io::{
+    self,
+    Read,
+    Write
 };
+#[cfg(feature = "zeroize")]
+use crate::core::overwrite;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 const OPAD: u8 = 0x5c;
 const IPAD: u8 = 0x36;
+const MAX_BLOCKSIZE: usize = 136; // Largest blocksize of the supported hash engines
 
+#[derive(Clone)]
 pub struct Hmac<T: HashEngine> {
     inner: T,
     outer: T,
     istate: HmacMidState<T>,
-    msg_buffer: Vec<u8>
+    keyed: bool          // Cleared when the key is wiped, the engine must then be re-keyed
 }
 
 /// Struct used to represent the inner and outer hash midstates of a HMAC function
+/// 
+/// The outer engine only ever holds the opad key until finalisation, so its midstate
+/// is always the keyed state. The keyed inner state (ipad) is carried alongside the
+/// current inner state so that a resumed engine can still be reset to its key.
 #[derive(Clone, Copy, Debug)]
 pub struct HmacMidState<T: HashEngine> {
     inner: T::Midstate,
-    outer: T::Midstate
+    outer: T::Midstate,
+    ipad: T::Midstate
+}
+
+impl<T: HashEngine> PartialEq for HmacMidState<T>
+where T::Midstate: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.outer == other.outer && self.ipad == other.ipad
+    }
 }
 
+impl<T: HashEngine> Eq for HmacMidState<T>
+where T::Midstate: Eq { }
+
+/// Engines are equal when they hold the same key and have processed the same message data.
+/// The comparison is not constant time.
+impl<T: HashEngine+PartialEq> PartialEq for Hmac<T>
+where T::Midstate: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.outer == other.outer && self.istate == other.istate && self.keyed == other.keyed
+    }
+}
+
+impl<T: HashEngine+Eq> Eq for Hmac<T>
+where T::Midstate: Eq { }
+
 impl<T: HashEngine> Default for Hmac<T> {
     fn default() -> Self {        
         Self {
             inner: T::default(),             // Hasher with no data inputted
             outer: T::default(),
             istate: HmacMidState::default(), // HmacMidstate with empty key
-            msg_buffer: vec![]
+            keyed: true
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: HashEngine + Zeroize> Zeroize for Hmac<T>
+where T::Midstate: Zeroize {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.outer.zeroize();
+        self.istate.zeroize();
+        self.keyed = false;
+    }
+}
+
+/// Wipe the keyed state and any buffered message data when the engine is dropped
+#[cfg(feature = "zeroize")]
+impl<T: HashEngine> Drop for Hmac<T> {
+    fn drop(&mut self) {
+        self.wipe_keys();
+    }
+}
+
+impl<T: HashEngine> Hmac<T> {
+    // The engines wipe themselves if they can, the keyed midstates are replaced with unkeyed ones
+    // and the engine is marked as needing a new key
+    fn wipe_keys(&mut self) {
+        self.inner.wipe();
+        self.outer.wipe();
+        #[cfg(feature = "zeroize")]
+        {
+            let unkeyed = T::default().midstate();
+            overwrite(&mut self.istate.inner, unkeyed);
+            overwrite(&mut self.istate.outer, unkeyed);
+            overwrite(&mut self.istate.ipad, unkeyed);
+            self.keyed = false;
         }
     }
+
+    fn assert_keyed(&self) {
+        assert!(self.keyed, "the HMAC key has been wiped, the engine must be re-keyed");
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: HashEngine> Zeroize for HmacMidState<T>
+where T::Midstate: Zeroize {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.outer.zeroize();
+        self.ipad.zeroize();
+    }
 }
 
 impl<T: HashEngine> Default for HmacMidState<T> {
     fn default() -> Self {
         // The midstate for default hmac is the midstate of each inner hash function where there is no input data
         // padded upto blocksize.
+        Self::from_key(&[])
+    }
+}
+
+impl<T: HashEngine> HmacMidState<T> {
+    /// Compute the keyed inner and outer midstates.
+    /// 
+    /// The primed key is built in a stack buffer and XORed with ipad/opad in place.
+    fn from_key(key: &[u8]) -> Self {
+        assert!(T::BLOCKSIZE <= MAX_BLOCKSIZE);
+        let mut buffer = [0u8; MAX_BLOCKSIZE];
+        let block = &mut buffer[..T::BLOCKSIZE];
 
-        // compute the primed key, where the key is an empty byte array.
-        let mut key: Vec<u8> = vec![];
+        // Prime the key, hashing it if it is longer than the blocksize and padding it with zeroes.
         if key.len() > T::BLOCKSIZE {
             let mut e = T::default();
             e.input(key);
-            key = e.finalise().into();
+            let digest = e.finalise();
+            block[..digest.as_ref().len()].copy_from_slice(digest.as_ref());
+        } else {
+            block[..key.len()].copy_from_slice(key);
         }
-        if key.len() < T::BLOCKSIZE {
-            let mut padding = vec![];
-            padding.resize(T::BLOCKSIZE-key.len(), 0x00);
-            key.extend(padding);
-        }
-        assert_eq!(key.len(), T::BLOCKSIZE);
 
-        let opad_key: Vec<u8> = key.iter().map(|x| x^OPAD).collect();
-        let ipad_key: Vec<u8> = key.iter().map(|x| x^IPAD).collect();
-        drop(key);
+        // Input the primed key xor ipad/opad into the respective inner/outer engines.
+        block.iter_mut().for_each(|x| *x ^= IPAD);
         let mut iengine: T = T::default();
-        iengine.input(ipad_key);
+        iengine.input(&block);
+        block.iter_mut().for_each(|x| *x ^= IPAD ^ OPAD);
         let mut oengine: T = T::default();
-        oengine.input(opad_key);
-        Self {
+        oengine.input(&block);
+
+        let midstate = Self {
             inner: iengine.midstate(),
-            outer: oengine.midstate()
-        }
+            outer: oengine.midstate(),
+            ipad: iengine.midstate()
+        };
+
+        #[cfg(feature = "zeroize")]
+        buffer.zeroize();
+        #[cfg(not(feature = "zeroize"))]
+        buffer.fill(0);
+        iengine.wipe();
+        oengine.wipe();
+
+        midstate
     }
 }
 
@@ -86,11 +203,15 @@ impl<T: HashEngine+Copy> HashEngine for Hmac<T> {
     type Digest = T::Digest;
     type Midstate = HmacMidState<T>;
     const BLOCKSIZE: usize = T::BLOCKSIZE;
+    const OUTPUT_SIZE: usize = T::OUTPUT_SIZE;
 
+    /// Panics if the key has been wiped, see rekey().
     fn reset(&mut self) {
-        self.inner.from_midstate(self.istate.inner, T::BLOCKSIZE); // reset the inner and outer hash engine midstate's to the primed key midstate.
-        self.outer.from_midstate(self.istate.outer, T::BLOCKSIZE);
-        self.msg_buffer = vec![];
+        self.assert_keyed();
+        self.inner.wipe(); // wipe any buffered message data left behind by the previous message
+
+        self.inner.from_midstate(self.istate.inner); // reset the inner and outer hash engine midstate's to the primed key midstate.
+        self.outer.from_midstate(self.istate.outer);
     }
 
     fn input<I>(&mut self, data: I)
@@ -98,80 +219,355 @@ impl<T: HashEngine+Copy> HashEngine for Hmac<T> {
         self.inner.input(data);
     }
 
+    fn try_input<I>(&mut self, data: I) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        self.inner.try_input(data)
+    }
+
+    /// The midstate of the inner engine only covers whole blocks, see pending() for the rest.
     fn midstate(&self) -> Self::Midstate {
         HmacMidState {
             inner: self.inner.midstate(),
-            outer: self.outer.midstate()
+            outer: self.istate.outer,
+            ipad: self.istate.inner
         }
     }
 
-    fn from_midstate(&mut self, midstate: Self::Midstate, length: usize) {
-        self.inner.from_midstate(midstate.inner, length);
-        self.outer.from_midstate(midstate.outer, length);
+    /// Restore the keyed inner and outer engines from a midstate.
+    /// 
+    /// The inner midstate's length includes the blocksized ipad key.
+    /// The outer engine has always processed exactly one block.
+    fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), Error> {
+        self.inner.try_from_midstate(midstate.inner)?;
+        self.outer.try_from_midstate(midstate.outer)?;
+        self.istate = HmacMidState {
+            inner: midstate.ipad,
+            outer: midstate.outer,
+            ipad: midstate.ipad
+        };
+        self.keyed = true;
+        Ok(())
+    }
+
+    fn pending(&self) -> &[u8] {
+        self.inner.pending()
+    }
+
+    fn bytes_hashed(&self) -> u64 {
+        self.inner.bytes_hashed()
     }
 
+    /// Panics if the key has been wiped, see rekey().
     fn finalise(&mut self) -> Self::Digest {
+        self.assert_keyed();
         self.outer.input(self.inner.finalise());
         self.outer.finalise()
     }
+
+    fn wipe(&mut self) {
+        self.wipe_keys();
+    }
 }
 
-impl<T: HashEngine+Copy> KeyBasedHashEngine for Hmac<T> {
-    fn new_with_key<I>(key: I) -> Self
-    where I: AsRef<[u8]> {
-        let mut engine = Self::default();
+impl<T: HashEngine+Copy> Hmac<T> {
+    /// Create a new HMAC engine with a key read from a reader.
+    /// 
+    /// Keys longer than the blocksize are hashed as they are read so the
+    /// whole key never needs to be held in memory.
+    #[cfg(feature = "std")]
+    pub fn new_with_key_from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        // Read up to one byte past the blocksize to find out whether the key needs hashing.
+        let mut buffer = [0u8; MAX_BLOCKSIZE+1];
+        let filled = read_full(&mut reader, &mut buffer[..T::BLOCKSIZE+1])?;
+        if filled <= T::BLOCKSIZE {
+            let engine = Self::new_with_key(&buffer[..filled]);
+            #[cfg(feature = "zeroize")]
+            buffer.zeroize();
+            #[cfg(not(feature = "zeroize"))]
+            buffer.fill(0);
+            return Ok(engine);
+        }
 
-        //Prime the key and xor it with opad/ipad
-        let mut key = key.as_ref().to_vec();
-        if key.len() > Self::BLOCKSIZE {
-            let mut e = T::default();
-            e.input(key);
-            key = e.finalise().into();
+        let mut e = T::default();
+        e.input(&buffer[..filled]);
+        loop {
+            let n = read_full(&mut reader, &mut buffer)?;
+            e.input(&buffer[..n]);
+            if n < buffer.len() {
+                break;
+            }
         }
-        if key.len() < Self::BLOCKSIZE {
-            let mut padding = vec![];
-            padding.resize(Self::BLOCKSIZE-key.len(), 0x00);
-            key.extend(padding);
+        #[cfg(feature = "zeroize")]
+        buffer.zeroize();
+        #[cfg(not(feature = "zeroize"))]
+        buffer.fill(0);
+
+        let key = e.finalise();
+        e.wipe();
+        Ok(Self::new_with_key(key))
+    }
+
+    /// Key the engine again, discarding any message data.
+    /// 
+    /// An engine whose key has been wiped cannot be reset or finalised until it is re-keyed.
+    pub fn rekey<K>(&mut self, key: K)
+    where K: AsRef<[u8]> {
+        self.istate = HmacMidState::from_key(key.as_ref());
+        self.keyed = true;
+        self.reset();
+    }
+
+    /// Compute the HMAC of a single message in one call
+    pub fn mac<K, M>(key: K, message: M) -> T::Digest
+    where K: AsRef<[u8]>, M: AsRef<[u8]> {
+        let mut engine = Self::new_with_key(key);
+        engine.input(message);
+        engine.finalise()
+    }
+
+    /// Finalise the HMAC and compare the result against the tag in constant time.
+    pub fn verify(&mut self, tag: &[u8]) -> bool {
+        ct_eq(self.finalise().as_ref(), tag)
+    }
+
+    /// Finalise the HMAC and compare the leftmost bytes of the result against a truncated tag in constant time.
+    /// 
+    /// Tags shorter than half of the digest length or 80 bits are rejected (RFC 2104 section 5).
+    pub fn verify_truncated(&mut self, tag: &[u8]) -> bool {
+        let digest = self.finalise();
+        let digest = digest.as_ref();
+        if tag.len() > digest.len() || tag.len() < ::core::cmp::max(digest.len()/2, 10) {
+            return false;
         }
-        assert_eq!(key.len(), Self::BLOCKSIZE);
 
-        let opad_key: Vec<u8> = key.iter().map(|x| x^OPAD).collect();
-        let ipad_key: Vec<u8> = key.iter().map(|x| x^IPAD).collect();
-        assert_eq!(opad_key.len(), T::BLOCKSIZE);
-        assert_eq!(ipad_key.len(), T::BLOCKSIZE);
+        ct_eq(&digest[..tag.len()], tag)
+    }
 
-        // Input the result into respective inner/outer engines.
-        engine.inner.input(ipad_key);  //the inner/outer engines have not yet hashed anything. (see Hmac<T>::default())
-        engine.outer.input(opad_key);
+    /// Finalise the HMAC and return the leftmost N bytes of the tag (RFC 2104 section 5).
+    /// 
+    /// Panics if N is larger than the digest length.
+    pub fn finalise_truncated<const N: usize>(&mut self) -> [u8; N] {
+        let digest = self.finalise();
+        let mut tag = [0u8; N];
+        tag.copy_from_slice(&digest.as_ref()[..N]);
 
+        tag
+    }
+}
+
+impl<T: HashEngine+Copy> KeyBasedHashEngine for Hmac<T> {
+    fn new_with_key<I>(key: I) -> Self
+    where I: AsRef<[u8]> {
         // Set the initial state of outer and inner engine's to the opad/ipad keys.
         // By storing the initial state of the inner and outer engines, there is no need
         // to store the key and recompute the states for the inner and outer engines.
-        engine.istate = HmacMidState {
-            inner: engine.inner.midstate(),
-            outer: engine.outer.midstate()
+        let mut engine = Self {
+            inner: T::default(),
+            outer: T::default(),
+            istate: HmacMidState::from_key(key.as_ref()),
+            keyed: true
         };
+        engine.reset();
 
         engine
     }
 }
 
+impl<T: HashEngine+Copy> Mac for Hmac<T> {
+    type Tag = T::Digest;
+
+    fn update<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.input(data);
+    }
+
+    fn finalise_tag(&mut self) -> Self::Tag {
+        self.finalise()
+    }
+
+    fn verify(&mut self, tag: &[u8]) -> bool {
+        Hmac::verify(self, tag)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: HashEngine+Copy> Write for Hmac<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        self.input_vectored(bufs);
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: HashEngine+Copy> fmt::Write for Hmac<T> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.input(s);
+        Ok(())
+    }
+}
+
+impl<T: HashEngine+Copy> Extend<u8> for Hmac<T> {
+    fn extend<I: IntoIterator<Item=u8>>(&mut self, iter: I) {
+        input_bytes(self, iter);
+    }
+}
+
+/// Read from the reader until the buffer is full or EOF is reached, returning the amount read.
+#[cfg(feature = "std")]
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+
+    Ok(filled)
+}
+
+/// HMAC-SHA256
+pub type HmacSha256 = Hmac<Sha256>;
+/// HMAC-SHA512
+pub type HmacSha512 = Hmac<Sha512>;
+
+/// Compute HMAC-SHA256 of a single message
+pub fn hmac_sha256<K, M>(key: K, message: M) -> Sha256Digest
+where K: AsRef<[u8]>, M: AsRef<[u8]> {
+    Hmac::<Sha256>::mac(key, message)
+}
+
+/// Compute HMAC-SHA512 of a single message
+pub fn hmac_sha512<K, M>(key: K, message: M) -> Sha512Digest
+where K: AsRef<[u8]>, M: AsRef<[u8]> {
+    Hmac::<Sha512>::mac(key, message)
+}
+
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::hex;
     use crate::core::HashEngine;
-    use crate::Sha256;
 
     #[test]
     fn hmac_sha256() {
         let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
         engine.input(b"The quick brown fox jumps over the lazy dog");
-        let digest = engine.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        let digest = engine.finalise().to_string();
         assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
     }
 
-    #[ignore]
+    #[test]
+    fn hmac_one_shot() {
+        // RFC 4231 test case 2
+        let tag = Hmac::<Sha256>::mac(b"Jefe", b"what do ya want for nothing?").to_string();
+        assert_eq!(tag, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        assert_eq!(super::hmac_sha256(b"Jefe", b"what do ya want for nothing?"), Hmac::<Sha256>::mac(b"Jefe", b"what do ya want for nothing?"));
+
+        let tag = hmac_sha512(b"Jefe", b"what do ya want for nothing?").to_string();
+        assert_eq!(tag, "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737");
+    }
+
+    #[test]
+    fn hmac_long_key() {
+        // RFC 4231 test case 6
+        let tag = Hmac::<Sha256>::mac([0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_string();
+        assert_eq!(tag, "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+
+        let tag = Hmac::<Sha512>::mac([0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_string();
+        assert_eq!(tag, "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hmac_key_from_reader() {
+        let long_key = [0xaa; 1000];
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key_from_reader(&long_key[..]).unwrap();
+        engine.input(b"message");
+        assert_eq!(engine.finalise(), Hmac::<Sha256>::mac(long_key, b"message"));
+
+        for len in [0, 20, 64, 65, 128, 129] {
+            let mut engine: Hmac<Sha512> = Hmac::new_with_key_from_reader(&long_key[..len]).unwrap();
+            engine.input(b"message");
+            assert_eq!(engine.finalise(), Hmac::<Sha512>::mac(&long_key[..len], b"message"));
+        }
+    }
+
+    #[test]
+    fn hmac_mac_trait() {
+        fn tag<M: Mac>(mut mac: M, data: &[u8]) -> M::Tag {
+            mac.update(data);
+            mac.finalise_tag()
+        }
+
+        let engine: Hmac<Sha256> = Hmac::new_with_key(b"Jefe");
+        let expected = Hmac::<Sha256>::mac(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(tag(engine.clone(), b"what do ya want for nothing?"), expected);
+
+        let mut engine = engine;
+        Mac::update(&mut engine, b"what do ya want for nothing?");
+        assert!(Mac::verify(&mut engine, &expected[..]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hmac_write() {
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"Jefe");
+        engine.write_all(b"what do ya want ").unwrap();
+        engine.write_all(b"for nothing?").unwrap();
+        assert_eq!(engine.finalise(), Hmac::<Sha256>::mac(b"Jefe", b"what do ya want for nothing?"));
+    }
+
+    #[test]
+    fn hmac_verify() {
+        let tag = super::hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"Jefe");
+        engine.input(b"what do ya want for nothing?");
+        assert!(engine.verify(&tag[..]));
+
+        let mut bad = tag.0;
+        bad[31] ^= 0x80;
+        engine.reset();
+        engine.input(b"what do ya want for nothing?");
+        assert!(!engine.verify(&bad));
+
+        engine.reset();
+        engine.input(b"what do ya want for nothing?");
+        assert!(!engine.verify(&tag[..16]));
+
+        engine.reset();
+        engine.input(b"what do ya want for nothing?");
+        assert!(engine.verify_truncated(&tag[..16]));
+
+        engine.reset();
+        engine.input(b"what do ya want for nothing?");
+        assert!(!engine.verify_truncated(&tag[..8]));
+    }
+
+    #[test]
+    fn hmac_truncated() {
+        // RFC 4231 test case 5
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key([0x0c; 20]);
+        engine.input(b"Test With Truncation");
+        assert_eq!(engine.finalise_truncated::<16>(), hex("a3b6167473100ee06e0c796c2955552b"));
+
+        let mut engine: Hmac<Sha512> = Hmac::new_with_key([0x0c; 20]);
+        engine.input(b"Test With Truncation");
+        assert_eq!(engine.finalise_truncated::<16>(), hex("415fad6271580a531d4179bc891d87a6"));
+    }
+
     #[test]
     fn midstate() {
         // Create a new engine with key "key".
@@ -181,9 +577,105 @@ mod tests {
         // Create a new engine without a key
         let mut engine: Hmac<Sha256> = Hmac::default();
         //copy in the midstate from the engine with the key into the engine without the key. 
-        engine.from_midstate(midstate, 64); // length is set to 64 here becasue the midstate of the inner and outer engines have been fed the blocksized ipad/opad keys.
+        engine.from_midstate(midstate);
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let digest = engine.finalise().to_string();
+        assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    }
+
+    #[test]
+    fn midstate_resume() {
+        // Capture the midstate after a block of message data has been processed
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.input([0x61; 64]);
+        let midstate: HmacMidState<Sha256> = engine.midstate();
+
+        let mut resumed: Hmac<Sha256> = Hmac::default();
+        resumed.from_midstate(midstate);
+        resumed.input(b"The quick brown fox jumps over the lazy dog");
         engine.input(b"The quick brown fox jumps over the lazy dog");
-        let digest = engine.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        assert_eq!(resumed.finalise(), engine.finalise());
+
+        // The resumed engine resets back to the keyed state
+        resumed.reset();
+        resumed.input(b"The quick brown fox jumps over the lazy dog");
+        let digest = resumed.finalise().to_string();
         assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize() {
+        use zeroize::Zeroize;
+
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.input(b"secret message");
+        engine.zeroize();
+        let midstate = engine.midstate();
+        assert_eq!(midstate.inner.registers(), [0; 8]);
+        assert_eq!(midstate.outer.registers(), [0; 8]);
+        assert_eq!(midstate.ipad.registers(), [0; 8]);
+
+        // Generic wrappers wipe through HashEngine::wipe(), which leaves unkeyed midstates
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.input(b"secret message");
+        engine.wipe();
+        let midstate = engine.midstate();
+        assert_eq!(midstate.ipad, Sha256::new().midstate());
+        assert_eq!(midstate.outer, Sha256::new().midstate());
+        assert!(engine.pending().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    #[should_panic(expected = "must be re-keyed")]
+    fn reset_after_wipe() {
+        // A wiped engine has no key left to reset to
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.wipe();
+        engine.reset();
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn rekey_after_wipe() {
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.input(b"secret message");
+        engine.wipe();
+        engine.rekey(b"key");
+        assert!(engine == Hmac::new_with_key(b"key"));
+
+        engine.input(b"The quick brown fox jumps over the lazy dog");
+        let digest = engine.finalise().to_string();
+        assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    }
+
+    #[test]
+    fn midstate_with_buffer() {
+        // Capture the engine at an offset that is not block aligned
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        engine.input(b"The quick brown fox ");
+        let midstate = engine.midstate();
+        let pending = engine.pending().to_vec();
+        assert_eq!(pending.len(), 20);
+
+        let mut resumed: Hmac<Sha256> = Hmac::default();
+        resumed.from_midstate_with_buffer(midstate, &pending);
+        resumed.input(b"jumps over the lazy dog");
+        let digest = resumed.finalise().to_string();
+        assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+
+        assert_eq!(resumed.try_from_midstate_with_buffer(midstate, &[0; 64]), Err(Error::PendingTooLong));
+    }
+
+    #[test]
+    fn finalise_reset() {
+        // The engine returns to the keyed state after each message
+        let mut engine: Hmac<Sha256> = Hmac::new_with_key(b"key");
+        for _ in 0..2 {
+            engine.input(b"The quick brown fox jumps over the lazy dog");
+            let digest = engine.finalise_reset().to_string();
+            assert_eq!(digest, "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+        }
+    }
+}
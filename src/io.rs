@@ -0,0 +1,314 @@
+// IO module
+//
+// Adapters for hashing data as it flows through std::io readers and writers.
+
+
+use crate::core::{
+    ct_eq,
+    HashEngine
+};
+use std::{
+    fs::File,
+    io::{
+        self,
+        Read,
+        Write
+    },
+    path::Path
+};
+#[cfg(feature = "tokio")]
+use std::{
+    pin::Pin,
+    task::{
+        Context,
+        Poll
+    }
+};
+#[cfg(feature = "tokio")]
+use tokio::io::{
+    AsyncRead,
+    AsyncWrite,
+    ReadBuf
+};
+
+/// Hash the contents of a file in fixed size chunks without reading it into memory
+pub fn hash_file<H, P>(path: P) -> io::Result<H::Digest>
+where H: HashEngine, P: AsRef<Path> {
+    let mut engine = H::default();
+    engine.input_from_reader(File::open(path)?)?;
+    Ok(engine.finalise())
+}
+
+/// Writer adapter that forwards all writes to the inner writer while feeding the hash engine.
+/// 
+/// Only the bytes accepted by the inner writer are hashed, so the digest always
+/// matches the data that was actually written.
+pub struct HashWriter<W: Write, H: HashEngine> {
+    writer: W,
+    engine: H
+}
+
+impl<W: Write, H: HashEngine> HashWriter<W, H> {
+    /// Wrap a writer, hashing with a new engine
+    pub fn new(writer: W) -> Self {
+        Self::with_engine(writer, H::default())
+    }
+
+    /// Wrap a writer, hashing with the given engine
+    pub fn with_engine(writer: W, engine: H) -> Self {
+        Self {
+            writer,
+            engine
+        }
+    }
+
+    /// Reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Finalise the engine, returning the inner writer and the digest of everything written
+    pub fn finish(mut self) -> (W, H::Digest) {
+        let digest = self.engine.finalise();
+        (self.writer, digest)
+    }
+}
+
+impl<W: Write, H: HashEngine> Write for HashWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.engine.input(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reader adapter that checks the data read through it against an expected digest.
+///
+/// The digest is compared in constant time once the inner reader reaches EOF, and a
+/// mismatch is returned as an InvalidData error from that read instead of Ok(0).
+pub struct VerifyingReader<R: Read, H: HashEngine> {
+    reader: R,
+    engine: H,
+    expected: H::Digest,
+    // Result of the comparison, once the digest has been finalised
+    verified: Option<bool>
+}
+
+impl<R: Read, H: HashEngine> VerifyingReader<R, H> {
+    /// Wrap a reader, expecting everything read to hash to the given digest
+    pub fn new(reader: R, expected: H::Digest) -> Self {
+        Self {
+            reader,
+            engine: H::default(),
+            expected,
+            verified: None
+        }
+    }
+
+    /// Reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    fn verify(&mut self) -> io::Result<()> {
+        let engine = &mut self.engine;
+        let expected = &self.expected;
+        let matched = *self.verified.get_or_insert_with(|| ct_eq(engine.finalise().as_ref(), expected.as_ref()));
+        if matched {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "digest mismatch"))
+        }
+    }
+
+    /// Check the data read so far against the expected digest, returning the inner reader
+    pub fn finish(mut self) -> io::Result<R> {
+        self.verify()?;
+        Ok(self.reader)
+    }
+}
+
+impl<R: Read, H: HashEngine> Read for VerifyingReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.verified.is_some() {
+            return self.verify().map(|_| 0);
+        }
+
+        let n = self.reader.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            self.verify()?;
+        }
+        self.engine.input(&buf[..n]);
+        Ok(n)
+    }
+}
+
+
+/// Async reader adapter that hashes all data read through it.
+#[cfg(feature = "tokio")]
+pub struct AsyncHashReader<R: AsyncRead+Unpin, H: HashEngine+Unpin> {
+    reader: R,
+    engine: H
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead+Unpin, H: HashEngine+Unpin> AsyncHashReader<R, H> {
+    /// Wrap a reader, hashing with a new engine
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            engine: H::default()
+        }
+    }
+
+    /// Finalise the engine, returning the inner reader and the digest of everything read
+    pub fn finish(mut self) -> (R, H::Digest) {
+        let digest = self.engine.finalise();
+        (self.reader, digest)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead+Unpin, H: HashEngine+Unpin> AsyncRead for AsyncHashReader<R, H> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.reader).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            this.engine.input(&buf.filled()[before..]);
+        }
+
+        result
+    }
+}
+
+/// Async writer adapter that hashes all data written through it.
+#[cfg(feature = "tokio")]
+pub struct AsyncHashWriter<W: AsyncWrite+Unpin, H: HashEngine+Unpin> {
+    writer: W,
+    engine: H
+}
+
+#[cfg(feature = "tokio")]
+impl<W: AsyncWrite+Unpin, H: HashEngine+Unpin> AsyncHashWriter<W, H> {
+    /// Wrap a writer, hashing with a new engine
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            engine: H::default()
+        }
+    }
+
+    /// Finalise the engine, returning the inner writer and the digest of everything written
+    pub fn finish(mut self) -> (W, H::Digest) {
+        let digest = self.engine.finalise();
+        (self.writer, digest)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: AsyncWrite+Unpin, H: HashEngine+Unpin> AsyncWrite for AsyncHashWriter<W, H> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.writer).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = result {
+            this.engine.input(&buf[..n]);
+        }
+
+        result
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_shutdown(cx)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    #[test]
+    fn hash_writer() {
+        let mut writer: HashWriter<Vec<u8>, Sha256> = HashWriter::new(vec![]);
+        writer.write_all(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").unwrap();
+        let (data, digest) = writer.finish();
+        assert_eq!(data, b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec());
+        assert_eq!(digest.to_string(), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[test]
+    fn verifying_reader() {
+        let data: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let expected = crate::Sha256Digest::from_hex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1").unwrap();
+
+        let mut reader: VerifyingReader<&[u8], Sha256> = VerifyingReader::new(data, expected);
+        let mut out = Vec::new();
+        assert_eq!(reader.read_to_end(&mut out).unwrap(), data.len());
+        assert!(reader.finish().is_ok());
+
+        let mut reader: VerifyingReader<&[u8], Sha256> = VerifyingReader::new(&data[1..], expected);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(reader.finish().is_err());
+    }
+
+    #[test]
+    fn input_from_reader() {
+        // Larger than the internal chunk size
+        let data = vec![0x61u8; 20000];
+        let mut engine = Sha256::new();
+        assert_eq!(engine.input_from_reader(&data[..]).unwrap(), 20000);
+        let mut expected = Sha256::new();
+        expected.input(&data);
+        let expected = expected.finalise();
+        assert_eq!(engine.finalise(), expected);
+
+        let path = std::env::temp_dir().join(format!("btc-hashes-{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let digest = hash_file::<Sha256, _>(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest.unwrap(), expected);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_adapters() {
+        // The inner reader and writer are always ready so they can be polled without a runtime.
+        let data: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+
+        let mut reader: AsyncHashReader<&[u8], Sha256> = AsyncHashReader::new(data);
+        let mut out = [0u8; 10];
+        loop {
+            let mut buf = ReadBuf::new(&mut out);
+            match Pin::new(&mut reader).poll_read(&mut cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => break,
+                Poll::Ready(Ok(())) => continue,
+                _ => panic!("read failed")
+            }
+        }
+        let (_, digest) = reader.finish();
+        assert_eq!(digest.to_string(), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+
+        let mut writer: AsyncHashWriter<Vec<u8>, Sha256> = AsyncHashWriter::new(vec![]);
+        for chunk in data.chunks(7) {
+            match Pin::new(&mut writer).poll_write(&mut cx, chunk) {
+                Poll::Ready(Ok(n)) => assert_eq!(n, chunk.len()),
+                _ => panic!("write failed")
+            }
+        }
+        let (written, digest) = writer.finish();
+        assert_eq!(written, data.to_vec());
+        assert_eq!(digest.to_string(), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+}
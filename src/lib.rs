@@ -7,7 +7,6 @@
 //    - Write
 //        HMAC test cases (https://datatracker.ietf.org/doc/html/rfc4231#section-4)
 //        Midstate test cases
-//        Midstate implementation for Hmac using inner and outer engine midstates
 //
 //    - Implement midstate extraction and starting a hash from a given midstate and data.
 //        > Starting from a given midstate
@@ -17,22 +16,191 @@
 //
 
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
+// u64::is_multiple_of() needs Rust 1.87, the crate sticks to % for older compilers
+#![allow(clippy::manual_is_multiple_of)]
+
+#[cfg(any(feature = "alloc", test))]
+extern crate alloc;
+
 // Code modules
 mod core;
+mod error;
 mod constants;
+mod arch;
+pub mod cpu;
 mod sha2;
+mod backend;
+mod sha1;
 mod ripemd;
+mod sha3;
 mod hmac;
 mod pbkdf2;
+mod hkdf;
+#[cfg(feature = "alloc")]
+mod scrypt;
+#[cfg(feature = "alloc")]
+mod phc;
+#[cfg(feature = "alloc")]
+mod drbg;
+#[cfg(feature = "alloc")]
+mod otp;
+#[cfg(feature = "alloc")]
+mod tree;
+mod header;
+mod pow;
+mod bip32;
+mod slip10;
+mod siphash;
+mod bip152;
+mod bip158;
+mod signed_message;
+mod sighash;
+#[cfg(feature = "alloc")]
+mod merkle;
+mod electrum;
+mod shachain;
+mod noise;
+#[cfg(feature = "alloc")]
+mod bip38;
+mod onion;
+#[cfg(feature = "alloc")]
+mod timestamp;
+mod tagged;
+mod nested;
+mod multi;
+mod prefix;
+mod hasher;
+mod commitment;
+mod truncated;
+mod hashable;
+#[cfg(feature = "alloc")]
+mod piece;
+mod buffered;
+pub mod compat;
+#[cfg(feature = "wgpu")]
+mod gpu;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "secrecy")]
+mod secret;
+#[cfg(feature = "self-test")]
+mod self_test;
+mod newtype;
+#[cfg(feature = "digest")]
+mod rustcrypto;
+pub mod prelude;
 
 
 /// API
 pub use crate::core::HashEngine;
 pub use crate::core::KeyBasedHashEngine;
+pub use crate::core::Mac;
+pub use crate::core::Kdf;
+pub use crate::core::Midstate;
+pub use crate::core::DisplayBackwards;
+pub use error::Error;
+pub use error::HexError;
 pub use sha2::Sha224;
 pub use sha2::Sha256;
 pub use sha2::Sha384;
 pub use sha2::Sha512;
+pub use sha2::{Sha224Digest, Sha256Digest, Sha384Digest, Sha512Digest};
+pub use sha2::{Sha224With, Sha256With, Sha384With, Sha512With};
+pub use sha2::sha256d_64;
+pub use sha2::{sha256_compress, sha512_compress};
+pub use sha2::Sha256Fixed;
+pub use backend::{Sha256Backend, Sha512Backend, Portable};
+pub use sha1::Sha1;
+pub use sha1::Sha1Digest;
 pub use ripemd::Ripemd160;
+pub use ripemd::Ripemd160Digest;
+pub use sha3::{Sha3_256, Sha3_256Digest};
 pub use hmac::Hmac;
-pub use pbkdf2::PBKDF2;
\ No newline at end of file
+pub use hmac::{hmac_sha256, hmac_sha512};
+pub use hmac::{HmacSha256, HmacSha512};
+#[cfg(feature = "alloc")]
+pub use pbkdf2::PBKDF2;
+#[cfg(feature = "alloc")]
+pub use pbkdf2::PBKDF2Builder;
+#[cfg(feature = "alloc")]
+pub use pbkdf2::PBKDF2Blocks;
+pub use pbkdf2::pbkdf2;
+pub use pbkdf2::{pbkdf2_hmac_sha256, pbkdf2_hmac_sha512};
+#[cfg(feature = "alloc")]
+pub use pbkdf2::{Pbkdf2HmacSha256, Pbkdf2HmacSha512};
+pub use hkdf::Hkdf;
+pub use hkdf::{hkdf_sha256, hkdf_sha512, derive_subkey};
+#[cfg(feature = "alloc")]
+pub use scrypt::scrypt;
+#[cfg(feature = "alloc")]
+pub use phc::{PhcHash, PhcAlgorithm, PhcError};
+#[cfg(feature = "alloc")]
+pub use drbg::HmacDrbg;
+#[cfg(feature = "alloc")]
+pub use drbg::{HashDrbg, HashDrbgEngine};
+#[cfg(feature = "alloc")]
+pub use otp::Hotp;
+#[cfg(feature = "alloc")]
+pub use otp::Totp;
+#[cfg(feature = "alloc")]
+pub use tree::{tree_hash, TreeHasher, TREE_CHUNK_SIZE};
+#[cfg(feature = "alloc")]
+pub use piece::PieceHasher;
+pub use buffered::BufHasher;
+pub use header::HeaderHasher;
+pub use pow::compact_to_target;
+pub use bip32::{master_key_hash, ckd_hash, BIP32_SEED_KEY, HARDENED};
+pub use slip10::{Slip10Curve, slip10_master_key_hash, slip10_ckd_hash, slip10_retry_hash};
+pub use siphash::SipHash24;
+pub use bip152::{shortid, shortid_key};
+pub use bip158::{filter_key, hash_to_range, BIP158_M, BIP158_P};
+pub use signed_message::{signed_message_hash, SIGNED_MESSAGE_PREFIX};
+pub use sighash::{SighashCache, SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE, SIGHASH_ANYONECANPAY};
+#[cfg(feature = "alloc")]
+pub use merkle::{merkle_root, witness_commitment, witness_commitment_script, WITNESS_COMMITMENT_HEADER};
+pub use electrum::electrum_scripthash;
+pub use shachain::{shachain_secret, ShachainStore, SHACHAIN_MAX_INDEX};
+pub use noise::{noise_initial_state, mix_hash, mix_key, NOISE_PROTOCOL_NAME, LIGHTNING_PROLOGUE};
+#[cfg(feature = "alloc")]
+pub use bip38::{bip38_address_hash, bip38_derive_key, bip38_passfactor, bip38_derive_ec_key, BIP38_N, BIP38_R, BIP38_P};
+pub use onion::{onion_checksum, ONION_VERSION};
+#[cfg(feature = "alloc")]
+pub use onion::onion_address;
+#[cfg(feature = "alloc")]
+pub use timestamp::{CommitmentOp, evaluate_commitment};
+pub use tagged::{Tag, TaggedHash, TagCache, tag_midstate};
+pub use tagged::{Bip340Challenge, TapLeaf, TapBranch, TapTweak, TapSighash};
+pub use nested::{NestedHash, Sha256d, Hash160};
+pub use truncated::Truncated;
+pub use hashable::{Hashable, BigEndian};
+pub use multi::{MultiHasher, EngineSet};
+pub use prefix::PrefixCache;
+pub use hasher::{EngineHasher, SipHashBuilder, Sha256Builder};
+pub use commitment::{Commitment, commit, COMMITMENT_SALT_SIZE};
+#[cfg(feature = "rand")]
+pub use commitment::commit_random;
+#[cfg(feature = "wgpu")]
+pub use gpu::{GpuSha256d, GpuError};
+#[cfg(feature = "std")]
+pub use io::{HashWriter, VerifyingReader};
+#[cfg(feature = "std")]
+pub use io::hash_file;
+#[cfg(feature = "tokio")]
+pub use io::{AsyncHashReader, AsyncHashWriter};
+#[cfg(feature = "secrecy")]
+pub use secret::pbkdf2_secret;
+#[cfg(feature = "self-test")]
+pub use self_test::{self_test, SelfTestReport, KnownAnswer};
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
@@ -0,0 +1,83 @@
+// Merkle module
+//
+// Bitcoin's transaction merkle trees hash pairs of nodes with sha256d, duplicating
+// the last node of any level with an odd number of nodes. The witness commitment
+// (BIP141) is the same tree over wtxids, with the coinbase's wtxid set to zero.
+
+use alloc::vec::Vec;
+use crate::sha256d_64;
+
+/// Witness commitment header that follows OP_RETURN in the coinbase output
+pub const WITNESS_COMMITMENT_HEADER: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+
+/// Compute the merkle root of a list of txids, in internal byte order
+pub fn merkle_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level: Vec<[u8; 32]> = leaves.to_vec();
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| sha256d_64(&pair[0], pair.last().unwrap()))
+            .collect();
+    }
+    level.first().copied()
+}
+
+/// Compute the BIP141 witness commitment.
+/// 
+/// `wtxids` excludes the coinbase, whose wtxid is taken to be zero.
+pub fn witness_commitment(wtxids: &[[u8; 32]], witness_reserved_value: &[u8; 32]) -> [u8; 32] {
+    let mut leaves = Vec::with_capacity(wtxids.len() + 1);
+    leaves.push([0u8; 32]);
+    leaves.extend_from_slice(wtxids);
+
+    let root = merkle_root(&leaves).unwrap();
+    sha256d_64(&root, witness_reserved_value)
+}
+
+/// Build the coinbase output script holding a witness commitment
+pub fn witness_commitment_script(commitment: &[u8; 32]) -> [u8; 38] {
+    let mut script = [0u8; 38];
+    script[0] = 0x6a;                // OP_RETURN
+    script[1] = 0x24;                // Push 36 bytes
+    script[2..6].copy_from_slice(&WITNESS_COMMITMENT_HEADER);
+    script[6..].copy_from_slice(commitment);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::Sha256Digest;
+
+    fn txids() -> Vec<[u8; 32]> {
+        vec![
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d"
+        ].into_iter().map(|txid| Sha256Digest::from_hex_reversed(txid).unwrap().0).collect()
+    }
+
+    #[test]
+    fn block_100000() {
+        let txids = txids();
+        let root = merkle_root(&txids).unwrap();
+        assert_eq!(Sha256Digest(root).to_hex_reversed(), "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
+
+        // Odd number of leaves duplicates the last one
+        let root = merkle_root(&txids[..3]).unwrap();
+        assert_eq!(Sha256Digest(root).to_hex_reversed(), "fa435470825de273081dcc706b25514c936fa6dc80ab965ce6970d68ddd0b553");
+        assert_eq!(merkle_root(&[]), None);
+    }
+
+    #[test]
+    fn witness_commitments() {
+        // Blocks with only a coinbase
+        let commitment = witness_commitment(&[], &[0; 32]);
+        let script = witness_commitment_script(&commitment);
+        assert_eq!(script, hex("6a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9"));
+
+        let commitment = witness_commitment(&txids()[1..], &[0; 32]);
+        assert_eq!(Sha256Digest(commitment).to_string(), "1a761dfa7dcaf223f15efb3780d0e1903a1a177d47e9a831592302fb84745751");
+    }
+}
@@ -0,0 +1,128 @@
+// Multi hasher module
+//
+// Computes several digests of the same data in a single pass, by fanning each
+// input out to a tuple of hash engines. Useful when a large file needs more than
+// one digest and should only be read once.
+
+use crate::HashEngine;
+
+/// A tuple of hash engines that all receive the same input
+pub trait EngineSet: Default {
+    /// Tuple of the digests of each engine, in the same order
+    type Digests;
+
+    fn input(&mut self, data: &[u8]);
+
+    fn reset(&mut self);
+
+    fn finalise(&mut self) -> Self::Digests;
+}
+
+macro_rules! impl_engine_set {
+    ($($engine: ident: $idx: tt),+) => {
+        impl<$($engine: HashEngine),+> EngineSet for ($($engine,)+) {
+            type Digests = ($($engine::Digest,)+);
+
+            fn input(&mut self, data: &[u8]) {
+                $(self.$idx.input(data);)+
+            }
+
+            fn reset(&mut self) {
+                $(self.$idx.reset();)+
+            }
+
+            fn finalise(&mut self) -> Self::Digests {
+                ($(self.$idx.finalise(),)+)
+            }
+        }
+    };
+}
+
+impl_engine_set!(A: 0);
+impl_engine_set!(A: 0, B: 1);
+impl_engine_set!(A: 0, B: 1, C: 2);
+impl_engine_set!(A: 0, B: 1, C: 2, D: 3);
+impl_engine_set!(A: 0, B: 1, C: 2, D: 3, E: 4);
+
+/// Hashes the same input with every engine in the tuple `T`.
+///
+/// ```
+/// use btc_hashes::{MultiHasher, Ripemd160, Sha1, Sha256};
+///
+/// let mut hasher = MultiHasher::<(Sha256, Ripemd160, Sha1)>::new();
+/// hasher.input(b"abc");
+/// let (sha256, ripemd160, sha1) = hasher.finalise();
+/// assert_eq!(ripemd160.to_string(), "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MultiHasher<T: EngineSet> {
+    engines: T
+}
+
+impl<T: EngineSet> MultiHasher<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Input data into every engine
+    pub fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.engines.input(data.as_ref());
+    }
+
+    /// Input data and return the hasher so digests can be computed in a single expression
+    pub fn chain<I>(mut self, data: I) -> Self
+    where I: AsRef<[u8]> {
+        self.input(data);
+        self
+    }
+
+    /// Reset every engine
+    pub fn reset(&mut self) {
+        self.engines.reset();
+    }
+
+    /// Compute the digest of every engine
+    pub fn finalise(&mut self) -> T::Digests {
+        self.engines.finalise()
+    }
+
+    /// Compute the digest of every engine and reset them
+    pub fn finalise_reset(&mut self) -> T::Digests {
+        let digests = self.finalise();
+        self.reset();
+        digests
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: EngineSet> std::io::Write for MultiHasher<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ripemd160, Sha1, Sha256};
+
+    #[test]
+    fn multi_hasher() {
+        let mut hasher = MultiHasher::<(Sha256, Ripemd160, Sha1)>::new();
+        hasher.input(b"abcdbcdecdefdefgefghfghighijhijk");
+        hasher.input(b"ijkljklmklmnlmnomnopnopq");
+        let (sha256, ripemd160, sha1) = hasher.finalise_reset();
+        assert_eq!(sha256.to_string(), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+        assert_eq!(ripemd160.to_string(), "12a053384a9c0c88e405a06c27dcf49ada62eb2b");
+        assert_eq!(sha1.to_string(), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+
+        let (sha256,) = MultiHasher::<(Sha256,)>::new().chain(b"abc").finalise();
+        assert_eq!(sha256.to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
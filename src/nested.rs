@@ -0,0 +1,121 @@
+// Nested hash module
+//
+// Two stage hashes such as sha256d and hash160 feed the message into an inner
+// hash function and then hash the inner digest with an outer hash function:
+//      Outer(Inner(m))
+// The midstate and blocksize are those of the inner engine, since that is the
+// only engine that sees message data.
+
+use ::core::marker::PhantomData;
+use crate::{
+    error::Error,
+    HashEngine,
+    Ripemd160,
+    Sha256
+};
+
+/// A hash engine that hashes the digest of the `Inner` engine with the `Outer` engine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NestedHash<Outer: HashEngine, Inner: HashEngine> {
+    inner: Inner,
+    outer: PhantomData<Outer>
+}
+
+/// SHA256 applied twice
+pub type Sha256d = NestedHash<Sha256, Sha256>;
+/// RIPEMD160 of SHA256
+pub type Hash160 = NestedHash<Ripemd160, Sha256>;
+
+impl<Outer: HashEngine, Inner: HashEngine> Default for NestedHash<Outer, Inner> {
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            outer: PhantomData
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<Outer: HashEngine, Inner: HashEngine + zeroize::Zeroize> zeroize::Zeroize for NestedHash<Outer, Inner> {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+impl<Outer: HashEngine, Inner: HashEngine> HashEngine for NestedHash<Outer, Inner> {
+    type Digest = Outer::Digest;
+    type Midstate = Inner::Midstate;
+    const BLOCKSIZE: usize = Inner::BLOCKSIZE;
+    const OUTPUT_SIZE: usize = Outer::OUTPUT_SIZE;
+
+    fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.inner.input(data);
+    }
+
+    fn try_input<I>(&mut self, data: I) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        self.inner.try_input(data)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn midstate(&self) -> Self::Midstate {
+        self.inner.midstate()
+    }
+
+    fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), Error> {
+        self.inner.try_from_midstate(midstate)
+    }
+
+    fn pending(&self) -> &[u8] {
+        self.inner.pending()
+    }
+
+    fn bytes_hashed(&self) -> u64 {
+        self.inner.bytes_hashed()
+    }
+
+    fn finalise(&mut self) -> Self::Digest {
+        let digest = self.inner.finalise();
+        Outer::default().chain(digest).finalise()
+    }
+
+    fn wipe(&mut self) {
+        self.inner.wipe();
+    }
+}
+
+impl<Outer: HashEngine, Inner: HashEngine> ::core::fmt::Write for NestedHash<Outer, Inner> {
+    fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+        self.input(s);
+        Ok(())
+    }
+}
+
+impl<Outer: HashEngine, Inner: HashEngine> NestedHash<Outer, Inner> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_hash() {
+        let digest = Sha256d::new().chain(b"abc").finalise();
+        assert_eq!(digest.to_string(), "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358");
+
+        let mut engine = Hash160::new();
+        engine.input(b"abc");
+        assert_eq!(engine.finalise_reset().to_string(), "bb1be98c142444d7a56aa3981c3942a978e4dc33");
+        let generator = crate::Sha256Digest::from_hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap();
+        engine.input([0x02]);
+        engine.input(generator);
+        assert_eq!(engine.finalise().to_string(), "751e76e8199196d454941c45d1b3a323f1433bd6");
+    }
+}
@@ -0,0 +1,167 @@
+// Newtype module
+//
+// Macro for declaring domain specific digest types such as txids and block
+// hashes on top of the digest types of the crate's engines, so that digests
+// of different domains cannot be mixed up.
+
+
+/// Declare a strongly typed wrapper around one of the crate's digest types.
+///
+/// The wrapper inherits hex formatting and parsing, conversion to and from
+/// the wrapped digest, and serde support when the `serde` feature is enabled.
+///
+/// ```
+/// use btc_hashes::{hash_newtype, Sha256Digest};
+///
+/// hash_newtype! {
+///     /// A transaction id
+///     pub struct Txid(Sha256Digest);
+/// }
+///
+/// let txid: Txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b".parse().unwrap();
+/// assert_eq!(txid.to_hex_reversed(), "3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a");
+/// ```
+#[macro_export]
+macro_rules! hash_newtype {
+    ($(#[$attr: meta])* $vis: vis struct $name: ident($digest: ty);) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name(pub $digest);
+
+        impl $name {
+            /// Parse from a hex string in either case
+            pub fn from_hex(s: &str) -> Result<Self, $crate::HexError> {
+                <$digest>::from_hex(s).map(Self)
+            }
+
+            /// Parse from a hex string written in reverse byte order
+            pub fn from_hex_reversed(s: &str) -> Result<Self, $crate::HexError> {
+                <$digest>::from_hex_reversed(s).map(Self)
+            }
+
+            /// The wrapped digest
+            pub fn to_digest(self) -> $digest {
+                self.0
+            }
+        }
+
+        impl From<$digest> for $name {
+            fn from(digest: $digest) -> Self {
+                Self(digest)
+            }
+        }
+
+        impl From<$name> for $digest {
+            fn from(hash: $name) -> Self {
+                hash.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                self.0.as_ref()
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = $digest;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::HexError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::from_hex(s)
+            }
+        }
+
+        impl ::core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::LowerHex::fmt(&self.0, f)
+            }
+        }
+
+        impl ::core::fmt::UpperHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::UpperHex::fmt(&self.0, f)
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.0)
+            }
+        }
+
+        $crate::__hash_newtype_serde!($name, $digest);
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_serde {
+    ($name: ident, $digest: ty) => {
+        impl $crate::serde::Serialize for $name {
+            fn serialize<S: $crate::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                $crate::serde::Serialize::serialize(&self.0, s)
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $name {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                <$digest as $crate::serde::Deserialize>::deserialize(d).map(Self)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hash_newtype_serde {
+    ($name: ident, $digest: ty) => { };
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{HashEngine, Sha256, Sha256Digest, HexError};
+
+    hash_newtype! {
+        /// Test wrapper
+        struct Txid(Sha256Digest);
+    }
+
+    #[test]
+    fn newtype() {
+        let digest = Sha256::new().finalise();
+        let hash = Txid::from(digest);
+        assert_eq!(hash.to_string(), digest.to_string());
+        assert_eq!(format!("{:?}", hash), format!("Txid({})", digest));
+        assert_eq!(hash.to_string().parse::<Txid>(), Ok(hash));
+        assert_eq!(Txid::from_hex_reversed(&digest.display_backwards().to_string()), Ok(hash));
+        assert_eq!("00".parse::<Txid>(), Err(HexError::InvalidLength { expected: 64, got: 2 }));
+        assert_eq!(hash.to_digest(), digest);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn newtype_serde() {
+        let hash = Txid::from(Sha256::new().finalise());
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, "\"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\"");
+        assert_eq!(serde_json::from_str::<Txid>(&json).unwrap(), hash);
+        assert!(serde_json::from_str::<Txid>("\"e3b0\"").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+// Noise module
+//
+// The hashing side of the Noise_XK handshake used by Lightning's transport (BOLT8).
+// The handshake hash h accumulates every message with SHA256, and the chaining key
+// ck is ratcheted with a two output HKDF each time a Diffie-Hellman result is mixed in.
+
+use crate::{
+    HashEngine,
+    Hkdf,
+    Sha256
+};
+
+/// Noise protocol name hashed into the initial chaining key
+pub const NOISE_PROTOCOL_NAME: &[u8] = b"Noise_XK_secp256k1_ChaChaPoly_SHA256";
+
+/// Prologue mixed into the handshake hash by Lightning nodes
+pub const LIGHTNING_PROLOGUE: &[u8] = b"lightning";
+
+/// Compute the initial chaining key and handshake hash for a prologue
+pub fn noise_initial_state<P: AsRef<[u8]>>(prologue: P) -> ([u8; 32], [u8; 32]) {
+    let ck = Sha256::new().chain(NOISE_PROTOCOL_NAME).finalise().0;
+    (ck, mix_hash(&ck, prologue))
+}
+
+/// Mix data into the handshake hash, h = SHA256(h || data)
+pub fn mix_hash<D: AsRef<[u8]>>(h: &[u8; 32], data: D) -> [u8; 32] {
+    Sha256::new().chain(h).chain(data).finalise().0
+}
+
+/// Mix keying material into the chaining key, returning the new chaining key and the derived key
+pub fn mix_key<I: AsRef<[u8]>>(ck: &[u8; 32], ikm: I) -> ([u8; 32], [u8; 32]) {
+    let mut okm = [0u8; 64];
+    Hkdf::<Sha256>::extract(ck, ikm).expand([], &mut okm).unwrap();
+
+    let mut ck = [0u8; 32];
+    let mut key = [0u8; 32];
+    ck.copy_from_slice(&okm[..32]);
+    key.copy_from_slice(&okm[32..]);
+    (ck, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn bolt8_act_one() {
+        // https://github.com/lightning/bolts/blob/master/08-transport.md#initiator-tests
+        let (ck, h) = noise_initial_state(LIGHTNING_PROLOGUE);
+        assert_eq!(Sha256Digest(ck).to_string(), "2640f52eebcd9e882958951c794250eedb28002c05d7dc2ea0f195406042caf1");
+
+        let h = mix_hash(&h, hex::<33>("028d7500dd4c12685d1f568b4c2b5048e8534b873319f3a8daa612b469132ec7f7"));
+        assert_eq!(Sha256Digest(h).to_string(), "8401b3fdcaaa710b5405400536a3d5fd7792fe8e7fe29cd8b687216fe323ecbd");
+
+        let h = mix_hash(&h, hex::<33>("036360e856310ce5d294e8be33fc807077dc56ac80d95d9cd4ddbd21325eff73f7"));
+        assert_eq!(Sha256Digest(h).to_string(), "9e0e7de8bb75554f21db034633de04be41a2b8a18da7a319a03c803bf02b396c");
+
+        let (ck, temp_k1) = mix_key(&ck, hex::<32>("1e2fb3c8fe8fb9f262f649f64d26ecf0f2c0a805a767cf02dc2d77a6ef1fdcc3"));
+        assert_eq!(Sha256Digest(ck).to_string(), "b61ec1191326fa240decc9564369dbb3ae2b34341d1e11ad64ed89f89180582f");
+        assert_eq!(Sha256Digest(temp_k1).to_string(), "e68f69b7f096d7917245f5e5cf8ae1595febe4d4644333c99f9c4a1282031c9f");
+    }
+}
@@ -0,0 +1,65 @@
+// Onion module
+//
+// Tor v3 onion addresses are the base32 encoding of the service's ed25519 public
+// key, a two byte checksum and the version byte:
+//      CHECKSUM = SHA3-256(".onion checksum" || PUBKEY || VERSION)[..2]
+//      ADDRESS = base32(PUBKEY || CHECKSUM || VERSION) + ".onion"
+//
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use crate::{
+    HashEngine,
+    Sha3_256
+};
+
+/// Version byte of v3 onion addresses
+pub const ONION_VERSION: u8 = 3;
+
+#[cfg(feature = "alloc")]
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Compute the checksum of a v3 onion address for an ed25519 public key
+pub fn onion_checksum(pubkey: &[u8; 32]) -> [u8; 2] {
+    let digest = Sha3_256::new()
+        .chain(b".onion checksum")
+        .chain(pubkey)
+        .chain([ONION_VERSION])
+        .finalise();
+    [digest.0[0], digest.0[1]]
+}
+
+/// Encode the v3 onion address of an ed25519 public key, including the ".onion" suffix
+#[cfg(feature = "alloc")]
+pub fn onion_address(pubkey: &[u8; 32]) -> String {
+    let mut raw = [0u8; 35];
+    raw[..32].copy_from_slice(pubkey);
+    raw[32..34].copy_from_slice(&onion_checksum(pubkey));
+    raw[34] = ONION_VERSION;
+
+    // 35 bytes is exactly 56 base32 characters, so there is never any padding
+    let mut address = String::with_capacity(62);
+    for chunk in raw.chunks_exact(5) {
+        let bits = chunk.iter().fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+        for i in (0..8).rev() {
+            address.push(BASE32_ALPHABET[(bits >> (i * 5)) as usize & 31] as char);
+        }
+    }
+    address.push_str(".onion");
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn duckduckgo_onion() {
+        let pubkey = hex("1d04a1d04a338c6e6ae970bfabee49049d6702250984ca950c01673f4ec034ad");
+
+        assert_eq!(onion_checksum(&pubkey), [0x91, 0x64]);
+        #[cfg(feature = "alloc")]
+        assert_eq!(onion_address(&pubkey), "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion");
+    }
+}
@@ -0,0 +1,185 @@
+// One-time password module
+//
+// HOTP (RFC 4226) and TOTP (RFC 6238) built on top of Hmac.
+// Both are generic over the underlying hash engine so SHA1, SHA256 and SHA512
+// variants can be created with Hotp::<Sha1>, Hotp::<Sha256> etc.
+
+
+use crate::{
+    core::{
+        ct_eq,
+        HashEngine,
+        KeyBasedHashEngine
+    },
+    hmac::Hmac
+};
+use ::core::marker::PhantomData;
+use alloc::vec::Vec;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+#[cfg(feature = "std")]
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH
+};
+
+/// Counter based one-time password generator
+pub struct Hotp<T: HashEngine+Copy> {
+    hash: PhantomData<T>,
+    key: Vec<u8>,
+    digits: u32
+}
+
+impl<T: HashEngine+Copy> Hotp<T> {
+    /// Create a new HOTP generator with the shared secret and the amount of digits (usually 6 or 8).
+    /// 
+    /// Panics if the amount of digits is not between 1 and 9.
+    pub fn new<I>(key: I, digits: u32) -> Self
+    where I: AsRef<[u8]> {
+        assert!((1..=9).contains(&digits), "digits must be between 1 and 9");
+
+        Self {
+            hash: PhantomData::<T>,
+            key: key.as_ref().to_vec(),
+            digits
+        }
+    }
+
+    /// Generate the one-time password for the given counter value
+    pub fn generate(&self, counter: u64) -> u32 {
+        let mut engine: Hmac<T> = Hmac::new_with_key(&self.key);
+        engine.input(counter.to_be_bytes());
+        let hs = engine.finalise();
+        let hs = hs.as_ref();
+
+        // Dynamic truncation
+        // The low 4 bits of the last byte select the offset of the 31 bit value.
+        let offset = (hs[hs.len()-1] & 0x0f) as usize;
+        let bin_code = u32::from_be_bytes([hs[offset], hs[offset+1], hs[offset+2], hs[offset+3]]) & 0x7fffffff;
+
+        bin_code % 10u32.pow(self.digits)
+    }
+
+    /// Check a one-time password against the given counter value, in constant time
+    pub fn verify(&self, code: u32, counter: u64) -> bool {
+        ct_eq(&self.generate(counter).to_be_bytes(), &code.to_be_bytes())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: HashEngine+Copy> Zeroize for Hotp<T> {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+/// Wipe the shared secret when the generator is dropped
+#[cfg(feature = "zeroize")]
+impl<T: HashEngine+Copy> Drop for Hotp<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Time based one-time password generator
+pub struct Totp<T: HashEngine+Copy> {
+    hotp: Hotp<T>,
+    step: u64,
+    t0: u64
+}
+
+impl<T: HashEngine+Copy> Totp<T> {
+    /// Create a new TOTP generator with the shared secret, amount of digits and time step in seconds (usually 30).
+    /// 
+    /// Panics if the amount of digits is not between 1 and 9 or the time step is zero.
+    pub fn new<I>(key: I, digits: u32, step: u64) -> Self
+    where I: AsRef<[u8]> {
+        assert!(step > 0, "time step must be at least one second");
+
+        Self {
+            hotp: Hotp::new(key, digits),
+            step,
+            t0: 0
+        }
+    }
+
+    /// Set the unix time to start counting time steps from (T0)
+    pub fn t0(&mut self, t0: u64) {
+        self.t0 = t0;
+    }
+
+    /// Generate the one-time password for the given unix time in seconds
+    pub fn generate(&self, time: u64) -> u32 {
+        self.hotp.generate(time.saturating_sub(self.t0) / self.step)
+    }
+
+    /// Generate the one-time password for the current system time
+    #[cfg(feature = "std")]
+    pub fn now(&self) -> u32 {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time is before the unix epoch")
+            .as_secs();
+
+        self.generate(time)
+    }
+
+    /// Check a one-time password against the given unix time in seconds, in constant time
+    pub fn verify(&self, code: u32, time: u64) -> bool {
+        self.hotp.verify(code, time.saturating_sub(self.t0) / self.step)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Sha1, Sha256, Sha512
+    };
+
+    #[test]
+    fn hotp() {
+        // RFC 4226 Appendix D
+        let hotp: Hotp<Sha1> = Hotp::new(b"12345678901234567890", 6);
+        let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp.generate(counter as u64), *code);
+            assert!(hotp.verify(*code, counter as u64));
+            assert!(!hotp.verify(*code, counter as u64 + 1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "digits must be between 1 and 9")]
+    fn hotp_digits() {
+        let _: Hotp<Sha1> = Hotp::new(b"12345678901234567890", 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "time step must be at least one second")]
+    fn totp_step() {
+        let _: Totp<Sha1> = Totp::new(b"12345678901234567890", 6, 0);
+    }
+
+    #[test]
+    fn totp() {
+        // RFC 6238 Appendix B
+        let sha1: Totp<Sha1> = Totp::new(b"12345678901234567890", 8, 30);
+        let sha256: Totp<Sha256> = Totp::new(b"12345678901234567890123456789012", 8, 30);
+        let sha512: Totp<Sha512> = Totp::new(b"1234567890123456789012345678901234567890123456789012345678901234", 8, 30);
+        let cases: Vec<(u64, u32, u32, u32)> = vec![
+            (59, 94287082, 46119246, 90693936),
+            (1111111109, 7081804, 68084774, 25091201),
+            (2000000000, 69279037, 90698825, 38618901)
+        ];
+
+        for case in cases {
+            assert_eq!(sha1.generate(case.0), case.1);
+            assert_eq!(sha256.generate(case.0), case.2);
+            assert_eq!(sha512.generate(case.0), case.3);
+            assert!(sha1.verify(case.1, case.0));
+            assert!(!sha1.verify(case.1 ^ 1, case.0));
+        }
+    }
+}
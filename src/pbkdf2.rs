@@ -1,20 +1,48 @@
+// PBKDF2 module
+//
+// Password based key derivation (RFC 8018). The owned PBKDF2 engine, its builder
+// and block iterator need the alloc feature. The pbkdf2() functions derive from
+// borrowed slices and never allocate.
+
 use crate::{
+    error::Error,
     HashEngine,
-    KeyBasedHashEngine
+    KeyBasedHashEngine,
+    Hmac,
+    Sha256,
+    Sha512
+};
+#[cfg(feature = "alloc")]
+use crate::core::{
+    ct_eq,
+    Kdf
 };
-use std::{
+#[cfg(feature = "alloc")]
+use ::core::{
     marker::PhantomData,
     convert::TryInto
 };
+#[cfg(feature = "alloc")]
+use alloc::{
+    vec,
+    vec::Vec
+};
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+use zeroize::Zeroize;
 
-pub struct PBKDF2<T: KeyBasedHashEngine> {
+#[cfg(feature = "alloc")]
+pub(crate) const MIN_VERIFY_LENGTH: usize = 14; // NIST SP 800-132 requires derived keys of at least 112 bits
+
+#[cfg(feature = "alloc")]
+pub struct PBKDF2<T: KeyBasedHashEngine+Clone> {
     hash: PhantomData<T>,
     password: Vec<u8>,
     salt: Vec<u8>,
     iter: usize
 }
 
-impl<T: KeyBasedHashEngine> PBKDF2<T> {
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> PBKDF2<T> {
     /// Set how many iterations will be used
     pub fn iter(&mut self, count: usize) {
         self.iter = count;
@@ -22,83 +50,232 @@ impl<T: KeyBasedHashEngine> PBKDF2<T> {
 
     pub fn input_salt<I>(&mut self, salt: I)
     where I: AsRef<[u8]> {
-        self.salt.extend(salt.as_ref());
-    }
-
-    // F(Password, Salt, c, i) = U1 ^ U2 ^ ⋯ ^ Uc
-    fn f_compression(&self) -> T::Digest {
-        let mut prf = T::new_with_key(&self.password); // Input the password to be compressed into the hash engine as the key
-        prf.input(&self.salt);                    // Input the salt as the hash engine's message
-        prf.input(1u32.to_be_bytes());            // Input '1' to start off
-        let mut u: Vec<T::Digest> = vec![prf.finalise()];
-        drop(prf);
-        for i in 1..self.iter {                        // For each iteration, hash the previous hash with the password
-            let mut prf = T::new_with_key(&self.password);
-            //prf.key(&self.password);
-            prf.input(&u[i-1]);
-            u.push(prf.finalise());
-            drop(prf)
+        extend_wiped(&mut self.salt, salt.as_ref());
+    }
+
+    /// Clear the inputted salt, keeping the password and iteration count
+    pub fn clear_salt(&mut self) {
+        #[cfg(feature = "zeroize")]
+        self.salt.zeroize();
+        self.salt = vec![];
+    }
+
+    /// Clear the inputted password, keeping the salt and iteration count
+    pub fn clear_password(&mut self) {
+        #[cfg(feature = "zeroize")]
+        self.password.zeroize();
+        self.password = vec![];
+    }
+
+    /// Iterate over the derived key blocks T1, T2, ... lazily.
+    /// 
+    /// Fails if the iteration count is zero. The iterator ends after (2^32 - 1) blocks.
+    pub fn blocks(&self) -> Result<PBKDF2Blocks<'_, T>, Error> {
+        // Blocks are only computed as they are taken, so there is no output length to check yet
+        check_params::<T>(self.iter, 0)?;
+        Ok(PBKDF2Blocks {
+            engine: self,
+            index: 1
+        })
+    }
+
+    /// Derive a key with the same length as the expected key and compare them in constant time.
+    /// 
+    /// The key is derived and compared one block at a time, so only one block is held.
+    /// Keys shorter than 14 bytes never verify, as they would match almost any password,
+    /// and neither do keys when the parameters are invalid.
+    pub fn verify(&self, expected: &[u8]) -> bool {
+        if expected.len() < MIN_VERIFY_LENGTH || check_params::<T>(self.iter, expected.len()).is_err() {
+            return false;
         }
 
-        while u.len() != 1 {                // XOR each of the hashes together recursively until one remains
-            let xor: Result<_, _> = u[0]
-                        .into_iter()
-                        .zip(u[1].into_iter())
-                        .map(|(x, y)| x^y)
-                        .collect::<Vec<u8>>()
-                        .try_into();
-                        
-            // For some stupid reason, I cannot call .expect() after converting Vec<u8> into T::Digest using try_into()
-            // Spent a while looking for a solution but the closest I came to was a stale github issue on the Rust compiler
-            // repository.
-            // Calling expect() just looks nicer than having a ugly match block so it does not matter.
-            u[0] = match xor {
-                Ok(x) => x,
-                _ => panic!("bad xor")
-            };
-            u.remove(1);
+        let prf = T::new_with_key(&self.password);
+        let mut block = vec![0u8; T::OUTPUT_SIZE];
+        let mut equal = true;
+        for (index, chunk) in (1..=u32::MAX).zip(expected.chunks(T::OUTPUT_SIZE)) {
+            f::<T>(&prf, &self.salt, self.iter, index, &mut block[..chunk.len()]);
+            equal &= ct_eq(&block[..chunk.len()], chunk);
         }
+        wipe(&mut block);
+
+        equal
+    }
+
+    /// Derive a key of arbitrary length into the output buffer, computing each block in parallel.
+    /// 
+    /// Produces the same output as Kdf::derive() and fails on the same parameters. Each Ti is
+    /// independent of the others so long outputs are split across the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn par_derive(&self, out: &mut [u8]) -> Result<(), Error>
+    where T: Send + Sync {
+        use rayon::prelude::*;
+
+        check_params::<T>(self.iter, out.len())?;
+        // The block count was bounded above, so each index fits in 32 bits
+        let prf = T::new_with_key(&self.password);
+        out.par_chunks_mut(T::OUTPUT_SIZE)
+            .enumerate()
+            .for_each(|(i, chunk)| f::<T>(&prf, &self.salt, self.iter, i as u32 + 1, chunk));
+        Ok(())
+    }
+
+    // Ti = F(Password, Salt, c, i)
+    fn f_compression(&self, index: u32) -> T::Digest {
+        let prf = T::new_with_key(&self.password);
+        let mut block = vec![0u8; T::OUTPUT_SIZE];
+        f::<T>(&prf, &self.salt, self.iter, index, &mut block);
 
-        u[0]
+        block.try_into().ok().expect("block is digest sized")
     }
 }
 
-impl<T: KeyBasedHashEngine> HashEngine for PBKDF2<T> {
+/// Derive a key from a borrowed password and salt into the output buffer.
+/// 
+/// Unlike the PBKDF2 engine, which owns its password and salt, this never allocates
+/// and so can be used on targets without a heap. Fails if the iteration count is zero
+/// or the output is longer than (2^32 - 1) blocks.
+pub fn pbkdf2<T: KeyBasedHashEngine+Clone>(password: &[u8], salt: &[u8], iterations: usize, out: &mut [u8]) -> Result<(), Error> {
+    check_params::<T>(iterations, out.len())?;
+    derive_blocks(&T::new_with_key(password), salt, iterations, out);
+    Ok(())
+}
+
+// The iteration count must be at least one and dkLen must not be larger than (2^32 - 1) * hLen.
+// Every entry point that derives a key checks its parameters here first.
+fn check_params<T: HashEngine>(iterations: usize, dklen: usize) -> Result<(), Error> {
+    if iterations == 0 {
+        return Err(Error::InvalidIterationCount);
+    }
+    if (dklen as u64) > (u32::MAX as u64) * (T::OUTPUT_SIZE as u64) {
+        return Err(Error::DerivedKeyTooLong);
+    }
+
+    Ok(())
+}
+
+// DK = T1 + T2 + ⋯ + Tdklen/hlen, each block is written straight into its chunk of the output.
+// The keyed engine is only primed once and then cloned for each block and iteration.
+fn derive_blocks<T: KeyBasedHashEngine+Clone>(prf: &T, salt: &[u8], iterations: usize, out: &mut [u8]) {
+    for (index, chunk) in (1..=u32::MAX).zip(out.chunks_mut(T::OUTPUT_SIZE)) {
+        f::<T>(prf, salt, iterations, index, chunk);
+    }
+}
+
+// F(Password, Salt, c, i) = U1 ^ U2 ^ ⋯ ^ Uc
+// The keyed PRF is passed in and the leading bytes of the result are written into out,
+// which must not be longer than the PRF's output.
+fn f<T: KeyBasedHashEngine+Clone>(prf: &T, salt: &[u8], iter: usize, index: u32, out: &mut [u8]) {
+    let mut engine = prf.clone();
+    engine.input(salt);                       // Input the salt as the hash engine's message
+    engine.input(index.to_be_bytes());        // Input the block index (starting from '1')
+    let mut u: T::Digest = engine.finalise();
+    out.copy_from_slice(&u.as_ref()[..out.len()]);
+    for _ in 1..iter {                        // For each iteration, hash the previous hash with the password
+        let mut engine = prf.clone();
+        engine.input(u);
+        u = engine.finalise();
+        out.iter_mut()                        // XOR each of the hashes together as they are computed
+            .zip(u)
+            .for_each(|(x, y)| *x ^= y);
+    }
+}
+
+// Append to a buffer holding a password or salt. When the buffer has to grow, the data is
+// moved into an allocation of exactly the new length and the old allocation is wiped, so
+// no unwiped copies are left behind by the reallocation.
+#[cfg(feature = "alloc")]
+fn extend_wiped(buffer: &mut Vec<u8>, data: &[u8]) {
+    #[cfg(feature = "zeroize")]
+    if buffer.capacity() - buffer.len() < data.len() {
+        let mut grown = Vec::with_capacity(buffer.len() + data.len());
+        grown.extend_from_slice(buffer);
+        buffer.zeroize();
+        *buffer = grown;
+    }
+
+    buffer.extend_from_slice(data);
+}
+
+// Clear a block buffer once it is no longer needed
+#[cfg(feature = "alloc")]
+fn wipe(block: &mut [u8]) {
+    #[cfg(feature = "zeroize")]
+    block.zeroize();
+    #[cfg(not(feature = "zeroize"))]
+    block.fill(0);
+}
+
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> HashEngine for PBKDF2<T> {
     type Digest = T::Digest;
     type Midstate = T::Midstate;
     const BLOCKSIZE: usize = T::BLOCKSIZE;
+    const OUTPUT_SIZE: usize = T::OUTPUT_SIZE;
 
     /// Input the password to be hashed
     fn input<I>(&mut self, data: I)
     where I: AsRef<[u8]> {
-        self.password.extend(data.as_ref());
+        extend_wiped(&mut self.password, data.as_ref());
     }
 
-    /// Reset the inputted password, salt and iteration count
+    /// Reset the inputted password and salt.
+    /// The iteration count is kept until changed with iter().
     fn reset(&mut self) {
-        self.password = vec![];
-        self.salt = vec![];
-        self.iter = 1;
+        self.clear_password();
+        self.clear_salt();
     }
 
+    /// The password is only buffered until the key is derived, so nothing is ever
+    /// processed into a midstate and this is always the PRF's unkeyed midstate.
     fn midstate(&self) -> Self::Midstate {
-        unimplemented!("PBKDF2 has no midstate");
+        T::default().midstate()
+    }
+
+    /// Every PBKDF2 midstate is the PRF's unkeyed midstate, so restoring one clears the
+    /// password. The salt and iteration count are kept.
+    fn try_from_midstate(&mut self, _midstate: Self::Midstate) -> Result<(), Error> {
+        self.clear_password();
+        Ok(())
+    }
+
+    /// Always empty. The password is buffered until the key is derived, but it is never
+    /// exposed through the generic engine API, so a PBKDF2 engine cannot be captured and resumed.
+    fn pending(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Whether no password has been input, as the midstate never captures it
+    fn is_block_aligned(&self) -> bool {
+        self.password.is_empty()
     }
 
-    fn from_midstate(&mut self, _midstate: Self::Midstate, _length: usize) {
-        unimplemented!("PBKDF2 has no midstate");
+    /// Length of the password input so far
+    fn bytes_hashed(&self) -> u64 {
+        self.password.len() as u64
     }
 
+    /// Derive a key the length of the PRF's output.
+    /// 
+    /// Panics if the iteration count is zero, see Kdf::derive().
     fn finalise(&mut self) -> Self::Digest {
         // DK = T1 + T2 + ⋯ + Tdklen/hlen
         // Ti = F(Password, Salt, c, i)
         // Since dklen and hlen are the same for Bitcoin, only one round of F() needs to be run.
-        
-        Self::f_compression(&self)
+        // Use Kdf::derive() for other output lengths.
+        if let Err(e) = check_params::<T>(self.iter, T::OUTPUT_SIZE) {
+            panic!("{}", e);
+        }
+
+        Self::f_compression(self, 1)
+    }
+
+    fn wipe(&mut self) {
+        self.reset();
     }
 }
 
-impl<T: KeyBasedHashEngine> Default for PBKDF2<T> {
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> Default for PBKDF2<T> {
     fn default() -> Self {
         Self {
             hash: PhantomData::<T>,
@@ -109,7 +286,24 @@ impl<T: KeyBasedHashEngine> Default for PBKDF2<T> {
     }
 }
 
-impl<T: KeyBasedHashEngine> PBKDF2<T> {
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+impl<T: KeyBasedHashEngine+Clone> Zeroize for PBKDF2<T> {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.salt.zeroize();
+    }
+}
+
+/// Wipe the password and salt when the engine is dropped
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+impl<T: KeyBasedHashEngine+Clone> Drop for PBKDF2<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> PBKDF2<T> {
     /// Create a new PBKDF2 hasher with the parameter as salt.
     pub fn new<I>(salt: I) -> Self
     where I: AsRef<[u8]> {        
@@ -120,16 +314,147 @@ impl<T: KeyBasedHashEngine> PBKDF2<T> {
             iter: 1
         }
     }
+
+    /// Create a builder to configure the password, salt, iteration count and output length.
+    pub fn builder() -> PBKDF2Builder<T> {
+        PBKDF2Builder::default()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> Kdf for PBKDF2<T> {
+    /// Derive a key of arbitrary length into the output buffer.
+    /// 
+    /// DK = T1 + T2 + ⋯ + Tdklen/hlen where the final block is truncated to fit.
+    /// Fails if the iteration count is zero or the output is longer than (2^32 - 1) blocks.
+    fn derive(&self, out: &mut [u8]) -> Result<(), Error> {
+        check_params::<T>(self.iter, out.len())?;
+        derive_blocks(&T::new_with_key(&self.password), &self.salt, self.iter, out);
+        Ok(())
+    }
+}
+
+/// Iterator over PBKDF2 derived key blocks
+/// 
+/// Each item is the output of F(Password, Salt, c, i) for the next block index i.
+#[cfg(feature = "alloc")]
+pub struct PBKDF2Blocks<'a, T: KeyBasedHashEngine+Clone> {
+    engine: &'a PBKDF2<T>,
+    index: u32
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: KeyBasedHashEngine+Clone> Iterator for PBKDF2Blocks<'a, T> {
+    type Item = T::Digest;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The block index is a 32 bit counter starting from 1, so there are at most 2^32-1 blocks.
+        if self.index == 0 {
+            return None;
+        }
+
+        let block = self.engine.f_compression(self.index);
+        self.index = self.index.wrapping_add(1);
+        Some(block)
+    }
+}
+
+/// Fluent builder for PBKDF2 derivations
+/// 
+/// PBKDF2::<Hmac<Sha512>>::builder().password(p).salt(s).iterations(n).dklen(l).derive()
+#[cfg(feature = "alloc")]
+pub struct PBKDF2Builder<T: KeyBasedHashEngine+Clone> {
+    engine: PBKDF2<T>,
+    dklen: Option<usize>
+}
+
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> Default for PBKDF2Builder<T> {
+    fn default() -> Self {
+        Self {
+            engine: PBKDF2::default(),
+            dklen: None
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: KeyBasedHashEngine+Clone> PBKDF2Builder<T> {
+    /// Set the password
+    pub fn password<I>(mut self, password: I) -> Self
+    where I: AsRef<[u8]> {
+        self.engine.clear_password();
+        extend_wiped(&mut self.engine.password, password.as_ref());
+        self
+    }
+
+    /// Set the salt
+    pub fn salt<I>(mut self, salt: I) -> Self
+    where I: AsRef<[u8]> {
+        self.engine.clear_salt();
+        extend_wiped(&mut self.engine.salt, salt.as_ref());
+        self
+    }
+
+    /// Set the iteration count
+    pub fn iterations(mut self, count: usize) -> Self {
+        self.engine.iter = count;
+        self
+    }
+
+    /// Set the length of the derived key in bytes.
+    /// Defaults to the output length of the PRF.
+    pub fn dklen(mut self, len: usize) -> Self {
+        self.dklen = Some(len);
+        self
+    }
+
+    /// Run the derivation, failing if the iteration count is zero or the key is too long
+    pub fn derive(self) -> Result<Vec<u8>, Error> {
+        let mut dk = vec![0; self.dklen.unwrap_or(T::OUTPUT_SIZE)];
+        Kdf::derive(&self.engine, &mut dk)?;
+        Ok(dk)
+    }
+
+    /// Return the configured PBKDF2 engine
+    pub fn build(self) -> PBKDF2<T> {
+        self.engine
+    }
+}
+
+/// PBKDF2 using HMAC-SHA256 as the PRF
+#[cfg(feature = "alloc")]
+pub type Pbkdf2HmacSha256 = PBKDF2<Hmac<Sha256>>;
+/// PBKDF2 using HMAC-SHA512 as the PRF (BIP39)
+#[cfg(feature = "alloc")]
+pub type Pbkdf2HmacSha512 = PBKDF2<Hmac<Sha512>>;
+
+/// Derive a key using PBKDF2-HMAC-SHA256
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: usize, out: &mut [u8]) -> Result<(), Error> {
+    pbkdf2::<Hmac<Sha256>>(password, salt, iterations, out)
+}
+
+/// Derive a key using PBKDF2-HMAC-SHA512
+/// 
+/// BIP39 seeds are derived with 2048 iterations into a 64 byte output.
+pub fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], iterations: usize, out: &mut [u8]) -> Result<(), Error> {
+    pbkdf2::<Hmac<Sha512>>(password, salt, iterations, out)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::hex;
+    use crate::{
+        Hmac, Sha1
+    };
+    #[cfg(feature = "alloc")]
     use crate::{
-        Hmac, Sha512, Sha384
+        Sha512, Sha384
     };
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn pbkdf2_hmac_sha512() {
         // Test cases from: https://stackoverflow.com/questions/15593184/pbkdf2-hmac-sha-512-test-vectors
         let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
@@ -137,17 +462,17 @@ mod tests {
 
         // 1 iteration
         e.iter(1);
-        let digest = e.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        let digest = e.finalise().to_string();
         assert_eq!(digest, "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce");
 
         // 2 iterations
         e.iter(2);
-        let digest = e.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        let digest = e.finalise().to_string();
         assert_eq!(digest, "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
     
         // 4096 iterations
         e.iter(4096);
-        let digest = e.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        let digest = e.finalise().to_string();
         assert_eq!(digest, "d197b1b33db0143e018b12f3d1d1479e6cdebdcc97c5c0f87f6902e072f457b5143f30602641b3d55cd335988cb36b84376060ecd532e039b742a239434af2d5");
 
         // update data
@@ -155,11 +480,236 @@ mod tests {
         e.input(b"passwordPASSWORDpassword");
         e.input_salt(b"saltSALTsaltSALTsaltSALTsaltSALTsalt");
         e.iter(4096);
-        let digest = e.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        let digest = e.finalise().to_string();
         assert_eq!(digest, "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd9532fa33e0f75aefe30225c583a186cd82bd4daea9724a3d3b8");
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_dklen() {
+        // RFC 6070
+        let mut e = PBKDF2::<Hmac<Sha1>>::new(b"saltSALTsaltSALTsaltSALTsaltSALTsalt");
+        e.input(b"passwordPASSWORDpassword");
+        e.iter(4096);
+        let mut dk = [0u8; 25];
+        e.derive(&mut dk).unwrap();
+        assert_eq!(dk, hex("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"));
+
+        // Output longer than a single block
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
+        e.input(b"password");
+        e.iter(2);
+        let mut dk = [0u8; 100];
+        e.derive(&mut dk).unwrap();
+        assert_eq!(dk, hex("e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e473e311ad827b68945f4e2dddb204c78e40e2495141e411cd272d020640d673cd34aa29f"));
+
+        // Zero iterations are rejected rather than run as one
+        e.iter(0);
+        assert_eq!(e.derive(&mut [0; 100]), Err(Error::InvalidIterationCount));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_builder() {
+        let dk = PBKDF2::<Hmac<Sha512>>::builder()
+            .password(b"password")
+            .salt(b"salt")
+            .iterations(2)
+            .derive()
+            .unwrap();
+        assert_eq!(dk, hex::<64>("e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e"));
+
+        let dk = PBKDF2::<Hmac<Sha1>>::builder()
+            .password(b"passwordPASSWORDpassword")
+            .salt(b"saltSALTsaltSALTsaltSALTsaltSALTsalt")
+            .iterations(4096)
+            .dklen(25)
+            .derive()
+            .unwrap();
+        assert_eq!(dk, hex::<25>("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"));
+
+        // Zero iterations are rejected rather than run as one
+        let dk = PBKDF2::<Hmac<Sha512>>::builder()
+            .password(b"password")
+            .salt(b"salt")
+            .iterations(0)
+            .derive();
+        assert_eq!(dk, Err(Error::InvalidIterationCount));
+    }
+
+    #[test]
+    fn pbkdf2_one_shot() {
+        // BIP39 test vector with passphrase "TREZOR"
+        let mut seed = [0u8; 64];
+        super::pbkdf2_hmac_sha512(
+            b"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            b"mnemonicTREZOR",
+            2048,
+            &mut seed
+        ).unwrap();
+        assert_eq!(seed, hex("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"));
+
+        // RFC 7914 section 11
+        let mut dk = [0u8; 64];
+        pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut dk).unwrap();
+        assert_eq!(dk, hex("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_rotating_salt() {
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"sa");
+        e.input(b"password");
+        e.iter(2);
+
+        // Swap the salt without touching the password or iteration count
+        e.clear_salt();
+        e.input_salt(b"salt");
+        let digest = e.finalise().to_string();
+        assert_eq!(digest, "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
+
+        // Iteration count persists across resets
+        e.reset();
+        e.input(b"password");
+        e.input_salt(b"salt");
+        let digest = e.finalise().to_string();
+        assert_eq!(digest, "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
+
+        // Swap the password
+        e.clear_password();
+        e.input(b"passwordPASSWORDpassword");
+        e.clear_salt();
+        e.input_salt(b"saltSALTsaltSALTsaltSALTsaltSALTsalt");
+        e.iter(4096);
+        let digest = e.finalise().to_string();
+        assert_eq!(digest, "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd9532fa33e0f75aefe30225c583a186cd82bd4daea9724a3d3b8");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_blocks() {
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
+        e.input(b"password");
+        e.iter(2);
+        let dk = e.blocks()
+            .unwrap()
+            .take(2)
+            .flatten()
+            .take(100)
+            .collect::<Vec<u8>>();
+        assert_eq!(dk, hex::<100>("e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e473e311ad827b68945f4e2dddb204c78e40e2495141e411cd272d020640d673cd34aa29f"));
+
+        e.iter(0);
+        assert!(e.blocks().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    #[should_panic(expected = "iteration count must be at least one")]
+    fn pbkdf2_finalise_zero_iterations() {
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
+        e.input(b"password");
+        e.iter(0);
+        e.finalise();
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_alias() {
+        use crate::prelude::*;
+
+        let mut e = Pbkdf2HmacSha512::new(b"salt");
+        e.input(b"password");
+        e.iter(2);
+        let digest = e.finalise().to_string();
+        assert_eq!(digest, "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_kdf_trait() {
+        fn derive_key<K: Kdf>(kdf: &K) -> Result<[u8; 25], Error> {
+            let mut key = [0u8; 25];
+            kdf.derive(&mut key)?;
+            Ok(key)
+        }
+
+        let mut e = PBKDF2::<Hmac<Sha1>>::new(b"saltSALTsaltSALTsaltSALTsaltSALTsalt");
+        e.input(b"passwordPASSWORDpassword");
+        e.iter(4096);
+        assert_eq!(derive_key(&e).unwrap(), hex("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"));
+
+        e.iter(0);
+        assert_eq!(derive_key(&e), Err(Error::InvalidIterationCount));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_verify() {
+        let mut e = PBKDF2::<Hmac<Sha1>>::new(b"saltSALTsaltSALTsaltSALTsaltSALTsalt");
+        e.input(b"passwordPASSWORDpassword");
+        e.iter(4096);
+        let mut dk: [u8; 25] = hex("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038");
+        assert!(e.verify(&dk));
+        dk[24] ^= 0x01;
+        assert!(!e.verify(&dk));
+
+        // Empty and short keys never verify
+        assert!(!e.verify(&[]));
+        assert!(!e.verify(&dk[..8]));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pbkdf2_midstate() {
+        // Generic code cannot read the password back out of the engine
+        fn resume<T: HashEngine>(engine: &T) -> T {
+            let mut resumed = T::default();
+            resumed.from_midstate_with_buffer(engine.midstate(), engine.pending());
+            resumed
+        }
+
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
+        e.input(b"pass");
+        e.input(b"word");
+        e.iter(2);
+        assert!(e.pending().is_empty());
+        assert!(!e.is_block_aligned());
+
+        let mut resumed = resume(&e);
+        resumed.input_salt(b"salt");
+        resumed.iter(2);
+        assert!(resumed.is_block_aligned());
+        assert_ne!(resumed.finalise(), e.finalise());
+    }
+
+    #[test]
+    fn pbkdf2_borrowed() {
+        // RFC 6070, derived without the owned password and salt buffers
+        let mut dk = [0u8; 25];
+        super::pbkdf2::<Hmac<Sha1>>(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, &mut dk).unwrap();
+        assert_eq!(dk, hex("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"));
+
+        // Zero iterations are rejected rather than run as one
+        assert_eq!(super::pbkdf2::<Hmac<Sha1>>(b"password", b"salt", 0, &mut [0; 25]), Err(Error::InvalidIterationCount));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn pbkdf2_par_derive() {
+        let mut e = PBKDF2::<Hmac<Sha512>>::new(b"salt");
+        e.input(b"password");
+        e.iter(2);
+        let mut dk = [0u8; 100];
+        e.par_derive(&mut dk).unwrap();
+        assert_eq!(dk, hex("e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53cf76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e473e311ad827b68945f4e2dddb204c78e40e2495141e411cd272d020640d673cd34aa29f"));
+
+        e.iter(0);
+        assert_eq!(e.par_derive(&mut [0; 100]), Err(Error::InvalidIterationCount));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
     #[ignore]
     // great test to run for speed benching
     fn pbkdf2_speed_test() {
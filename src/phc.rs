@@ -0,0 +1,283 @@
+// PHC string format module
+//
+// Serialization and parsing of PBKDF2 password hashes in the PHC string format
+//      $pbkdf2-sha256$i=<iterations>$<salt>$<hash>
+// where the salt and hash are encoded using base64 without padding.
+
+
+use crate::{
+    core::ct_eq,
+    pbkdf2::{
+        pbkdf2,
+        MIN_VERIFY_LENGTH
+    },
+    Error,
+    Hmac,
+    Sha256,
+    Sha512
+};
+use ::core::{
+    fmt,
+    str::FromStr
+};
+use alloc::{
+    string::String,
+    vec,
+    vec::Vec
+};
+
+/// Default salt length in bytes
+const SALT_LEN: usize = 16;
+
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// PRF used for the PBKDF2 derivation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhcAlgorithm {
+    Pbkdf2Sha256,
+    Pbkdf2Sha512
+}
+
+impl PhcAlgorithm {
+    /// The algorithm identifier used in the PHC string
+    pub fn id(&self) -> &'static str {
+        match self {
+            PhcAlgorithm::Pbkdf2Sha256 => "pbkdf2-sha256",
+            PhcAlgorithm::Pbkdf2Sha512 => "pbkdf2-sha512"
+        }
+    }
+
+    /// Output length of the PRF in bytes
+    fn output_len(&self) -> usize {
+        match self {
+            PhcAlgorithm::Pbkdf2Sha256 => 32,
+            PhcAlgorithm::Pbkdf2Sha512 => 64
+        }
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8], iterations: usize, out: &mut [u8]) -> Result<(), Error> {
+        match self {
+            PhcAlgorithm::Pbkdf2Sha256 => pbkdf2::<Hmac<Sha256>>(password, salt, iterations, out),
+            PhcAlgorithm::Pbkdf2Sha512 => pbkdf2::<Hmac<Sha512>>(password, salt, iterations, out)
+        }
+    }
+}
+
+/// Errors from creating or parsing a PHC string
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhcError {
+    UnknownAlgorithm,
+    MissingField,
+    BadIterations,
+    BadEncoding,
+    /// The salt is empty or the hash is too short to be checked safely
+    BadLength
+}
+
+impl fmt::Display for PhcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhcError::UnknownAlgorithm => write!(f, "unknown PHC algorithm identifier"),
+            PhcError::MissingField => write!(f, "missing field in PHC string"),
+            PhcError::BadIterations => write!(f, "invalid iteration count parameter"),
+            PhcError::BadEncoding => write!(f, "invalid base64 encoding"),
+            PhcError::BadLength => write!(f, "empty salt or hash too short")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PhcError { }
+
+/// A PBKDF2 password hash that can be stored as a PHC string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhcHash {
+    pub algorithm: PhcAlgorithm,
+    pub iterations: usize,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>
+}
+
+impl PhcHash {
+    /// Hash a password using the given salt.
+    /// 
+    /// Fails with the same errors as parsing would, if the salt is empty or the iteration count is zero.
+    pub fn new<P, S>(algorithm: PhcAlgorithm, password: P, salt: S, iterations: usize) -> Result<Self, PhcError>
+    where P: AsRef<[u8]>, S: AsRef<[u8]> {
+        if salt.as_ref().is_empty() {
+            return Err(PhcError::BadLength);
+        }
+
+        // The hash is a single PRF block, so only the iteration count can be rejected
+        let mut hash = vec![0; algorithm.output_len()];
+        algorithm.derive(password.as_ref(), salt.as_ref(), iterations, &mut hash)
+            .map_err(|_| PhcError::BadIterations)?;
+
+        Ok(Self {
+            algorithm,
+            iterations,
+            salt: salt.as_ref().to_vec(),
+            hash
+        })
+    }
+
+    /// Hash a password, generating a 16 byte salt with the provided hook.
+    ///
+    /// The hook is given the salt buffer to fill, usually from the operating system's RNG.
+    pub fn generate<P, F>(algorithm: PhcAlgorithm, password: P, iterations: usize, fill_salt: F) -> Result<Self, PhcError>
+    where P: AsRef<[u8]>, F: FnOnce(&mut [u8]) {
+        let mut salt = [0u8; SALT_LEN];
+        fill_salt(&mut salt);
+
+        Self::new(algorithm, password, salt, iterations)
+    }
+
+    /// Check a password against the stored hash in constant time.
+    /// 
+    /// Hashes shorter than 14 bytes never verify, as they would match almost any password,
+    /// and neither do hashes with a zero iteration count.
+    pub fn verify<P>(&self, password: P) -> bool
+    where P: AsRef<[u8]> {
+        if self.hash.len() < MIN_VERIFY_LENGTH {
+            return false;
+        }
+
+        let mut dk = vec![0; self.hash.len()];
+        if self.algorithm.derive(password.as_ref(), &self.salt, self.iterations, &mut dk).is_err() {
+            return false;
+        }
+
+        ct_eq(&dk, &self.hash)
+    }
+}
+
+impl fmt::Display for PhcHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}$i={}${}${}", self.algorithm.id(), self.iterations, b64_encode(&self.salt), b64_encode(&self.hash))
+    }
+}
+
+impl FromStr for PhcHash {
+    type Err = PhcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split('$');
+        if fields.next() != Some("") {
+            return Err(PhcError::MissingField);
+        }
+
+        let algorithm = match fields.next() {
+            Some("pbkdf2-sha256") => PhcAlgorithm::Pbkdf2Sha256,
+            Some("pbkdf2-sha512") => PhcAlgorithm::Pbkdf2Sha512,
+            Some(_) => return Err(PhcError::UnknownAlgorithm),
+            None => return Err(PhcError::MissingField)
+        };
+
+        let iterations = fields.next()
+            .ok_or(PhcError::MissingField)?
+            .strip_prefix("i=")
+            .ok_or(PhcError::BadIterations)?
+            .parse::<usize>()
+            .map_err(|_| PhcError::BadIterations)?;
+        if iterations == 0 {
+            return Err(PhcError::BadIterations);
+        }
+
+        let salt = b64_decode(fields.next().ok_or(PhcError::MissingField)?)?;
+        let hash = b64_decode(fields.next().ok_or(PhcError::MissingField)?)?;
+        if fields.next().is_some() {
+            return Err(PhcError::MissingField);
+        }
+        if salt.is_empty() || hash.len() < MIN_VERIFY_LENGTH {
+            return Err(PhcError::BadLength);
+        }
+
+        Ok(Self {
+            algorithm,
+            iterations,
+            salt,
+            hash
+        })
+    }
+}
+
+/// Base64 encode without padding
+fn b64_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, x)| acc | (*x as u32) << (16 - 8*i));
+        for i in 0..=chunk.len() {
+            out.push(B64_ALPHABET[(n >> (18 - 6*i) & 0x3f) as usize] as char);
+        }
+    }
+
+    out
+}
+
+/// Base64 decode without padding
+fn b64_decode(data: &str) -> Result<Vec<u8>, PhcError> {
+    if data.len()%4 == 1 {
+        return Err(PhcError::BadEncoding);
+    }
+
+    let mut out = vec![];
+    for chunk in data.as_bytes().chunks(4) {
+        let mut n: u32 = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = B64_ALPHABET.iter()
+                .position(|x| x == c)
+                .ok_or(PhcError::BadEncoding)?;
+            n |= (value as u32) << (18 - 6*i);
+        }
+        for i in 0..chunk.len()-1 {
+            out.push((n >> (16 - 8*i)) as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phc_string() {
+        let phc = PhcHash::new(PhcAlgorithm::Pbkdf2Sha256, b"password", b"saltsaltsaltsalt", 1000).unwrap();
+        let encoded = phc.to_string();
+        assert_eq!(encoded, "$pbkdf2-sha256$i=1000$c2FsdHNhbHRzYWx0c2FsdA$8nX7hwFEzIB8aPajJTYK8weHQc5Ngz0pFVAKvSu4jQA");
+
+        let parsed: PhcHash = encoded.parse().unwrap();
+        assert_eq!(parsed, phc);
+        assert!(parsed.verify(b"password"));
+        assert!(!parsed.verify(b"Password"));
+
+        assert_eq!("$pbkdf2-md5$i=1$c2FsdA$c2FsdA".parse::<PhcHash>(), Err(PhcError::UnknownAlgorithm));
+        assert_eq!("$pbkdf2-sha256$i=x$c2FsdA$c2FsdA".parse::<PhcHash>(), Err(PhcError::BadIterations));
+        assert_eq!("$pbkdf2-sha256$i=1$c2F*dA$c2FsdA".parse::<PhcHash>(), Err(PhcError::BadEncoding));
+        assert_eq!("$pbkdf2-sha256$i=1$c2FsdA".parse::<PhcHash>(), Err(PhcError::MissingField));
+
+        // An empty hash would verify any password
+        assert_eq!("$pbkdf2-sha256$i=1$c2FsdA$".parse::<PhcHash>(), Err(PhcError::BadLength));
+        assert_eq!("$pbkdf2-sha256$i=1$$8nX7hwFEzIB8aPajJTYK8weHQc5Ngz0pFVAKvSu4jQA".parse::<PhcHash>(), Err(PhcError::BadLength));
+        let empty = PhcHash { hash: vec![], ..phc.clone() };
+        assert!(!empty.verify(b"password"));
+        let zero = PhcHash { iterations: 0, ..phc };
+        assert!(!zero.verify(b"password"));
+
+        // Hashes that could not be parsed back are not created either
+        assert_eq!(PhcHash::new(PhcAlgorithm::Pbkdf2Sha256, b"password", b"salt", 0), Err(PhcError::BadIterations));
+        assert_eq!(PhcHash::new(PhcAlgorithm::Pbkdf2Sha256, b"password", b"", 1000), Err(PhcError::BadLength));
+    }
+
+    #[test]
+    fn phc_generate() {
+        let phc = PhcHash::generate(PhcAlgorithm::Pbkdf2Sha512, b"password", 10, |salt| salt.fill(0xab)).unwrap();
+        assert_eq!(phc.salt, vec![0xab; SALT_LEN]);
+        assert!(phc.to_string().parse::<PhcHash>().unwrap().verify(b"password"));
+        assert_eq!(PhcHash::generate(PhcAlgorithm::Pbkdf2Sha512, b"password", 0, |salt| salt.fill(0xab)), Err(PhcError::BadIterations));
+    }
+}
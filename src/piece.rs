@@ -0,0 +1,108 @@
+// Piece hash module
+//
+// Hash lists, as used by BitTorrent and for cloud upload integrity, split a stream
+// into fixed size pieces and hash each piece on its own so that pieces can be
+// verified independently. A top hash over the concatenated piece digests commits
+// to the whole list.
+
+use crate::HashEngine;
+use alloc::vec::{
+    Drain,
+    Vec
+};
+
+/// Streaming hasher that produces the digest of each fixed size piece of its input
+pub struct PieceHasher<H: HashEngine> {
+    engine: H,
+    piece_size: usize,
+    filled: usize,
+    pieces: Vec<H::Digest>
+}
+
+impl<H: HashEngine> PieceHasher<H> {
+    /// Create a piece hasher with the given piece size.
+    ///
+    /// Panics if the piece size is zero.
+    pub fn new(piece_size: usize) -> Self {
+        assert!(piece_size > 0, "piece size must not be zero");
+
+        Self {
+            engine: H::default(),
+            piece_size,
+            filled: 0,
+            pieces: Vec::new()
+        }
+    }
+
+    pub fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        let mut data = data.as_ref();
+        while !data.is_empty() {
+            let take = data.len().min(self.piece_size - self.filled);
+            self.engine.input(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+
+            if self.filled == self.piece_size {
+                self.pieces.push(self.engine.finalise_reset());
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// The digests of the pieces completed since the last call, in order
+    pub fn take_pieces(&mut self) -> Drain<'_, H::Digest> {
+        self.pieces.drain(..)
+    }
+
+    /// Digests of the pieces that have not been taken, including a final partial piece
+    pub fn finalise(mut self) -> Vec<H::Digest> {
+        if self.filled > 0 {
+            self.pieces.push(self.engine.finalise());
+        }
+
+        self.pieces
+    }
+
+    /// Top hash of a hash list, the digest of all the piece digests concatenated
+    pub fn root(pieces: &[H::Digest]) -> H::Digest {
+        let mut engine = H::default();
+        engine.input_iter(pieces);
+        engine.finalise()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: HashEngine> std::io::Write for PieceHasher<H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    #[test]
+    fn piece_hasher() {
+        let digest = |data: &[u8]| Sha256::new().chain(data).finalise();
+
+        let mut hasher = PieceHasher::<Sha256>::new(4);
+        hasher.input(b"abcdef");
+        assert_eq!(hasher.take_pieces().collect::<Vec<_>>(), vec![digest(b"abcd")]);
+        hasher.input(b"gh");
+        hasher.input(b"ij");
+        let pieces = hasher.finalise();
+        assert_eq!(pieces, vec![digest(b"efgh"), digest(b"ij")]);
+
+        let all = [digest(b"abcd"), pieces[0], pieces[1]];
+        let concatenated: Vec<u8> = all.iter().flat_map(|d| d.0).collect();
+        assert_eq!(PieceHasher::<Sha256>::root(&all), digest(&concatenated));
+    }
+}
@@ -0,0 +1,78 @@
+// Proof of work module
+//
+// Block hashes are compared against the target as 256-bit little endian integers,
+// where the target is stored in the header in the compact "nBits" form: a one byte
+// base 256 exponent followed by a three byte mantissa with a sign bit.
+
+use crate::Sha256Digest;
+
+/// Expand a compact "nBits" value into a 256-bit little endian target.
+/// 
+/// Returns None for negative or overflowing values, which are never valid targets.
+pub fn compact_to_target(nbits: u32) -> Option<[u8; 32]> {
+    let exponent = (nbits >> 24) as usize;
+    let mut mantissa = nbits & 0x007fffff;
+    if nbits & 0x00800000 != 0 && mantissa != 0 {
+        return None
+    }
+
+    let mut target = [0u8; 32];
+    if exponent <= 3 {
+        mantissa >>= 8 * (3 - exponent);
+        target[..3].copy_from_slice(&mantissa.to_le_bytes()[..3]);
+        return Some(target)
+    }
+
+    for (i, byte) in mantissa.to_le_bytes()[..3].iter().enumerate() {
+        match target.get_mut(exponent - 3 + i) {
+            Some(slot) => *slot = *byte,
+            None if *byte != 0 => return None,
+            None => ()
+        }
+    }
+    Some(target)
+}
+
+impl Sha256Digest {
+    /// Check if the digest, read as a little endian integer, is at or below the compact target
+    pub fn meets_target(&self, nbits: u32) -> bool {
+        compact_to_target(nbits).is_some_and(|target| self.meets_target_le(&target))
+    }
+
+    /// Check if the digest is at or below a 256-bit little endian target
+    pub fn meets_target_le(&self, target: &[u8; 32]) -> bool {
+        self.0.iter().rev().cmp(target.iter().rev()) != ::core::cmp::Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_hex(nbits: u32) -> Option<String> {
+        compact_to_target(nbits).map(|target| Sha256Digest(target).display_backwards().to_string())
+    }
+
+    #[test]
+    fn compact_targets() {
+        assert_eq!(target_hex(0x1d00ffff).unwrap(), "00000000ffff0000000000000000000000000000000000000000000000000000");
+        assert_eq!(target_hex(0x05009234).unwrap(), "0000000000000000000000000000000000000000000000000000000092340000");
+        assert_eq!(target_hex(0x01123456).unwrap(), "0000000000000000000000000000000000000000000000000000000000000012");
+        assert_eq!(target_hex(0x20123456).unwrap(), "1234560000000000000000000000000000000000000000000000000000000000");
+        assert_eq!(target_hex(0x00000000).unwrap(), "0000000000000000000000000000000000000000000000000000000000000000");
+        assert_eq!(target_hex(0x04923456), None);
+        assert_eq!(target_hex(0x21123456), None);
+        assert_eq!(target_hex(0xff123456), None);
+    }
+
+    #[test]
+    fn genesis_meets_target() {
+        let genesis = Sha256Digest::from_hex_reversed("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+        assert!(genesis.meets_target(0x1d00ffff));
+        assert!(!genesis.meets_target(0x1a00ffff));
+        assert!(!genesis.meets_target(0x04923456));
+
+        let target = compact_to_target(0x1d00ffff).unwrap();
+        assert!(Sha256Digest(target).meets_target(0x1d00ffff));
+    }
+}
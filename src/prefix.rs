@@ -0,0 +1,64 @@
+// Prefix cache module
+//
+// Many workloads hash the same prefix followed by a different suffix each time,
+// such as sighashes of one transaction or tagged hashes. The prefix is hashed once
+// and the advanced engine, including any pending bytes of a partial block, is
+// cloned for every suffix.
+
+use crate::HashEngine;
+
+/// An engine that has already been fed a common prefix
+#[derive(Clone, Copy, Debug)]
+pub struct PrefixCache<T: HashEngine + Clone> {
+    engine: T
+}
+
+impl<T: HashEngine + Clone> PrefixCache<T> {
+    /// Hash the prefix once with a fresh engine
+    pub fn new<I>(prefix: I) -> Self
+    where I: AsRef<[u8]> {
+        Self::from_engine(T::default().chain(prefix))
+    }
+
+    /// Cache an engine that has already been fed the prefix
+    pub fn from_engine(engine: T) -> Self {
+        Self {
+            engine
+        }
+    }
+
+    /// An engine that continues from the end of the prefix
+    pub fn engine_for_suffix(&self) -> T {
+        self.engine.clone()
+    }
+
+    /// Digest of the prefix followed by the suffix
+    pub fn hash_suffix<I>(&self, suffix: I) -> T::Digest
+    where I: AsRef<[u8]> {
+        self.engine_for_suffix().chain(suffix).finalise()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Sha256, Sha512};
+
+    #[test]
+    fn prefix_cache() {
+        // Prefixes that end on and off a block boundary
+        let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".repeat(3);
+        for split in [0, 1, 56, 64, 100, 128, message.len()] {
+            let cache = PrefixCache::<Sha256>::new(&message[..split]);
+            assert_eq!(cache.hash_suffix(&message[split..]), Sha256::new().chain(&message).finalise());
+
+            let cache = PrefixCache::<Sha512>::new(&message[..split]);
+            let mut engine = cache.engine_for_suffix();
+            engine.input(&message[split..]);
+            assert_eq!(engine.finalise(), Sha512::new().chain(&message).finalise());
+        }
+
+        let cache = PrefixCache::<Sha256>::new(b"ab");
+        assert_eq!(cache.hash_suffix(b"c").to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
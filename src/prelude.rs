@@ -0,0 +1,46 @@
+// Prelude module
+//
+// Re-exports the engine traits, the engines, their digest types and the one-shot
+// functions so that typical wallet code only needs a single use statement:
+//      use btc_hashes::prelude::*;
+
+pub use crate::{
+    HashEngine,
+    KeyBasedHashEngine,
+    Mac,
+    Kdf,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha1,
+    Ripemd160,
+    Sha3_256,
+    Sha256d,
+    Hash160,
+    Hmac,
+    TaggedHash,
+    Sha224Digest,
+    Sha256Digest,
+    Sha384Digest,
+    Sha512Digest,
+    Sha1Digest,
+    Ripemd160Digest,
+    Sha3_256Digest,
+    HmacSha256,
+    HmacSha512,
+    hmac_sha256,
+    hmac_sha512,
+    pbkdf2,
+    pbkdf2_hmac_sha256,
+    pbkdf2_hmac_sha512,
+    hkdf_sha256,
+    hkdf_sha512,
+    hash_newtype,
+    hash_tag
+};
+#[cfg(feature = "alloc")]
+pub use crate::{
+    Pbkdf2HmacSha256,
+    Pbkdf2HmacSha512
+};
@@ -1,22 +1,27 @@
 use crate::{
     core::{
         message::{
-            Message,
             MessageBlock,
             MessageSchedule,
         },
         HashEngine,
         State,
+        Midstate,
         functions::ripemd160::*,
+        digest_struct,
         hash_struct,
         iconst_funcs,
         midstate_funcs,
         input_func,
-        impl_default
+        impl_default,
+        impl_write,
+        impl_extend,
+        impl_fmt_write,
+        impl_hash_batch,
+        impl_state_bytes
     },
     constants::RIPEMD160_INITIAL_CONSTANTS
 };
-use std::mem::size_of_val;
 
 // A Ripemd160 round within a block
 macro_rules! round {
@@ -31,39 +36,41 @@ macro_rules! round {
     };
 }
 
+digest_struct!(Ripemd160Digest, 20);
 hash_struct!(Ripemd160, 64, u64, u32, 5);
 impl_default!(Ripemd160, RIPEMD160_INITIAL_CONSTANTS, Self::BLOCKSIZE);
+impl_write!(Ripemd160);
+
+impl_extend!(Ripemd160);
+
+impl_fmt_write!(Ripemd160);
+impl_hash_batch!(Ripemd160);
+
+impl_state_bytes!(Ripemd160, u64, u32, 5);
 
 impl HashEngine for Ripemd160 {
-    type Digest = [u8; 20];
-    type Midstate = [u32; 5];
+    type Digest = Ripemd160Digest;
+    type Midstate = Midstate<u32, 5>;
     const BLOCKSIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 20;
 
     input_func!(u64);
-    iconst_funcs!(RIPEMD160_INITIAL_CONSTANTS, Self::BLOCKSIZE);
+    iconst_funcs!(Self::BLOCKSIZE);
     midstate_funcs!(u64);
 
     fn finalise(&mut self) -> Self::Digest {
         // Get the final blocks
-        let fblocks: Vec<MessageBlock<{Self::BLOCKSIZE}>> = MessageBlock::from_message(self.pad_fbuffer());
-        
-        assert!(fblocks.len() <= 2);
-        for fblock in fblocks {
-            Self::process_block(&mut self.state, fblock);
+        let (fblocks, count) = self.pad_fbuffer();
+        for fblock in &fblocks[..count] {
+            Self::process_block(&mut self.state, &fblock.0);
         }
 
         let mut result: [u8; 20] = [0; 20];
+        for (chunk, reg) in result.chunks_mut(4).zip(self.state.read().iter()) {
+            chunk.copy_from_slice(&reg.to_le_bytes());
+        }
 
-        result.copy_from_slice(
-            &self.state.read()
-                .iter()
-                .flat_map(|buf|
-                    buf.to_le_bytes()
-                )
-                .collect::<Vec<u8>>()
-        );
-
-        result
+        result.into()
     }
 }
 
@@ -73,10 +80,8 @@ impl Ripemd160 {
     }
     
     /// Process a RIPEMD160 data block
-    fn process_block(mdbuf: &mut State<u32, 5>, block: MessageBlock<{Self::BLOCKSIZE}>) {
-        let mut schedule: MessageSchedule<u32, 16> = MessageSchedule::from(block);
-        schedule.reverse_words(); //RIPEMD160 words are little endian
-        let words = schedule.0;
+    fn process_block(mdbuf: &mut State<u32, 5>, block: &[u8; Self::BLOCKSIZE]) {
+        let words = MessageSchedule::<u32, 16>::from_le(block).0; //RIPEMD160 words are little endian
         let buffer = mdbuf.read();
         let (mut aa, mut bb, mut cc, mut dd, mut ee) = (buffer[0], buffer[1], buffer[2], buffer[3], buffer[4]);
         let (mut aaa, mut bbb, mut ccc, mut ddd, mut eee) = (buffer[0], buffer[1], buffer[2], buffer[3], buffer[4]);
@@ -174,20 +179,11 @@ impl Ripemd160 {
     }
 
     /// Padding the final buffer upon hash finalisation
-    fn pad_fbuffer(&self) -> Message<{Self::BLOCKSIZE}> {
-        let end_index = self.length as usize%Self::BLOCKSIZE; //Data in the buffer past this end index has already been processed.
-        
-        // Create the final message blocks
-        let mut fmsg_data: Vec<u8> = vec![];
-        fmsg_data.extend_from_slice(&self.buffer[..end_index]);
-        fmsg_data.push(0x80);                                 // append single '1' bit
-        while fmsg_data.len()%Self::BLOCKSIZE != Self::BLOCKSIZE-size_of_val(&self.length) {
-            fmsg_data.push(0x00);                             // pad with zeroes
-        }
-        fmsg_data.extend((self.length*8).to_le_bytes()); // append original data length in bits (Ripemd uses little endian here)
-        assert_eq!(fmsg_data.len()%Self::BLOCKSIZE, 0);       // check the padded data mod blocksize is zero
+    fn pad_fbuffer(&self) -> ([MessageBlock<{Self::BLOCKSIZE}>; 2], usize) {
+        let end_index = (self.length%Self::BLOCKSIZE as u64) as usize; //Data in the buffer past this end index has already been processed.
 
-        Message::new(fmsg_data)
+        // Create the final message blocks, appending the original data length in bits (Ripemd uses little endian here)
+        MessageBlock::pad(&self.buffer[..end_index], &(self.length*8).to_le_bytes())
     }
 }
 
@@ -212,7 +208,7 @@ mod tests {
         for case in cases {
             let mut hasher = Ripemd160::new();
             hasher.input(&case.0);
-            let digest = hasher.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let digest = hasher.finalise().to_string();
             assert_eq!(digest, case.1);
         }
     }
@@ -0,0 +1,124 @@
+// RustCrypto trait module
+//
+// Implementations of the RustCrypto `digest` traits so that the crate's HMAC
+// engines can be used anywhere the ecosystem expects a generic MAC, such as
+// the hkdf crate or AEAD constructions.
+//
+// The digest traits describe sizes with typenum, so they are implemented for
+// each concrete HMAC rather than generically over the underlying engine.
+
+
+use crate::{
+    HashEngine,
+    KeyBasedHashEngine,
+    Hmac,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Ripemd160,
+    Sha3_256
+};
+use digest::{
+    crypto_common::KeySizeUser,
+    consts::{U20, U28, U32, U48, U64, U128, U136},
+    FixedOutput,
+    FixedOutputReset,
+    InvalidLength,
+    Key,
+    KeyInit,
+    MacMarker,
+    Output,
+    OutputSizeUser,
+    Reset,
+    Update
+};
+
+/// Macro to implement the digest MAC traits for a HMAC engine
+macro_rules! impl_digest_mac {
+    ($engine: ty, $output_size: ty, $block_size: ty) => {
+        impl OutputSizeUser for Hmac<$engine> {
+            type OutputSize = $output_size;
+        }
+
+        impl KeySizeUser for Hmac<$engine> {
+            type KeySize = $block_size;
+        }
+
+        impl KeyInit for Hmac<$engine> {
+            fn new(key: &Key<Self>) -> Self {
+                Self::new_with_key(key)
+            }
+
+            /// HMAC accepts keys of any length
+            fn new_from_slice(key: &[u8]) -> Result<Self, InvalidLength> {
+                Ok(Self::new_with_key(key))
+            }
+        }
+
+        impl Update for Hmac<$engine> {
+            fn update(&mut self, data: &[u8]) {
+                self.input(data);
+            }
+        }
+
+        impl FixedOutput for Hmac<$engine> {
+            fn finalize_into(mut self, out: &mut Output<Self>) {
+                out.copy_from_slice(self.finalise().as_ref());
+            }
+        }
+
+        impl Reset for Hmac<$engine> {
+            fn reset(&mut self) {
+                HashEngine::reset(self);
+            }
+        }
+
+        impl FixedOutputReset for Hmac<$engine> {
+            fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                out.copy_from_slice(self.finalise_reset().as_ref());
+            }
+        }
+
+        impl MacMarker for Hmac<$engine> { }
+    };
+}
+
+impl_digest_mac!(Sha1, U20, U64);
+impl_digest_mac!(Sha224, U28, U64);
+impl_digest_mac!(Sha256, U32, U64);
+impl_digest_mac!(Sha384, U48, U128);
+impl_digest_mac!(Sha512, U64, U128);
+impl_digest_mac!(Ripemd160, U20, U64);
+impl_digest_mac!(Sha3_256, U32, U136);
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use digest::Mac;
+
+    /// Generic over any RustCrypto MAC
+    fn mac<M: Mac + KeyInit>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+        let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+        Mac::update(&mut mac, msg);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn digest_mac() {
+        // RFC 4231 test case 2
+        let tag = mac::<Hmac<Sha256>>(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(tag, hex::<32>("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+
+        let mut engine = <Hmac<Sha512> as Mac>::new_from_slice(b"Jefe").unwrap();
+        Mac::update(&mut engine, b"what do ya want for nothing?");
+        let tag = engine.finalize_reset().into_bytes();
+        assert_eq!(&tag[..], &crate::hmac_sha512(b"Jefe", b"what do ya want for nothing?")[..]);
+
+        Mac::update(&mut engine, b"what do ya want for nothing?");
+        assert!(Mac::verify_slice(engine, &tag).is_ok());
+    }
+}
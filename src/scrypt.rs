@@ -0,0 +1,141 @@
+// Scrypt module
+//
+// Memory hard key derivation (RFC 7914). The password and salt are stretched with
+// PBKDF2-HMAC-SHA256 into p blocks of 128 * r bytes, each block is mixed through
+// ROMix using N * 128 * r bytes of memory, and the mixed blocks are used as the
+// salt of a final PBKDF2-HMAC-SHA256.
+
+use ::core::convert::TryFrom;
+use alloc::{
+    vec,
+    vec::Vec
+};
+use crate::{
+    error::Error,
+    pbkdf2_hmac_sha256
+};
+
+/// Salsa20/8 core, replacing the block with the core applied to it
+fn salsa20_8(block: &mut [u32; 16]) {
+    let mut x = *block;
+    macro_rules! quarter {
+        ($a: expr, $b: expr, $c: expr, $d: expr) => {
+            x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+            x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+            x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+            x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+        };
+    }
+
+    for _ in 0..4 {
+        // Columns
+        quarter!(0, 4, 8, 12);
+        quarter!(5, 9, 13, 1);
+        quarter!(10, 14, 2, 6);
+        quarter!(15, 3, 7, 11);
+        // Rows
+        quarter!(0, 1, 2, 3);
+        quarter!(5, 6, 7, 4);
+        quarter!(10, 11, 8, 9);
+        quarter!(15, 12, 13, 14);
+    }
+
+    for (word, mixed) in block.iter_mut().zip(x.iter()) {
+        *word = word.wrapping_add(*mixed);
+    }
+}
+
+/// BlockMix with Salsa20/8 over 2 * r 64 byte blocks, writing the shuffled output
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&input[(2*r - 1) * 16..]);
+
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (a, b) in x.iter_mut().zip(block) {
+            *a ^= b;
+        }
+        salsa20_8(&mut x);
+
+        // Even blocks go to the first half of the output and odd blocks to the second
+        let position = (i / 2 + (i % 2) * r) * 16;
+        output[position..position + 16].copy_from_slice(&x);
+    }
+}
+
+/// ROMix over one 128 * r byte block, using n blocks of scratch memory
+fn ro_mix(block: &mut [u8], n: usize, r: usize, v: &mut [u32]) {
+    let words = 32 * r;
+    let mut x: Vec<u32> = block.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    let mut y = vec![0u32; words];
+
+    for chunk in v.chunks_exact_mut(words) {
+        chunk.copy_from_slice(&x);
+        block_mix(&x, &mut y, r);
+        ::core::mem::swap(&mut x, &mut y);
+    }
+
+    for _ in 0..n {
+        // Integerify takes the first word of the last 64 byte block, n is a power of two
+        let j = x[words - 16] as usize & (n - 1);
+        for (a, b) in x.iter_mut().zip(&v[j * words..(j + 1) * words]) {
+            *a ^= b;
+        }
+        block_mix(&x, &mut y, r);
+        ::core::mem::swap(&mut x, &mut y);
+    }
+
+    for (out, word) in block.chunks_exact_mut(4).zip(x.iter()) {
+        out.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Derive a key with scrypt, filling the entire output buffer.
+/// 
+/// The cost n must be a power of two greater than one and below 2^(16 * r), r * p must be
+/// below 2^30, and the memory needed must be addressable on the target.
+pub fn scrypt(password: &[u8], salt: &[u8], n: u64, r: u32, p: u32, out: &mut [u8]) -> Result<(), Error> {
+    if n < 2 || !n.is_power_of_two() || r == 0 || p == 0 || (r as u64) * (p as u64) >= 1 << 30 {
+        return Err(Error::InvalidCost);
+    }
+    // RFC 7914 requires N < 2^(128 * r / 8)
+    if r < 4 && n >= 1 << (16 * r) {
+        return Err(Error::InvalidCost);
+    }
+    let (n, r, p) = match (usize::try_from(n), usize::try_from(r), usize::try_from(p)) {
+        (Ok(n), Ok(r), Ok(p)) => (n, r, p),
+        _ => return Err(Error::InvalidCost)
+    };
+    let (blocks_len, v_len) = match (p.checked_mul(128).and_then(|x| x.checked_mul(r)), n.checked_mul(32).and_then(|x| x.checked_mul(r))) {
+        (Some(blocks_len), Some(v_len)) => (blocks_len, v_len),
+        _ => return Err(Error::InvalidCost)
+    };
+
+    let mut blocks = vec![0u8; blocks_len];
+    pbkdf2_hmac_sha256(password, salt, 1, &mut blocks)?;
+
+    let mut v = vec![0u32; v_len];
+    for block in blocks.chunks_exact_mut(128 * r) {
+        ro_mix(block, n, r, &mut v);
+    }
+
+    pbkdf2_hmac_sha256(password, &blocks, 1, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn scrypt_rfc7914() {
+        let mut out = [0u8; 64];
+        scrypt(b"", b"", 16, 1, 1, &mut out).unwrap();
+        assert_eq!(out, hex("77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"));
+        scrypt(b"password", b"NaCl", 1024, 8, 16, &mut out).unwrap();
+        assert_eq!(out, hex("fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"));
+
+        assert_eq!(scrypt(b"", b"", 15, 1, 1, &mut out), Err(Error::InvalidCost));
+        assert_eq!(scrypt(b"", b"", 1 << 16, 1, 1, &mut out), Err(Error::InvalidCost));
+        assert_eq!(scrypt(b"", b"", 1 << 63, 8, 1, &mut out), Err(Error::InvalidCost));
+    }
+}
@@ -0,0 +1,49 @@
+// Secret module
+//
+// Accepts PBKDF2 passwords and HMAC keys wrapped in the secrecy crate's types,
+// such as SecretString and SecretVec<u8>. The secret is only exposed as a borrowed
+// slice for the duration of the call and is never copied into a Vec.
+
+use secrecy::ExposeSecret;
+use crate::{
+    Error,
+    HashEngine,
+    KeyBasedHashEngine,
+    Hmac,
+    pbkdf2
+};
+
+/// Derive a key from a secret password and salt into the output buffer
+pub fn pbkdf2_secret<T, P, S>(password: &P, salt: &[u8], iterations: usize, out: &mut [u8]) -> Result<(), Error>
+where T: KeyBasedHashEngine + Clone, P: ExposeSecret<S>, S: AsRef<[u8]> {
+    pbkdf2::<T>(password.expose_secret().as_ref(), salt, iterations, out)
+}
+
+impl<T: HashEngine + Copy> Hmac<T> {
+    /// Create an HMAC engine from a secret key
+    pub fn new_with_secret_key<K, S>(key: &K) -> Self
+    where K: ExposeSecret<S>, S: AsRef<[u8]> {
+        Self::new_with_key(key.expose_secret().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::{Sha256, Sha512};
+    use secrecy::{SecretString, SecretVec};
+
+    #[test]
+    fn secret_inputs() {
+        let password = SecretString::new("password".to_string());
+        let mut dk = [0u8; 32];
+        pbkdf2_secret::<Hmac<Sha256>, _, _>(&password, b"salt", 1, &mut dk).unwrap();
+        assert_eq!(dk, hex("120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"));
+
+        let key = SecretVec::new(b"key".to_vec());
+        let mut hmac = Hmac::<Sha512>::new_with_secret_key(&key);
+        hmac.input(b"message");
+        assert_eq!(hmac.finalise(), Hmac::<Sha512>::mac(b"key", b"message"));
+    }
+}
@@ -0,0 +1,115 @@
+// Self test module
+//
+// Power on self tests in the style of FIPS 140, running a known answer test for
+// each algorithm compiled into the crate. The expected answers are the published
+// examples from FIPS 180-4, FIPS 202, the RIPEMD-160 paper, RFC 4231, RFC 7914 and
+// RFC 5869. Engines use the same backend selection as everywhere else, so the tests
+// exercise the accelerated code paths the caller will actually run.
+
+use alloc::vec::Vec;
+use ::core::fmt;
+use crate::{
+    core::decode_hex,
+    hkdf_sha256,
+    hmac_sha256,
+    hmac_sha512,
+    pbkdf2_hmac_sha256,
+    HashEngine,
+    Ripemd160,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha3_256,
+    Sha512
+};
+
+/// Outcome of the known answer test for one algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KnownAnswer {
+    pub algorithm: &'static str,
+    pub passed: bool
+}
+
+/// Results of every known answer test run by self_test()
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub results: Vec<KnownAnswer>
+}
+
+impl SelfTestReport {
+    /// Whether every known answer test passed
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+
+    /// The known answer tests that failed
+    pub fn failures(&self) -> impl Iterator<Item = &KnownAnswer> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for result in &self.results {
+            writeln!(f, "{}: {}", result.algorithm, if result.passed { "pass" } else { "FAIL" })?;
+        }
+        Ok(())
+    }
+}
+
+fn matches(output: &[u8], expected: &str) -> bool {
+    let mut bytes = alloc::vec![0; expected.len()/2];
+    decode_hex(expected, &mut bytes).is_ok() && output == bytes.as_slice()
+}
+
+fn digest_of<T: HashEngine>(expected: &str) -> bool {
+    matches(T::default().chain(b"abc").finalise().as_ref(), expected)
+}
+
+/// Run the known answer test of every compiled in algorithm
+pub fn self_test() -> SelfTestReport {
+    let mut results = Vec::new();
+    let mut record = |algorithm, passed| results.push(KnownAnswer { algorithm, passed });
+
+    record("SHA1", digest_of::<Sha1>("a9993e364706816aba3e25717850c26c9cd0d89d"));
+    record("SHA224", digest_of::<Sha224>("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"));
+    record("SHA256", digest_of::<Sha256>("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+    record("SHA384", digest_of::<Sha384>("cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"));
+    record("SHA512", digest_of::<Sha512>("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"));
+    record("SHA3-256", digest_of::<Sha3_256>("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"));
+    record("RIPEMD160", digest_of::<Ripemd160>("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"));
+
+    // RFC 4231 test case 2
+    let (key, data) = (b"Jefe", b"what do ya want for nothing?");
+    record("HMAC-SHA256", matches(&hmac_sha256(key, data).0, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"));
+    record("HMAC-SHA512", matches(&hmac_sha512(key, data).0, "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"));
+
+    // RFC 7914 section 11
+    let mut dk = [0u8; 64];
+    let passed = pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut dk).is_ok();
+    record("PBKDF2-HMAC-SHA256", passed && matches(&dk, "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"));
+
+    // RFC 5869 test case 1
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    let mut okm = [0u8; 42];
+    let passed = hkdf_sha256(salt, [0x0b; 22], info, &mut okm).is_ok()
+        && matches(&okm, "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865");
+    record("HKDF-SHA256", passed);
+
+    SelfTestReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_test_passes() {
+        let report = self_test();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.failures().count(), 0);
+        assert_eq!(report.results.len(), 11);
+    }
+}
@@ -0,0 +1,163 @@
+// SHA1 Module
+//
+// SHA1 is not used within Bitcoin but is required by a number of protocols
+// built on top of HMAC such as HOTP/TOTP.
+use crate::{
+    core::{
+        message::{
+            MessageBlock
+        },
+        HashEngine,
+        State,
+        Midstate,
+        functions::sha1::*,
+        digest_struct,
+        hash_struct,
+        iconst_funcs,
+        midstate_funcs,
+        input_func,
+        bit_input_func,
+        impl_default,
+        impl_write,
+        impl_extend,
+        impl_fmt_write,
+        impl_hash_batch,
+        impl_state_bytes
+    },
+    constants::{
+        SHA1_INITIAL_CONSTANTS,
+        SHA1_ROUND_CONSTANTS
+    }
+};
+
+digest_struct!(Sha1Digest, 20);
+hash_struct!(Sha1, 64, u64, u32, 5);
+impl_default!(Sha1, SHA1_INITIAL_CONSTANTS, Self::BLOCKSIZE);
+impl_write!(Sha1);
+
+impl_extend!(Sha1);
+
+impl_fmt_write!(Sha1);
+impl_hash_batch!(Sha1);
+
+impl_state_bytes!(Sha1, u64, u32, 5);
+
+impl HashEngine for Sha1 {
+    type Digest = Sha1Digest;
+    type Midstate = Midstate<u32, 5>;
+    const BLOCKSIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 20;
+
+    input_func!(u64);
+    iconst_funcs!(Self::BLOCKSIZE);
+    midstate_funcs!(u64);
+
+    fn finalise(&mut self) -> Self::Digest {
+        // Get the final blocks
+        let (fblocks, count) = self.pad_fbuffer();
+        for fblock in &fblocks[..count] {
+            Self::process_block(&mut self.state, &fblock.0);
+        }
+
+        let mut result: [u8; 20] = [0; 20];
+        for (chunk, reg) in result.chunks_mut(4).zip(self.state.read().iter()) {
+            chunk.copy_from_slice(&reg.to_be_bytes());
+        }
+
+        result.into()
+    }
+}
+
+impl Sha1 {
+    bit_input_func!();
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process a SHA1 data block
+    fn process_block(state: &mut State<u32, 5>, block: &[u8; Self::BLOCKSIZE]) {
+        // Create the 80 word schedule
+        // W[i] = ROTL1(W[i-3] ^ W[i-8] ^ W[i-14] ^ W[i-16])
+        let mut w = [0u32; 80];
+        for (i, chunk) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i-3] ^ w[i-8] ^ w[i-14] ^ w[i-16]).rotate_left(1);
+        }
+
+        let _state = state.read();
+        let (mut a, mut b, mut c, mut d, mut e) = (_state[0], _state[1], _state[2], _state[3], _state[4]);
+        for (i, word) in w.iter().enumerate() {
+            let f = match i {
+                0..=19  => choice(b, c, d),
+                20..=39 => parity(b, c, d),
+                40..=59 => majority(b, c, d),
+                _       => parity(b, c, d)
+            };
+
+            let t = a.rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(SHA1_ROUND_CONSTANTS[i/20])
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+
+        // update the state
+        let new_state: [u32; 5] = [
+            _state[0].wrapping_add(a),
+            _state[1].wrapping_add(b),
+            _state[2].wrapping_add(c),
+            _state[3].wrapping_add(d),
+            _state[4].wrapping_add(e)
+        ];
+        state.update(new_state);
+    }
+
+    /// Padding the final buffer upon hash finalisation
+    fn pad_fbuffer(&self) -> ([MessageBlock<{Self::BLOCKSIZE}>; 2], usize) {
+        let end_index = (self.length%Self::BLOCKSIZE as u64) as usize; //Data in the buffer past this end index has already been processed.
+
+        // Create the final message blocks, appending the original data length in bits
+        let bit_length = self.length*8 + self.bits as u64;
+        MessageBlock::pad_bits(&self.buffer[..end_index], self.buffer[end_index], self.bits, &bit_length.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha1() {
+        let cases: Vec<(Vec<u8>, &str)> = vec![
+            (vec![], "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (b"abc".to_vec(), "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec(), "84983e441c3bd26ebaae4aa1f95129e5e54670f1")
+        ];
+
+
+        for case in cases {
+            let mut hasher = Sha1::new();
+            hasher.input(&case.0);
+            let digest = hasher.finalise().to_string();
+            assert_eq!(digest, case.1);
+        }
+    }
+
+    #[test]
+    fn bit_input() {
+        // NIST CAVP SHA1ShortMsg, Len = 5
+        let mut hasher = Sha1::new();
+        hasher.input_bits(&[0x98], 5);
+        assert_eq!(hasher.finalise_reset().to_string(), "29826b003b906e660eff4027ce98af3531ac75ba");
+        hasher.input_bits(b"abc", 24);
+        assert_eq!(hasher.finalise().to_string(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+}
@@ -3,18 +3,25 @@
 use crate::{
     core::{
         message::{
-            Message,
             MessageBlock,
             MessageSchedule
         },
         HashEngine,
         State,
+        Midstate,
         functions::sha2::*,
+        digest_struct,
         hash_struct,
         iconst_funcs,
         midstate_funcs,
         input_func,
-        impl_default
+        bit_input_func,
+        impl_default,
+        impl_write,
+        impl_extend,
+        impl_fmt_write,
+        impl_hash_batch,
+        impl_state_bytes
     },
     constants::{
         SHA224_INITIAL_CONSTANTS,
@@ -24,18 +31,54 @@ use crate::{
         SHA256_ROUND_CONSTANTS,
         SHA512_ROUND_CONSTANTS
     },
-    
-};
-use std::{
-    convert::TryInto,
-    mem::size_of_val
+    backend::{
+        Sha256Backend,
+        Sha512Backend
+    },
+    arch
 };
+use ::core::mem::size_of_val;
+use ::core::convert::TryInto;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 /// Macro to run the SHA2 compression accordingly for each hash function
+/// 
+/// An accelerated compression function can be passed in, which is tried first
+/// and reports whether it ran so the portable compression can be used instead.
 macro_rules! sha2_compression {
-    ($constants: expr, $schedule_length: expr, $base: ty) => {
-        fn process_block(state: &mut State<$base, 8>, block: MessageBlock<{Self::BLOCKSIZE}>) {
-            let schedule: MessageSchedule<$base, $schedule_length> = MessageSchedule::from(block);
+    ($constants: expr, $schedule_length: tt, $base: ty, $accel: path) => {
+        fn process_block(state: &mut State<$base, 8>, block: &[u8; Self::BLOCKSIZE]) {
+            // Accelerated compression cannot report its rounds
+            #[cfg(feature = "trace")]
+            if crate::trace::enabled() {
+                return Self::compress(state, block);
+            }
+
+            let mut registers = state.read();
+            if $accel(&mut registers, block) {
+                state.update(registers);
+            } else {
+                Self::compress(state, block);
+            }
+        }
+
+        sha2_compression!(@compress $constants, $schedule_length, $base);
+    };
+    ($constants: expr, $schedule_length: tt, $base: ty) => {
+        fn process_block(state: &mut State<$base, 8>, block: &[u8; Self::BLOCKSIZE]) {
+            Self::compress(state, block);
+        }
+
+        sha2_compression!(@compress $constants, $schedule_length, $base);
+    };
+    (@compress $constants: expr, $schedule_length: tt, $base: ty) => {
+        /// Portable SHA2 compression
+        #[cfg(any(not(feature = "unrolled"), feature = "trace"))]
+        pub(crate) fn compress(state: &mut State<$base, 8>, block: &[u8; Self::BLOCKSIZE]) {
+            // Only the initial 16 words are created from the block, the rest of the schedule
+            // is computed as it is needed in a rolling window.
+            let mut w = MessageSchedule::<$base, 16>::from(block).0;
             let _state = state.read();
             let mut a = _state[0];
             let mut b = _state[1];
@@ -47,11 +90,19 @@ macro_rules! sha2_compression {
             let mut h = _state[7];
             
             for i in 0..$schedule_length {
+                // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16], where W[i-16] is the word being replaced
+                if i >= 16 {
+                    w[i%16].value = <$base>::lsigma1(w[(i-2)%16].value)
+                        .wrapping_add(w[(i-7)%16].value)
+                        .wrapping_add(<$base>::lsigma0(w[(i-15)%16].value))
+                        .wrapping_add(w[i%16].value);
+                }
+
                 let t1: $base = <$base>::usigma1(e)
                     .wrapping_add(choice(e, f, g))
                     .wrapping_add(h)
                     .wrapping_add($constants[i])
-                    .wrapping_add(schedule.0[i].value);
+                    .wrapping_add(w[i%16].value);
                 
                 let t2: $base = <$base>::usigma0(a)
                     .wrapping_add(majority(a, b, c));
@@ -65,7 +116,9 @@ macro_rules! sha2_compression {
                 b = a;
                 a = t1.wrapping_add(t2);
                 e = e.wrapping_add(t1);
-    
+
+                #[cfg(feature = "trace")]
+                crate::trace::round(i, [a, b, c, d, e, f, g, h].map(u64::from));
             }  
             
             // update the state
@@ -82,27 +135,151 @@ macro_rules! sha2_compression {
     
             state.update(new_state);
         }
+
+        /// Portable SHA2 compression with every round unrolled
+        #[cfg(all(feature = "unrolled", not(feature = "trace")))]
+        pub(crate) fn compress(state: &mut State<$base, 8>, block: &[u8; Self::BLOCKSIZE]) {
+            let mut w = MessageSchedule::<$base, 16>::from(block).0.map(|word| word.value);
+            let _state = state.read();
+            let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = _state;
+
+            sha2_unrolled_rounds!($schedule_length, $constants, w, $base, a, b, c, d, e, f, g, h);
+
+            // update the state
+            let new_state: [$base; 8] = [
+                _state[0].wrapping_add(a),
+                _state[1].wrapping_add(b),
+                _state[2].wrapping_add(c),
+                _state[3].wrapping_add(d),
+                _state[4].wrapping_add(e),
+                _state[5].wrapping_add(f),
+                _state[6].wrapping_add(g),
+                _state[7].wrapping_add(h)
+            ];
+
+            state.update(new_state);
+        }
     }
 }
 
+/// A single SHA2 round with a fixed index.
+///
+/// Rather than shifting every register along, the caller rotates which register
+/// plays which role, so only d and h are written.
+#[cfg(all(feature = "unrolled", not(feature = "trace")))]
+macro_rules! sha2_round {
+    ($i: expr, $constants: expr, $w: ident, $base: ty, $a: ident, $b: ident, $c: ident, $d: ident, $e: ident, $f: ident, $g: ident, $h: ident) => {
+        // W[i] = σ1(W[i−2]) + W[i−7] + σ0(W[i−15]) + W[i−16], offsets are taken mod 16
+        if $i >= 16 {
+            $w[$i%16] = <$base>::lsigma1($w[($i+14)%16])
+                .wrapping_add($w[($i+9)%16])
+                .wrapping_add(<$base>::lsigma0($w[($i+1)%16]))
+                .wrapping_add($w[$i%16]);
+        }
+
+        let t1: $base = <$base>::usigma1($e)
+            .wrapping_add(choice($e, $f, $g))
+            .wrapping_add($h)
+            .wrapping_add($constants[$i])
+            .wrapping_add($w[$i%16]);
+        let t2: $base = <$base>::usigma0($a)
+            .wrapping_add(majority($a, $b, $c));
+
+        $d = $d.wrapping_add(t1);
+        $h = t1.wrapping_add(t2);
+    };
+}
+
+/// Eight SHA2 rounds, after which every register is back in its original role
+#[cfg(all(feature = "unrolled", not(feature = "trace")))]
+macro_rules! sha2_eight_rounds {
+    ($i: expr, $constants: expr, $w: ident, $base: ty, $a: ident, $b: ident, $c: ident, $d: ident, $e: ident, $f: ident, $g: ident, $h: ident) => {
+        sha2_round!($i, $constants, $w, $base, $a, $b, $c, $d, $e, $f, $g, $h);
+        sha2_round!($i+1, $constants, $w, $base, $h, $a, $b, $c, $d, $e, $f, $g);
+        sha2_round!($i+2, $constants, $w, $base, $g, $h, $a, $b, $c, $d, $e, $f);
+        sha2_round!($i+3, $constants, $w, $base, $f, $g, $h, $a, $b, $c, $d, $e);
+        sha2_round!($i+4, $constants, $w, $base, $e, $f, $g, $h, $a, $b, $c, $d);
+        sha2_round!($i+5, $constants, $w, $base, $d, $e, $f, $g, $h, $a, $b, $c);
+        sha2_round!($i+6, $constants, $w, $base, $c, $d, $e, $f, $g, $h, $a, $b);
+        sha2_round!($i+7, $constants, $w, $base, $b, $c, $d, $e, $f, $g, $h, $a);
+    };
+}
+
+/// All the rounds of SHA256 (64) or SHA512 (80)
+#[cfg(all(feature = "unrolled", not(feature = "trace")))]
+macro_rules! sha2_unrolled_rounds {
+    (64, $($args: tt)*) => {
+        sha2_eight_rounds!(0, $($args)*);
+        sha2_eight_rounds!(8, $($args)*);
+        sha2_eight_rounds!(16, $($args)*);
+        sha2_eight_rounds!(24, $($args)*);
+        sha2_eight_rounds!(32, $($args)*);
+        sha2_eight_rounds!(40, $($args)*);
+        sha2_eight_rounds!(48, $($args)*);
+        sha2_eight_rounds!(56, $($args)*);
+    };
+    (80, $($args: tt)*) => {
+        sha2_unrolled_rounds!(64, $($args)*);
+        sha2_eight_rounds!(64, $($args)*);
+        sha2_eight_rounds!(72, $($args)*);
+    };
+}
+
 /// Macro to implement input padding for SHA2 hash functions
+/// 
+/// The length is kept in bytes as a u64 and only widened to the size of the padded
+/// length field here, so SHA384/512 don't need u128 arithmetic on every input.
 macro_rules! sha2_pad_fbuffer {
-    () => {
+    ($encoded: ty) => {
+        sha2_pad_fbuffer!(Self::BLOCKSIZE, $encoded);
+    };
+    // Generic engines can't name Self in the array length, so take the block size literally
+    ($block_size: expr, $encoded: ty) => {
         /// Pad the final buffer upon hash finalisation
-        fn pad_fbuffer(&self) -> Message<{Self::BLOCKSIZE}> {
-            let end_index = self.length as usize%Self::BLOCKSIZE;  //Data in the buffer past this end index has already been processed.
+        fn pad_fbuffer(&self) -> ([MessageBlock<{$block_size}>; 2], usize) {
+            let end_index = (self.length%$block_size as u64) as usize;  //Data in the buffer past this end index has already been processed.
             
-            // Create the final message blocks
-            let mut fmsg_data: Vec<u8> = vec![];
-            fmsg_data.extend_from_slice(&self.buffer[..end_index]);
-            fmsg_data.push(0x80);                             // append single '1' bit
-            while fmsg_data.len()%Self::BLOCKSIZE != Self::BLOCKSIZE-size_of_val(&self.length) {
-                fmsg_data.push(0x00);                         // pad with zeroes
+            // Create the final message blocks, appending the original data length in bits
+            let bit_length = <$encoded>::from(self.length)*8 + <$encoded>::from(self.bits);
+            MessageBlock::pad_bits(&self.buffer[..end_index], self.buffer[end_index], self.bits, &bit_length.to_be_bytes())
+        }
+    };
+}
+
+/// Macro to implement length extension for SHA2 hash functions with untruncated digests
+macro_rules! sha2_length_extension {
+    ($digest: ident, $base: ty) => {
+        /// Padding appended to a message of the given length in bytes before it is hashed.
+        /// 
+        /// The message followed by this padding is always a whole number of blocks.
+        #[cfg(feature = "alloc")]
+        pub fn glue_padding(message_length: u64) -> Vec<u8> {
+            let end_index = (message_length%Self::BLOCKSIZE as u64) as usize;
+            let (blocks, count) = MessageBlock::<{Self::BLOCKSIZE}>::pad(&[0; Self::BLOCKSIZE][..end_index], &(message_length as u128*8).to_be_bytes()[16-Self::BLOCKSIZE/8..]);
+            blocks[..count]
+                .iter()
+                .flat_map(|block| block.0)
+                .skip(end_index)
+                .collect()
+        }
+
+        /// Continue hashing from the digest of an unknown message of a known length (length extension).
+        /// 
+        /// The engine resumes after the original message and its glue_padding(), so the digest
+        /// of further input equals the digest of message || glue_padding(message_length) || input.
+        pub fn from_digest(digest: $digest, message_length: u64) -> Self {
+            const WORD: usize = ::core::mem::size_of::<$base>();
+            let mut registers: [$base; 8] = [0; 8];
+            for (register, chunk) in registers.iter_mut().zip(digest.0.chunks(WORD)) {
+                *register = <$base>::from_be_bytes(chunk.try_into().expect("chunk is word sized"));
             }
-            fmsg_data.extend((self.length*8).to_be_bytes());  // append original data length
-            assert_eq!(fmsg_data.len()%Self::BLOCKSIZE, 0);   // check the padded data mod blocksize is zero
-    
-            Message::new(fmsg_data)
+
+            // The padding is a 0x80 byte and the bit length, filled out with zeroes to a whole block
+            let block = Self::BLOCKSIZE as u64;
+            let padded_length = (message_length + 1 + block/8).div_ceil(block)*block;
+            let mut engine = Self::new();
+            engine.from_midstate(Midstate::new(registers, padded_length));
+            engine
         }
     };
 }
@@ -114,92 +291,182 @@ macro_rules! sha2_finalisation {
             assert!(self.buffer.len() <= Self::BLOCKSIZE); // check the buffer is less than or equal to one block size.
     
             // Get the final blocks
-            let fblocks: Vec<MessageBlock<{Self::BLOCKSIZE}>> = MessageBlock::from_message(self.pad_fbuffer());
-            
-            assert!(fblocks.len() <= 2);
-            for fblock in fblocks {
-                Self::process_block(&mut self.state, fblock);
+            let (fblocks, count) = self.pad_fbuffer();
+            for fblock in &fblocks[..count] {
+                Self::process_block(&mut self.state, &fblock.0);
             }
     
+            // Write the registers big endian, truncated to the digest size for SHA224 and SHA384
             let mut result = [0u8; $digest_size];
-            
-            result.copy_from_slice(
-                &self.state.read()
-                    .iter()
-                    .rev()
-                    .skip(((self.state.read().len() * size_of_val(&self.state.read()[0])) - $digest_size) / size_of_val(&self.state.read()[0]))
-                    .flat_map( |reg|
-                        reg.to_le_bytes()
-                    )
-                    .rev()
-                    .collect::<Vec<u8>>()
-            );
+            let registers = self.state.read();
+            for (chunk, reg) in result.chunks_mut(size_of_val(&registers[0])).zip(registers.iter()) {
+                chunk.copy_from_slice(&reg.to_be_bytes());
+            }
 
-            result
+            result.into()
         }
     }
 }
 
 
 
+/// Macro to define a SHA2 engine that compresses blocks with a pluggable backend.
+/// 
+/// The engine is built from the same macros as the other engines, only the
+/// compression of each block is handed to the backend B.
+macro_rules! sha2_backend_engine {
+    ($name: ident, $backend: ident, $digest: ident, $block_size: expr, $encoded: ty, $base: ty, $iconsts: expr, $digest_size: expr) => {
+        hash_struct!($name<B: $backend>, $block_size, u64, $base, 8);
+        impl_default!($name<B: $backend>, $iconsts, $block_size);
+
+        impl<B: $backend> HashEngine for $name<B> {
+            type Digest = $digest;
+            type Midstate = Midstate<$base, 8>;
+            const BLOCKSIZE: usize = $block_size;
+            const OUTPUT_SIZE: usize = $digest_size;
+
+            input_func!(u64);
+            iconst_funcs!($block_size);
+            midstate_funcs!(u64);
+            sha2_finalisation!($digest_size);
+        }
+
+        impl<B: $backend> $name<B> {
+            sha2_pad_fbuffer!($block_size, $encoded);
+            bit_input_func!();
+
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            fn process_block(state: &mut State<$base, 8>, block: &[u8; $block_size]) {
+                let mut registers = state.read();
+                B::compress(&mut registers, block);
+                state.update(registers);
+            }
+        }
+
+        impl_write!($name<B: $backend>);
+        impl_extend!($name<B: $backend>);
+        impl_fmt_write!($name<B: $backend>);
+    };
+}
+
 // Define the 4 SHA2 hash function structs and implementations here
+digest_struct!(Sha224Digest, 28);
+digest_struct!(Sha256Digest, 32);
+digest_struct!(Sha384Digest, 48);
+digest_struct!(Sha512Digest, 64);
+
+impl Sha256Digest {
+    /// Hash the digest again with SHA256, turning sha256(m) into sha256d(m)
+    pub fn sha256d(&self) -> Sha256Digest {
+        self.hash_again::<Sha256>()
+    }
+}
+
+impl Sha512Digest {
+    /// Split into the left and right 32 bytes, such as the key and chain code of BIP32
+    pub fn split(self) -> ([u8; 32], [u8; 32]) {
+        let mut left = [0u8; 32];
+        let mut right = [0u8; 32];
+        left.copy_from_slice(&self.0[..32]);
+        right.copy_from_slice(&self.0[32..]);
+        (left, right)
+    }
+}
+
 hash_struct!(Sha224, 64, u64, u32, 8);
 hash_struct!(Sha256, 64, u64, u32, 8);
-hash_struct!(Sha384, 128, u128, u64, 8);
-hash_struct!(Sha512, 128, u128, u64, 8);
+hash_struct!(Sha384, 128, u64, u64, 8);
+hash_struct!(Sha512, 128, u64, u64, 8);
 
 impl_default!(Sha224, SHA224_INITIAL_CONSTANTS, Self::BLOCKSIZE);
 impl_default!(Sha256, SHA256_INITIAL_CONSTANTS, Self::BLOCKSIZE);
 impl_default!(Sha384, SHA384_INITIAL_CONSTANTS, Self::BLOCKSIZE);
 impl_default!(Sha512, SHA512_INITIAL_CONSTANTS, Self::BLOCKSIZE);
 
+impl_write!(Sha224);
+impl_write!(Sha256);
+impl_write!(Sha384);
+impl_write!(Sha512);
+
+impl_extend!(Sha224);
+impl_extend!(Sha256);
+impl_extend!(Sha384);
+impl_extend!(Sha512);
+
+impl_fmt_write!(Sha224);
+impl_fmt_write!(Sha256);
+impl_fmt_write!(Sha384);
+impl_fmt_write!(Sha512);
+
+impl_hash_batch!(Sha224);
+impl_hash_batch!(Sha384);
+impl_hash_batch!(Sha512);
+
+impl_state_bytes!(Sha224, u64, u32, 8);
+impl_state_bytes!(Sha256, u64, u32, 8);
+impl_state_bytes!(Sha384, u64 as u128, u64, 8);
+impl_state_bytes!(Sha512, u64 as u128, u64, 8);
+
+sha2_backend_engine!(Sha224With, Sha256Backend, Sha224Digest, 64, u64, u32, SHA224_INITIAL_CONSTANTS, 28);
+sha2_backend_engine!(Sha256With, Sha256Backend, Sha256Digest, 64, u64, u32, SHA256_INITIAL_CONSTANTS, 32);
+sha2_backend_engine!(Sha384With, Sha512Backend, Sha384Digest, 128, u128, u64, SHA384_INITIAL_CONSTANTS, 48);
+sha2_backend_engine!(Sha512With, Sha512Backend, Sha512Digest, 128, u128, u64, SHA512_INITIAL_CONSTANTS, 64);
+
 impl HashEngine for Sha224 {
-    type Digest = [u8; 28];
-    type Midstate = [u32; 8];
+    type Digest = Sha224Digest;
+    type Midstate = Midstate<u32, 8>;
     const BLOCKSIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 28;
 
     input_func!(u64);
-    iconst_funcs!(SHA224_INITIAL_CONSTANTS, Self::BLOCKSIZE);
+    iconst_funcs!(Self::BLOCKSIZE);
     midstate_funcs!(u64);
     sha2_finalisation!(28);
 }
 
 impl HashEngine for Sha256 {
-    type Digest = [u8; 32];
-    type Midstate = [u32; 8];
+    type Digest = Sha256Digest;
+    type Midstate = Midstate<u32, 8>;
     const BLOCKSIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 32;
 
     input_func!(u64);
-    iconst_funcs!(SHA256_INITIAL_CONSTANTS, Self::BLOCKSIZE);
+    iconst_funcs!(Self::BLOCKSIZE);
     midstate_funcs!(u64);
     sha2_finalisation!(32);
 }
 
 impl HashEngine for Sha384 {
-    type Digest = [u8; 48];
-    type Midstate = [u64; 8];
+    type Digest = Sha384Digest;
+    type Midstate = Midstate<u64, 8>;
     const BLOCKSIZE: usize = 128;
+    const OUTPUT_SIZE: usize = 48;
 
-    input_func!(u128);
-    iconst_funcs!(SHA384_INITIAL_CONSTANTS, Self::BLOCKSIZE);
-    midstate_funcs!(u128);
+    input_func!(u64);
+    iconst_funcs!(Self::BLOCKSIZE);
+    midstate_funcs!(u64);
     sha2_finalisation!(48);
 }
 
 impl HashEngine for Sha512 {
-    type Digest = [u8; 64];
-    type Midstate = [u64; 8];
+    type Digest = Sha512Digest;
+    type Midstate = Midstate<u64, 8>;
     const BLOCKSIZE: usize = 128;
+    const OUTPUT_SIZE: usize = 64;
 
-    input_func!(u128);
-    iconst_funcs!(SHA512_INITIAL_CONSTANTS, Self::BLOCKSIZE);
-    midstate_funcs!(u128);
+    input_func!(u64);
+    iconst_funcs!(Self::BLOCKSIZE);
+    midstate_funcs!(u64);
     sha2_finalisation!(64);
 }
 
 impl Sha224 {
-    sha2_compression!(SHA256_ROUND_CONSTANTS, 64, u32);
-    sha2_pad_fbuffer!();
+    sha2_compression!(SHA256_ROUND_CONSTANTS, 64, u32, arch::sha256_compress);
+    sha2_pad_fbuffer!(u64);
+    bit_input_func!();
 
     pub fn new() -> Self {
         Self::default()
@@ -207,17 +474,257 @@ impl Sha224 {
 }
 
 impl Sha256 {
-    sha2_compression!(SHA256_ROUND_CONSTANTS, 64, u32);
-    sha2_pad_fbuffer!();
+    sha2_compression!(SHA256_ROUND_CONSTANTS, 64, u32, arch::sha256_compress);
+    sha2_pad_fbuffer!(u64);
+    bit_input_func!();
+    sha2_length_extension!(Sha256Digest, u32);
 
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Midstate of a block header in the layout used by mining hardware.
+    /// 
+    /// Only the first 64 bytes of the header are hashed, the remaining 16 bytes
+    /// (merkle root tail, time, bits and nonce) are what the miner iterates over.
+    pub fn header_midstate(header: &[u8]) -> [u8; 32] {
+        assert!(header.len() >= Self::BLOCKSIZE);
+
+        let mut engine = Self::new();
+        engine.input(&header[..Self::BLOCKSIZE]);
+        engine.midstate().to_miner_bytes()
+    }
+
+    /// Hash a batch of independent messages, returning the digests in the same order.
+    /// 
+    /// Messages are hashed in groups of four with hash4(), and with the rayon feature
+    /// the groups are spread across the rayon thread pool.
+    #[cfg(feature = "alloc")]
+    pub fn hash_batch<I>(msgs: &[I]) -> Vec<Sha256Digest>
+    where I: AsRef<[u8]> + Sync {
+        // Hash up to four messages, returning how many of the digests are used
+        let hash_group = |group: &[I]| -> ([Sha256Digest; 4], usize) {
+            if let [a, b, c, d] = group {
+                return (Self::hash4([a.as_ref(), b.as_ref(), c.as_ref(), d.as_ref()]), 4);
+            }
+
+            let mut digests = [Sha256Digest::from([0; 32]); 4];
+            for (digest, msg) in digests.iter_mut().zip(group.iter()) {
+                *digest = Self::new().chain(msg).finalise();
+            }
+            (digests, group.len())
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            msgs.par_chunks(4)
+                .flat_map_iter(|group| {
+                    let (digests, n) = hash_group(group);
+                    IntoIterator::into_iter(digests).take(n)
+                })
+                .collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        msgs.chunks(4)
+            .flat_map(|group| {
+                let (digests, n) = hash_group(group);
+                IntoIterator::into_iter(digests).take(n)
+            })
+            .collect()
+    }
+
+    /// Hash four independent messages at once.
+    /// 
+    /// Blocks from the four messages are compressed side by side with SIMD (multi-buffer hashing)
+    /// on CPUs where that is faster, otherwise each message is compressed on its own.
+    pub fn hash4(messages: [&[u8]; 4]) -> [Sha256Digest; 4] {
+        // The final padded blocks of each message and how many blocks each message has in total
+        let tails = messages.map(|msg| {
+            MessageBlock::pad(&msg[msg.len()/Self::BLOCKSIZE*Self::BLOCKSIZE..], &(msg.len() as u64*8).to_be_bytes())
+        });
+        let mut counts = [0; 4];
+        for (count, (msg, tail)) in counts.iter_mut().zip(messages.iter().zip(tails.iter())) {
+            *count = msg.len()/Self::BLOCKSIZE + tail.1;
+        }
+
+        // Get the jth block of a message, either straight from the message or from its padded tail
+        let block = |l: usize, j: usize| -> &[u8; Self::BLOCKSIZE] {
+            let full = messages[l].len()/Self::BLOCKSIZE;
+            if j < full {
+                messages[l][j*Self::BLOCKSIZE..(j+1)*Self::BLOCKSIZE].try_into().expect("chunk is block sized")
+            } else {
+                &tails[l].0[j-full].0
+            }
+        };
+
+        let mut states = [SHA256_INITIAL_CONSTANTS; 4];
+        for j in 0..counts.iter().copied().max().unwrap_or(0) {
+            if counts.iter().all(|count| j < *count) && arch::sha256_compress4(&mut states, [block(0, j), block(1, j), block(2, j), block(3, j)]) {
+                continue;
+            }
+
+            for (l, registers) in states.iter_mut().enumerate() {
+                if j < counts[l] {
+                    let mut state = State::init(*registers);
+                    Self::process_block(&mut state, block(l, j));
+                    *registers = state.read();
+                }
+            }
+        }
+
+        states.map(|registers| {
+            let mut result = [0u8; 32];
+            for (chunk, reg) in result.chunks_mut(4).zip(registers.iter()) {
+                chunk.copy_from_slice(&reg.to_be_bytes());
+            }
+            result.into()
+        })
+    }
+}
+
+/// SHA256 of messages that all have the same, known length.
+/// 
+/// The final padded blocks, including the encoded length, are built once on creation.
+/// Hashing a message only copies its trailing bytes into a copy of them, which suits
+/// hashing many block headers or intermediate digests.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha256Fixed {
+    length: usize,
+    padding: [MessageBlock<64>; 2],
+    count: usize
+}
+
+impl Sha256Fixed {
+    /// Prepare the padding for messages of the given length in bytes
+    pub fn new(length: usize) -> Self {
+        let (padding, count) = MessageBlock::pad(&[0; 64][..length%64], &(length as u64*8).to_be_bytes());
+
+        Self {
+            length,
+            padding,
+            count
+        }
+    }
+
+    /// Length of the messages this hasher accepts
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Hash a message.
+    /// 
+    /// Panics if the message is not the length given on creation.
+    pub fn hash(&self, msg: &[u8]) -> Sha256Digest {
+        self.hash_from_midstate(Midstate::new(SHA256_INITIAL_CONSTANTS, 0), msg)
+    }
+
+    /// Hash the rest of a message that was started elsewhere and captured as a midstate.
+    /// 
+    /// Panics if the midstate is not block aligned, or if the midstate and the rest
+    /// together are not the length given on creation.
+    pub fn hash_from_midstate(&self, midstate: Midstate<u32, 8>, rest: &[u8]) -> Sha256Digest {
+        assert!(midstate.length() % 64 == 0, "midstate must be block aligned");
+        assert_eq!(midstate.length() as usize + rest.len(), self.length, "message length does not match");
+
+        let mut state = State::init(midstate.registers());
+        let mut blocks = rest.chunks_exact(64);
+        for block in &mut blocks {
+            Sha256::process_block(&mut state, block.try_into().expect("chunk is block sized"));
+        }
+
+        let mut padding = self.padding;
+        padding[0].0[..blocks.remainder().len()].copy_from_slice(blocks.remainder());
+        for block in &padding[..self.count] {
+            Sha256::process_block(&mut state, &block.0);
+        }
+
+        let mut result = [0u8; 32];
+        for (chunk, reg) in result.chunks_mut(4).zip(state.read().iter()) {
+            chunk.copy_from_slice(&reg.to_be_bytes());
+        }
+        result.into()
+    }
+}
+
+/// Padding block following a 64 byte message: the '1' bit and a length of 512 bits
+const PADDING_64: [u8; 64] = {
+    let mut block = [0u8; 64];
+    block[0] = 0x80;
+    block[62] = 0x02;
+    block
+};
+
+/// Double SHA256 of exactly 64 bytes, as used for merkle tree nodes.
+/// 
+/// The padding of the 64 byte message and of the 32 byte intermediate digest are both
+/// constant, so the three compressions are run directly without any buffering.
+pub fn sha256d_64(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    block[..32].copy_from_slice(left);
+    block[32..].copy_from_slice(right);
+
+    let mut state = State::init(SHA256_INITIAL_CONSTANTS);
+    Sha256::process_block(&mut state, &block);
+    Sha256::process_block(&mut state, &PADDING_64);
+
+    // Second hash over the 32 byte digest, padded with the '1' bit and a length of 256 bits
+    let mut block = [0u8; 64];
+    for (chunk, reg) in block.chunks_mut(4).zip(state.read().iter()) {
+        chunk.copy_from_slice(&reg.to_be_bytes());
+    }
+    block[32] = 0x80;
+    block[62] = 0x01;
+
+    let mut state = State::init(SHA256_INITIAL_CONSTANTS);
+    Sha256::process_block(&mut state, &block);
+
+    let mut result = [0u8; 32];
+    for (chunk, reg) in result.chunks_mut(4).zip(state.read().iter()) {
+        chunk.copy_from_slice(&reg.to_be_bytes());
+    }
+    result
+}
+
+/// The SHA256 compression function, processing a single block into the state registers.
+/// 
+/// No padding or length is added, so the caller is responsible for the message layout.
+/// Uses the accelerated compression when the CPU supports it.
+pub fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut s = State::init(*state);
+    Sha256::process_block(&mut s, block);
+    *state = s.read();
+}
+
+/// The SHA512 compression function, processing a single block into the state registers.
+/// 
+/// No padding or length is added, so the caller is responsible for the message layout.
+/// Uses the accelerated compression when the CPU supports it.
+pub fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) {
+    let mut s = State::init(*state);
+    Sha512::process_block(&mut s, block);
+    *state = s.read();
+}
+
+impl Midstate<u32, 8> {
+    /// Serialize the registers as little endian words.
+    /// 
+    /// This is the byte order ASIC firmwares and getwork/Stratum job dispatchers
+    /// expect a SHA256 midstate in, unlike the big endian words of to_bytes().
+    pub fn to_miner_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, r) in bytes.chunks_mut(4).zip(self.registers().iter()) {
+            chunk.copy_from_slice(&r.to_le_bytes());
+        }
+        bytes
+    }
 }
 
 impl Sha384 {
-    sha2_compression!(SHA512_ROUND_CONSTANTS, 80, u64);
-    sha2_pad_fbuffer!();
+    sha2_compression!(SHA512_ROUND_CONSTANTS, 80, u64, arch::sha512_compress);
+    sha2_pad_fbuffer!(u128);
+    bit_input_func!();
 
     pub fn new() -> Self {
         Self::default()
@@ -225,8 +732,10 @@ impl Sha384 {
 }
 
 impl Sha512 {
-    sha2_compression!(SHA512_ROUND_CONSTANTS, 80, u64);
-    sha2_pad_fbuffer!();
+    sha2_compression!(SHA512_ROUND_CONSTANTS, 80, u64, arch::sha512_compress);
+    sha2_pad_fbuffer!(u128);
+    bit_input_func!();
+    sha2_length_extension!(Sha512Digest, u64);
 
     pub fn new() -> Self {
         Self::default()
@@ -236,7 +745,9 @@ impl Sha512 {
 
 #[cfg(test)]
 mod tests {
-    use super::{HashEngine, Sha224, Sha256, Sha384, Sha512};
+    use super::{HashEngine, Sha224, Sha256, Sha384, Sha512, Sha224Digest, Sha256Digest, State, SHA256_INITIAL_CONSTANTS, SHA512_INITIAL_CONSTANTS, arch};
+    use crate::core::{hex, Midstate};
+    use crate::error::{Error, HexError};
 
     #[test]
     fn sha224() {
@@ -251,7 +762,7 @@ mod tests {
         for case in cases {
             let mut hasher = Sha224::new();
             hasher.input(&case.0);
-            let digest = hasher.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let digest = hasher.finalise().to_string();
             assert_eq!(digest, case.1);
         }
     }
@@ -275,12 +786,42 @@ mod tests {
         for case in cases {
             let mut hasher = Sha256::new();
             hasher.input(&case.0);
-            let digest = hasher.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let digest = hasher.finalise().to_string();
             assert_eq!(digest, case.1);
 
         }
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn write() {
+        // Stream data into the engine through std::io
+        let mut hasher = Sha256::new();
+        let mut data: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        std::io::copy(&mut data, &mut hasher).unwrap();
+        let digest = hasher.finalise().to_string();
+        assert_eq!(digest, "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn input_vectored() {
+        use std::io::IoSlice;
+
+        // Split the input across buffers that straddle the block boundary
+        let data = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+        let mut hasher = Sha512::new();
+        hasher.input_vectored(&[IoSlice::new(&data[..3]), IoSlice::new(&[]), IoSlice::new(&data[3..100]), IoSlice::new(&data[100..])]);
+        let digest = hasher.finalise().to_string();
+        assert_eq!(digest, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909");
+
+        let mut hasher = Sha256::new();
+        hasher.input_vectored(&[IoSlice::new(&data[..60]), IoSlice::new(&data[60..70])]);
+        let mut expected = Sha256::new();
+        expected.input(&data[..70]);
+        assert_eq!(hasher.finalise(), expected.finalise());
+    }
+
     #[ignore]
     #[test]
     fn h_ps() {
@@ -292,8 +833,8 @@ mod tests {
         loop {
             e.input([0; 64]);
             e.finalise();
-            println!("[{}] {}", i, e.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>());
-            i = i+1;
+            println!("[{}] {}", i, e.finalise());
+            i += 1;
 
             if now.elapsed().as_secs() == 1 {
                 break;
@@ -316,7 +857,7 @@ mod tests {
         for case in cases {
             let mut hasher = Sha384::new();
             hasher.input(&case.0);
-            let digest = hasher.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let digest = hasher.finalise().to_string();
             assert_eq!(digest, case.1);
         }
     }
@@ -334,8 +875,564 @@ mod tests {
         for case in cases {
             let mut hasher = Sha512::new();
             hasher.input(&case.0);
-            let digest = hasher.finalise().iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let digest = hasher.finalise().to_string();
+            assert_eq!(digest, case.1);
+        }
+    }
+
+    #[test]
+    fn digest_formatting() {
+        let digest = Sha256::new().finalise();
+        let hex = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(format!("{}", digest), hex);
+        assert_eq!(format!("{:x}", digest), hex);
+        assert_eq!(format!("{:X}", digest), hex.to_uppercase());
+        assert_eq!(format!("{:?}", digest), hex);
+        assert_eq!(digest.len(), 32);
+    }
+
+    #[test]
+    fn digest_from_hex() {
+        let hex = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let digest: Sha256Digest = hex.parse().unwrap();
+        assert_eq!(digest, Sha256::new().finalise());
+        assert_eq!(Sha256Digest::from_hex(&hex.to_uppercase()), Ok(digest));
+
+        assert_eq!(Sha256Digest::from_hex("e3b0"), Err(HexError::InvalidLength { expected: 64, got: 4 }));
+        assert_eq!(Sha256Digest::from_hex(&hex.replace('e', "g")), Err(HexError::InvalidChar('g')));
+
+        // Characters outside ASCII are reported whole, and counted as one character
+        assert_eq!(Sha256Digest::from_hex(&hex.replacen('e', "é", 1)), Err(HexError::InvalidChar('é')));
+        assert_eq!(Sha256Digest::from_hex(&hex.replacen("e3", "é", 1)), Err(HexError::InvalidLength { expected: 64, got: 63 }));
+        assert_eq!(Sha256Digest::from_hex(&hex.replacen("e3", "€", 1)), Err(HexError::InvalidLength { expected: 64, got: 63 }));
+    }
+
+    #[test]
+    fn structured_input() {
+        let cases: Vec<(u64, &[u8])> = vec![
+            (0xfc, &[0xfc]),
+            (0xfd, &[0xfd, 0xfd, 0x00]),
+            (0x10000, &[0xfe, 0x00, 0x00, 0x01, 0x00]),
+            (0x100000000, &[0xff, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00])
+        ];
+        for (n, encoded) in cases {
+            let mut engine = Sha256::new();
+            engine.input_compact_size(n);
+            assert_eq!(engine.pending(), encoded);
+        }
+
+        let mut engine = Sha256::new();
+        engine.input_u32_le(0x01020304);
+        engine.input_u64_le(0x05);
+        engine.input_slice_with_len(b"abc");
+        assert_eq!(engine.pending(), [4, 3, 2, 1, 5, 0, 0, 0, 0, 0, 0, 0, 3, b'a', b'b', b'c']);
+    }
+
+    #[test]
+    fn hash_again() {
+        let digest = Sha256::new().chain(b"abc").finalise();
+        assert_eq!(digest.sha256d().to_string(), "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358");
+        assert_eq!(digest.hash_again::<crate::Ripemd160>().to_string(), "bb1be98c142444d7a56aa3981c3942a978e4dc33");
+    }
+
+    #[test]
+    fn fmt_write() {
+        use ::core::fmt::Write;
+
+        let (a, bc) = ('a', "bc");
+        let mut engine = Sha256::new();
+        write!(engine, "{}{}", a, bc).unwrap();
+        assert_eq!(engine.finalise().to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let (height, txid) = (840000, "abc");
+        let mut engine = crate::Sha256d::new();
+        write!(engine, "{}:{}", height, txid).unwrap();
+        assert_eq!(engine.finalise(), crate::Sha256d::new().chain(b"840000:abc").finalise());
+    }
+
+    #[test]
+    fn split_digest() {
+        let (left, right) = Sha512::new().chain(b"abc").finalise().split();
+        assert_eq!(left, Sha256Digest::from_hex("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a").unwrap().0);
+        assert_eq!(right, Sha256Digest::from_hex("2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f").unwrap().0);
+    }
+
+    #[test]
+    fn digest_ordering() {
+        let empty = Sha256::new().finalise();
+        let abc = Sha256::new().chain(b"abc").finalise();
+        assert!(abc < empty);
+
+        let mut sorted = std::collections::BTreeSet::new();
+        sorted.insert(empty);
+        sorted.insert(abc);
+        sorted.insert(abc);
+        assert_eq!(sorted.into_iter().collect::<Vec<_>>(), vec![abc, empty]);
+
+        let bytes: [u8; 32] = empty.into();
+        assert_eq!(Sha256Digest::from(bytes), empty);
+        assert_eq!(AsRef::<[u8; 32]>::as_ref(&empty), &bytes);
+        assert_eq!(empty.into_inner(), bytes);
+    }
+
+    #[test]
+    fn display_backwards() {
+        // Bitcoin genesis block header
+        let header: [u8; 80] = hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c");
+        let mut hasher = Sha256::new();
+        hasher.input(header);
+        let first = hasher.finalise();
+        hasher.reset();
+        hasher.input(first);
+        let hash = hasher.finalise();
+
+        let block_hash = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        #[cfg(feature = "alloc")]
+        assert_eq!(hash.to_hex_reversed(), block_hash);
+        assert_eq!(format!("{}", hash.display_backwards()), block_hash);
+        assert_eq!(Sha256Digest::from_hex_reversed(block_hash), Ok(hash));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn midstate_bytes() {
+        let mut hasher = Sha256::new();
+        hasher.input([0x61; 100]);
+        let midstate = hasher.midstate();
+        assert_eq!(midstate.length(), 64);
+
+        let bytes = midstate.to_bytes();
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(Midstate::<u32, 8>::from_bytes(&bytes), Ok(midstate));
+        assert_eq!(Midstate::<u32, 8>::from_bytes(&bytes[1..]), Err(Error::InvalidMidstate));
+
+        let mut resumed = Sha256::new();
+        resumed.from_midstate(Midstate::<u32, 8>::from_bytes(&bytes).unwrap());
+        resumed.input([0x61; 36]);
+        assert_eq!(resumed.finalise(), hasher.finalise());
+    }
+
+    #[test]
+    fn header_midstate() {
+        // Bitcoin genesis block header
+        let header: [u8; 80] = hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c");
+        assert_eq!(Sha256::header_midstate(&header), hex("339a90bcf0bf58637daccc90a8ca591ee9d8c8c3c803014f3687b1961bf91947"));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn state_bytes() {
+        let mut hasher = Sha512::new();
+        hasher.input([0x61; 200]);
+        let bytes = hasher.to_state_bytes();
+        assert_eq!(bytes.len(), 64 + 64 + 16 + 1 + 72);
+
+        let mut resumed = Sha512::from_state_bytes(&bytes).unwrap();
+        resumed.input(b"abc");
+        hasher.input(b"abc");
+        assert_eq!(resumed.finalise(), hasher.finalise());
+
+        assert_eq!(Sha512::from_state_bytes(&bytes[..bytes.len()-1]).err(), Some(Error::InvalidState));
+        assert_eq!(Sha512::from_state_bytes(&bytes[..10]).err(), Some(Error::InvalidState));
+
+        // The length is serialized as 128 bits but lengths past 2^64 bits are rejected
+        let mut long = bytes.clone();
+        long[128+7] = 1;
+        assert_eq!(Sha512::from_state_bytes(&long).err(), Some(Error::InvalidState));
+
+        // A partial byte from input_bits() is kept
+        hasher.input_bits(&[0x68], 5);
+        let bytes = hasher.to_state_bytes();
+        assert_eq!(bytes.len(), 64 + 64 + 16 + 1 + 76);
+        assert_eq!(Sha512::from_state_bytes(&bytes), Ok(hasher));
+        assert_eq!(Sha512::from_state_bytes(&bytes).unwrap().finalise(), hasher.finalise());
+        assert_eq!(Sha512::from_state_bytes(&bytes[..bytes.len()-1]).err(), Some(Error::InvalidState));
+        let mut bad = bytes.clone();
+        bad[144] = 8;
+        assert_eq!(Sha512::from_state_bytes(&bad).err(), Some(Error::InvalidState));
+
+        // A custom IV is kept, so the resumed engine still resets to it
+        let iv = crate::constants::SHA224_INITIAL_CONSTANTS;
+        let engine = Sha256::with_iv(iv).chain([0x61; 100]);
+        let mut resumed = Sha256::from_state_bytes(&engine.to_state_bytes()).unwrap();
+        assert_eq!(resumed, engine);
+        assert_eq!(resumed.iv(), iv);
+        resumed.reset();
+        assert_eq!(resumed, Sha256::with_iv(iv));
+    }
+
+    #[test]
+    fn try_from_midstate() {
+        let mut hasher = Sha256::new();
+        let midstate = Midstate::new(Sha256::new().midstate().registers(), 10);
+        assert_eq!(hasher.try_from_midstate(midstate), Err(Error::UnalignedMidstate));
+        assert_eq!(hasher.try_from_midstate(Midstate::new(midstate.registers(), 64)), Ok(()));
+    }
+
+    #[test]
+    fn finalise_copy() {
+        let mut hasher = Sha256::new();
+        hasher.input(b"abc");
+        assert_eq!(hasher.finalise_copy().to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        hasher.input(b"dbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+        assert_eq!(hasher.finalise_copy(), hasher.finalise());
+    }
+
+    #[test]
+    fn finalise_into() {
+        let mut frame = [0xffu8; 40];
+        let mut hasher = Sha224::new();
+        hasher.input(b"abc");
+        hasher.finalise_into(&mut frame[4..]);
+        assert_eq!(frame[..4], [0xff; 4]);
+        assert_eq!(frame[4..32], Sha224Digest::from_hex("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7").unwrap()[..]);
+        assert_eq!(frame[32..], [0xff; 8]);
+    }
+
+    #[test]
+    fn chain() {
+        let digest = Sha256::new().chain(b"a").chain(b"bc").finalise().to_string();
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn input_iter() {
+        let fields = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+        let mut hasher = Sha256::new();
+        hasher.input_iter(&fields);
+        assert_eq!(hasher.finalise().to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let mut hasher = Sha256::new();
+        hasher.extend((0..200).map(|_| 0x61));
+        let mut expected = Sha256::new();
+        expected.input([0x61; 200]);
+        assert_eq!(hasher.finalise(), expected.finalise());
+    }
+
+    #[test]
+    fn output_size() {
+        assert_eq!(Sha224::OUTPUT_SIZE, Sha224::new().finalise().len());
+        assert_eq!(Sha256::OUTPUT_SIZE, Sha256::new().finalise().len());
+        assert_eq!(Sha384::output_size(), Sha384::new().finalise().len());
+        assert_eq!(Sha512::output_size(), Sha512::new().finalise().len());
+    }
+
+    #[test]
+    fn padding_boundaries() {
+        // Lengths around where the encoded length no longer fits in the final block
+        let cases: Vec<(usize, &str)> = vec![
+            (55, "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"),
+            (56, "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"),
+            (64, "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb")
+        ];
+        for case in cases {
+            let digest = Sha256::new().chain(vec![0x61; case.0]).finalise().to_string();
+            assert_eq!(digest, case.1);
+        }
+
+        let cases: Vec<(usize, &str)> = vec![
+            (111, "fa9121c7b32b9e01733d034cfc78cbf67f926c7ed83e82200ef86818196921760b4beff48404df811b953828274461673c68d04e297b0eb7b2b4d60fc6b566a2"),
+            (112, "c01d080efd492776a1c43bd23dd99d0a2e626d481e16782e75d54c2503b5dc32bd05f0f1ba33e568b88fd2d970929b719ecbb152f58f130a407c8830604b70ca")
+        ];
+        for case in cases {
+            let digest = Sha512::new().chain(vec![0x61; case.0]).finalise().to_string();
             assert_eq!(digest, case.1);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn aligned_input() {
+        // Whole blocks compressed straight from the input must match data fed through the buffer
+        let data: Vec<u8> = (0..1000u32).map(|x| x as u8).collect();
+        let mut bytewise = Sha256::new();
+        data.iter().for_each(|x| bytewise.input([*x]));
+        assert_eq!(Sha256::new().chain(&data).finalise(), bytewise.finalise());
+        assert_eq!(Sha256::new().chain(&data[..3]).chain(&data[3..]).finalise(), Sha256::new().chain(&data).finalise());
+
+        let mut bytewise = Sha512::new();
+        data.iter().for_each(|x| bytewise.input([*x]));
+        assert_eq!(Sha512::new().chain(&data).finalise(), bytewise.finalise());
+    }
+
+    #[test]
+    fn accelerated_compression() {
+        // The hardware backend, if the CPU has one, must agree with the portable compression
+        let mut block = [0u8; 64];
+        let mut portable = State::init(SHA256_INITIAL_CONSTANTS);
+        let mut accelerated = SHA256_INITIAL_CONSTANTS;
+        for i in 0..64 {
+            block.iter_mut().enumerate().for_each(|(j, x)| *x = (i*j) as u8 ^ 0xa5);
+            Sha256::compress(&mut portable, &block);
+            if !arch::sha256_compress(&mut accelerated, &block) {
+                return;
+            }
+            assert_eq!(portable.read(), accelerated);
+        }
+    }
+
+    #[test]
+    fn accelerated_compression_512() {
+        let mut block = [0u8; 128];
+        let mut portable = State::init(SHA512_INITIAL_CONSTANTS);
+        let mut accelerated = SHA512_INITIAL_CONSTANTS;
+        for i in 0..64 {
+            block.iter_mut().enumerate().for_each(|(j, x)| *x = (i*j) as u8 ^ 0xa5);
+            Sha512::compress(&mut portable, &block);
+            if !arch::sha512_compress(&mut accelerated, &block) {
+                return;
+            }
+            assert_eq!(portable.read(), accelerated);
+        }
+    }
+
+    #[test]
+    fn hash4() {
+        // Messages of different lengths, including an empty one, finish in different blocks
+        let data: Vec<u8> = (0..300u32).map(|x| x as u8).collect();
+        let messages = [&data[..0], &data[..55], &data[..64], &data[..300]];
+        let digests = Sha256::hash4(messages);
+        for (digest, msg) in digests.iter().zip(messages.iter()) {
+            assert_eq!(*digest, Sha256::new().chain(msg).finalise());
+        }
+    }
+
+    #[test]
+    fn custom_backend() {
+        use crate::backend::Portable;
+        use super::{Sha256With, Sha512With, Sha256Backend};
+        use ::core::sync::atomic::{AtomicUsize, Ordering};
+
+        // A backend that counts the blocks it is given and hands them on to the portable compression
+        static BLOCKS: AtomicUsize = AtomicUsize::new(0);
+        struct Counting;
+        impl Sha256Backend for Counting {
+            fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+                BLOCKS.fetch_add(1, Ordering::SeqCst);
+                Portable::compress(state, block);
+            }
+        }
+
+        let data = [0x61; 100];
+        let digest = Sha256With::<Counting>::new().chain(data).finalise();
+        assert_eq!(digest, Sha256::new().chain(data).finalise());
+        assert_eq!(BLOCKS.load(Ordering::SeqCst), 2);
+
+        let digest = Sha512With::<Portable>::new().chain(data).finalise();
+        assert_eq!(digest, Sha512::new().chain(data).finalise());
+
+        let digest = super::Sha224With::<Portable>::new().chain(data).finalise();
+        assert_eq!(digest, Sha224::new().chain(data).finalise());
+        let digest = super::Sha384With::<Portable>::new().chain(data).finalise();
+        assert_eq!(digest, Sha384::new().chain(data).finalise());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn hash_batch() {
+        let data: Vec<u8> = (0..300u32).map(|x| x as u8).collect();
+        let messages: Vec<&[u8]> = (0..11).map(|i| &data[..i*27]).collect();
+
+        let digests = Sha256::hash_batch(&messages);
+        assert_eq!(digests.len(), messages.len());
+        for (digest, msg) in digests.iter().zip(messages.iter()) {
+            assert_eq!(*digest, Sha256::new().chain(msg).finalise());
+        }
+
+        let digests = Sha512::hash_batch(&messages);
+        for (digest, msg) in digests.iter().zip(messages.iter()) {
+            assert_eq!(*digest, Sha512::new().chain(msg).finalise());
+        }
+    }
+
+    #[test]
+    fn sha256d_64() {
+        let mut left = [0u8; 32];
+        let mut right = [0u8; 32];
+        left.iter_mut().enumerate().for_each(|(i, x)| *x = i as u8);
+        right.iter_mut().enumerate().for_each(|(i, x)| *x = 32 + i as u8);
+        let digest = super::sha256d_64(&left, &right);
+        assert_eq!(Sha256Digest::from(digest).to_string(), "01c9f464780a1b6af4eb400fe2f2896cfb2169f5a65701439e4c2c4e213903ef");
+
+        let inner = Sha256::new().chain(left).chain(right).finalise();
+        assert_eq!(Sha256Digest::from(digest), Sha256::new().chain(inner).finalise());
+    }
+
+    #[test]
+    fn fixed_length() {
+        // Genesis block header
+        let header: [u8; 80] = hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c");
+        let headers = super::Sha256Fixed::new(80);
+        let digest = headers.hash(&header);
+        assert_eq!(digest.to_string(), "af42031e805ff493a07341e2f74ff58149d22ab9ba19f61343e2c86c71c5d66d");
+
+        // Second pass of sha256d
+        let digests = super::Sha256Fixed::new(32);
+        assert_eq!(digests.hash(digest.as_ref()).display_backwards().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+
+        // Resuming after the first block of the header
+        let midstate = Sha256::new().chain(&header[..64]).midstate();
+        assert_eq!(headers.hash_from_midstate(midstate, &header[64..]), digest);
+
+        for len in [0, 55, 56, 64, 119, 120, 200].iter() {
+            let msg = vec![0x61; *len];
+            assert_eq!(super::Sha256Fixed::new(*len).hash(&msg), Sha256::new().chain(&msg).finalise());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn length_extension() {
+        // Only the digest and length of secret || data are known
+        let message = b"secretkeyuser=alice";
+        let padding = Sha256::glue_padding(message.len() as u64);
+        assert_eq!(padding.len(), 45);
+        let digest = Sha256Digest::from_hex("f751bfcc35301459972d7ef8141a20071a4cc53c84cd5eb784d001f9619843d7").unwrap();
+        let forged = Sha256::from_digest(digest, message.len() as u64).chain(b";admin=true").finalise();
+        assert_eq!(forged.to_string(), "55965f129e4b53e7cfee68f622667470bea5c958ef8fa41acaaa66f119d5f735");
+        assert_eq!(forged, Sha256::new().chain(message).chain(&padding).chain(b";admin=true").finalise());
+
+        let digest = Sha512::new().chain(message).finalise();
+        let forged = Sha512::from_digest(digest, message.len() as u64).chain(b";admin=true").finalise();
+        assert_eq!(forged.to_string(), "61756c4a3edb13946a331e9b05ffd91d2c3fecc5b9abe333cbe801b50fe68b1ffaea7a5bef079487852916a3a3870ffa5d185e669693275504f2c45006fccd39");
+
+        for len in [0u64, 55, 56, 63, 64, 111, 112, 128] {
+            assert_eq!((len + Sha256::glue_padding(len).len() as u64)%64, 0);
+            assert_eq!((len + Sha512::glue_padding(len).len() as u64)%128, 0);
+        }
+    }
+
+    #[test]
+    fn custom_iv() {
+        // SHA512/256 is SHA512 from a different IV, truncated to 256 bits
+        let iv = [
+            0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+            0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2
+        ];
+        let mut engine = Sha512::with_iv(iv);
+        engine.input(b"abc");
+        let digest = engine.finalise_reset();
+        assert_eq!(&digest.to_string()[..64], "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23");
+        assert_eq!(engine.iv(), iv);
+        assert_eq!(engine.chain(b"abc").finalise(), digest);
+
+        // SHA224 is SHA256 from a different IV, truncated to 224 bits
+        let digest = Sha256::with_iv(crate::constants::SHA224_INITIAL_CONSTANTS).chain(b"abc").finalise();
+        assert_eq!(&digest.to_string()[..56], Sha224::new().chain(b"abc").finalise().to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn wipe_reset() {
+        // Wiping keeps the IV, so the engine still hashes from it afterwards
+        let iv = crate::constants::SHA224_INITIAL_CONSTANTS;
+        let mut engine = Sha256::with_iv(iv);
+        engine.input(b"secret message");
+        engine.wipe();
+        assert!(engine == Sha256::with_iv(iv));
+        engine.reset();
+        assert_eq!(engine.chain(b"abc").finalise(), Sha256::with_iv(iv).chain(b"abc").finalise());
+    }
+
+    #[test]
+    fn compression_function() {
+        // A single padded block of "abc"
+        let mut block = [0u8; 64];
+        block[..4].copy_from_slice(&[0x61, 0x62, 0x63, 0x80]);
+        block[63] = 24;
+        let mut state = crate::constants::SHA256_INITIAL_CONSTANTS;
+        super::sha256_compress(&mut state, &block);
+        let hex: String = state.iter().map(|r| format!("{:08x}", r)).collect();
+        assert_eq!(hex, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let mut block = [0u8; 128];
+        block[..4].copy_from_slice(&[0x61, 0x62, 0x63, 0x80]);
+        block[127] = 24;
+        let mut state = crate::constants::SHA512_INITIAL_CONSTANTS;
+        super::sha512_compress(&mut state, &block);
+        let hex: String = state.iter().map(|r| format!("{:016x}", r)).collect();
+        assert_eq!(hex, "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f");
+    }
+
+    #[test]
+    fn bit_input() {
+        // NIST CAVP SHA256ShortMsg, Len = 5
+        let mut engine = Sha256::new();
+        engine.input_bits(&[0x68], 5);
+        assert_eq!(engine.finalise_reset().to_string(), "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95");
+
+        // Partial bytes that end at the last byte before the length, and spill into a second block
+        let data: Vec<u8> = (0..70).chain(Some(0xff)).collect();
+        engine.input(&data[..40]);
+        engine.input_bits(&data[40..55].iter().copied().chain(Some(0xff)).collect::<Vec<u8>>(), 15*8 + 7);
+        assert_eq!(engine.finalise_reset().to_string(), "5a9f2a774ef5012d47e7d6ec37b05faf23c4fd98cdf684baf5e00b43fdef4b2c");
+        engine.input_bits(&data, 70*8 + 7);
+        assert_eq!(engine.finalise().to_string(), "41fb5222137ac01ba96988932ed5c72ed871c8ae39f3b8eddb9df59498969860");
+    }
+
+    #[test]
+    #[should_panic(expected = "no input may follow a partial byte")]
+    fn bits_after_bits() {
+        // A second partial byte would replace the one held for finalisation
+        let mut engine = Sha256::new();
+        engine.input_bits(&[0x68], 5);
+        engine.input_bits(&[0x80], 1);
+    }
+
+    #[test]
+    fn midstate_after_bits() {
+        // Restoring a midstate drops a partial byte left by input_bits(), so input can follow
+        let mut engine = Sha256::new();
+        engine.input_bits(&[0x68], 5);
+        engine.from_midstate(Sha256::new().chain([0x61; 64]).midstate());
+        assert!(engine.is_block_aligned());
+        engine.input(b"abc");
+        assert_eq!(engine, Sha256::new().chain([0x61; 64]).chain(b"abc"));
+        assert_eq!(engine.finalise(), Sha256::new().chain([0x61; 64]).chain(b"abc").finalise());
+    }
+
+    #[test]
+    fn length_overflow() {
+        // Resume 63 bytes short of the 2^64 - 1 bit limit
+        let mut engine = Sha256::new();
+        engine.from_midstate(Midstate::new(crate::constants::SHA256_INITIAL_CONSTANTS, (u64::MAX/8) & !63));
+        assert_eq!(engine.try_input([0; 64]), Err(crate::Error::MessageTooLong));
+        assert_eq!(engine.try_input([0; 63]), Ok(()));
+        assert_eq!(engine.try_input([0]), Err(crate::Error::MessageTooLong));
+        assert!(::std::panic::catch_unwind(move || engine.input([0])).is_err());
+        assert_eq!(Sha256::new().try_from_midstate(Midstate::new([0; 8], !63)), Err(crate::Error::MessageTooLong));
+    }
+
+    #[test]
+    fn bytes_hashed() {
+        let mut engine = Sha512::new();
+        assert!(engine.is_block_aligned());
+        engine.input([0; 200]);
+        assert_eq!(engine.bytes_hashed(), 200);
+        assert_eq!(engine.pending().len(), 72);
+        assert!(!engine.is_block_aligned());
+        engine.input([0; 56]);
+        assert!(engine.is_block_aligned());
+        assert_eq!(engine.midstate().length(), engine.bytes_hashed());
+    }
+
+    #[test]
+    fn engine_equality() {
+        let message = [0x61; 100];
+        let mut a = Sha256::new();
+        a.input(message);
+        let mut b = Sha256::new();
+        b.input(&message[..3]);
+        b.input(&message[3..]);
+        assert_eq!(a, b);
+        assert_eq!(a.midstate(), b.midstate());
+
+        // Stale bytes left in the buffer by an earlier message are not compared
+        b.reset();
+        b.input([0x62; 10]);
+        b.reset();
+        assert_eq!(b, Sha256::new());
+        assert_ne!(a, b);
+        assert_ne!(Sha256::with_iv([0; 8]), Sha256::new());
+    }
+}
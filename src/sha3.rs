@@ -0,0 +1,110 @@
+// SHA3 Module
+//
+// SHA3-256 is not used within Bitcoin but is needed by protocols that nodes
+// speak, such as Tor v3 onion addresses. The Keccak sponge absorbs the message
+// in 136 byte blocks, so it fits the same buffered engine as the other hashes
+// with the 25 lane state as the midstate.
+use crate::core::{
+    message::MessageBlock,
+    HashEngine,
+    State,
+    Midstate,
+    functions::keccak::keccak_f1600,
+    digest_struct,
+    hash_struct,
+    iconst_funcs,
+    midstate_funcs,
+    input_func,
+    impl_default,
+    impl_write,
+    impl_extend,
+    impl_fmt_write,
+    impl_hash_batch,
+    impl_state_bytes
+};
+
+const SHA3_INITIAL_STATE: [u64; 25] = [0; 25];
+
+digest_struct!(Sha3_256Digest, 32);
+hash_struct!(Sha3_256, 136, u64, u64, 25);
+impl_default!(Sha3_256, SHA3_INITIAL_STATE, Self::BLOCKSIZE);
+impl_write!(Sha3_256);
+
+impl_extend!(Sha3_256);
+
+impl_fmt_write!(Sha3_256);
+impl_hash_batch!(Sha3_256);
+
+impl_state_bytes!(Sha3_256, u64, u64, 25);
+
+impl HashEngine for Sha3_256 {
+    type Digest = Sha3_256Digest;
+    type Midstate = Midstate<u64, 25>;
+    const BLOCKSIZE: usize = 136;
+    const OUTPUT_SIZE: usize = 32;
+
+    input_func!(u64);
+    iconst_funcs!(Self::BLOCKSIZE);
+    midstate_funcs!(u64);
+
+    fn finalise(&mut self) -> Self::Digest {
+        // Pad with the SHA3 domain bits and the final '1' bit of the sponge padding
+        let end_index = (self.length%Self::BLOCKSIZE as u64) as usize;
+        let mut fblock = MessageBlock([0u8; 136]);
+        fblock.0[..end_index].copy_from_slice(&self.buffer[..end_index]);
+        fblock.0[end_index] ^= 0x06;
+        fblock.0[135] ^= 0x80;
+        Self::process_block(&mut self.state, &fblock.0);
+
+        let mut result: [u8; 32] = [0; 32];
+        for (chunk, lane) in result.chunks_mut(8).zip(self.state.read().iter()) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+
+        result.into()
+    }
+}
+
+impl Sha3_256 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Absorb a block into the rate lanes and permute
+    fn process_block(state: &mut State<u64, 25>, block: &[u8; Self::BLOCKSIZE]) {
+        let mut lanes = state.read();
+        for (lane, chunk) in lanes.iter_mut().zip(block.chunks_exact(8)) {
+            *lane ^= u64::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]);
+        }
+        keccak_f1600(&mut lanes);
+        state.update(lanes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha3_256() {
+        let cases: Vec<(Vec<u8>, &str)> = vec![
+            (vec![], "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"),
+            (b"abc".to_vec(), "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"),
+            (vec![b'a'; 135], "8094bb53c44cfb1e67b7c30447f9a1c33696d2463ecc1d9c92538913392843c9"),
+            (vec![b'a'; 136], "3fc5559f14db8e453a0a3091edbd2bc25e11528d81c66fa570a4efdcc2695ee1"),
+            ((0..512).map(|i| i as u8).collect(), "d4728ea5e9f3819f2b4760151a8f802dbe9f941fd6fb59b3715892436555772a")
+        ];
+
+        for case in cases {
+            let mut hasher = Sha3_256::new();
+            hasher.input(&case.0);
+            assert_eq!(hasher.finalise().to_string(), case.1);
+        }
+    }
+
+    #[test]
+    fn hmac_sha3_256() {
+        let tag = crate::Hmac::<Sha3_256>::mac(b"key", b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(tag.to_string(), "8c6e0683409427f8931711b10ca92a506eb1fafa48fadd66d76126f47ac2c333");
+    }
+}
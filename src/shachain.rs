@@ -0,0 +1,118 @@
+// Shachain module
+//
+// BOLT3 per-commitment secrets. Each secret is derived from a seed by flipping the
+// bits of the 48-bit index from the highest down, hashing after each flip. Secrets
+// are revealed from index 2^48 - 1 downwards, and since a secret can derive every
+// secret sharing its leading index bits, the receiver only needs to keep 49 of them.
+
+use crate::{
+    Error,
+    HashEngine,
+    Sha256
+};
+
+/// Index of the first per-commitment secret
+pub const SHACHAIN_MAX_INDEX: u64 = (1 << 48) - 1;
+
+/// Derive a secret by flipping and hashing the lowest `bits` bits of the index, highest first
+fn derive(base: &[u8; 32], bits: u32, index: u64) -> [u8; 32] {
+    let mut secret = *base;
+    for bit in (0..bits).rev() {
+        if index >> bit & 1 == 1 {
+            secret[bit as usize / 8] ^= 1 << (bit % 8);
+            secret = Sha256::new().chain(secret).finalise().0;
+        }
+    }
+    secret
+}
+
+/// Derive the per-commitment secret at an index from the seed
+pub fn shachain_secret(seed: &[u8; 32], index: u64) -> [u8; 32] {
+    derive(seed, 48, index)
+}
+
+/// Compact storage of received per-commitment secrets
+#[derive(Clone, Debug)]
+pub struct ShachainStore {
+    known: [Option<(u64, [u8; 32])>; 49]
+}
+
+impl Default for ShachainStore {
+    fn default() -> Self {
+        Self {
+            known: [None; 49]
+        }
+    }
+}
+
+impl ShachainStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store the next received secret, checking that it derives the secrets it replaces
+    pub fn insert(&mut self, index: u64, secret: [u8; 32]) -> Result<(), Error> {
+        let position = index.trailing_zeros().min(48) as usize;
+        for (known_index, known_secret) in self.known[..position].iter().flatten() {
+            if derive(&secret, position as u32, *known_index) != *known_secret {
+                return Err(Error::InvalidSecret)
+            }
+        }
+
+        self.known[position] = Some((index, secret));
+        Ok(())
+    }
+
+    /// Derive a previously received secret, if it is covered by the stored secrets
+    pub fn get(&self, index: u64) -> Option<[u8; 32]> {
+        self.known.iter().enumerate().find_map(|(bit, known)| {
+            let (known_index, known_secret) = known.as_ref()?;
+            let mask = !((1u64 << bit) - 1);
+            if index & mask == known_index & mask {
+                Some(derive(known_secret, bit as u32, index))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn generate_from_seed() {
+        // https://github.com/lightning/bolts/blob/master/03-transactions.md#generation-tests
+        let cases: Vec<([u8; 32], u64, &str)> = vec![
+            ([0x00; 32], SHACHAIN_MAX_INDEX, "02a40c85b6f28da08dfdbe0926c53fab2de6d28c10301f8f7c4073d5e42e3148"),
+            ([0xff; 32], SHACHAIN_MAX_INDEX, "7cc854b54e3e0dcdb010d7a3fee464a9687be6e8db3be6854c475621e007a5dc"),
+            ([0xff; 32], 0xaaaaaaaaaaa, "56f4008fb007ca9acf0e15b054d5c9fd12ee06cea347914ddbaed70d1c13a528"),
+            ([0xff; 32], 0x555555555555, "9015daaeb06dba4ccc05b91b2f73bd54405f2be9f217fbacd3c5ac2e62327d31"),
+            ([0x01; 32], 1, "915c75942a26bb3a433a8ce2cb0427c29ec6c1775cfc78328b57f6ba7bfeaa9c")
+        ];
+
+        for case in cases {
+            assert_eq!(Sha256Digest(shachain_secret(&case.0, case.1)).to_string(), case.2);
+        }
+    }
+
+    #[test]
+    fn store() {
+        let seed = [0x2a; 32];
+        let mut store = ShachainStore::new();
+        for index in (SHACHAIN_MAX_INDEX - 20..=SHACHAIN_MAX_INDEX).rev() {
+            store.insert(index, shachain_secret(&seed, index)).unwrap();
+        }
+        for index in SHACHAIN_MAX_INDEX - 20..=SHACHAIN_MAX_INDEX {
+            assert_eq!(store.get(index), Some(shachain_secret(&seed, index)));
+        }
+        assert_eq!(store.get(SHACHAIN_MAX_INDEX - 21), None);
+        assert!(store.known.iter().filter(|known| known.is_some()).count() <= 49);
+
+        // A secret from another seed does not derive the stored ones
+        let index = SHACHAIN_MAX_INDEX - 21;
+        assert_eq!(store.insert(index, shachain_secret(&[0x2b; 32], index)), Err(Error::InvalidSecret));
+    }
+}
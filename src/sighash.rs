@@ -0,0 +1,171 @@
+// Sighash module
+//
+// BIP143 segwit v0 signature hashing. The hashes of all prevouts, sequences and
+// outputs are the same for every input of a transaction, so they are computed once
+// and the start of the preimage (version, hashPrevouts and hashSequence) is kept as
+// a partially filled engine that each SIGHASH_ALL input continues from.
+
+use crate::{
+    HashEngine,
+    Sha256
+};
+
+/// Sign all outputs
+pub const SIGHASH_ALL: u32 = 0x01;
+/// Sign no outputs
+pub const SIGHASH_NONE: u32 = 0x02;
+/// Sign only the output with the same index as the input
+pub const SIGHASH_SINGLE: u32 = 0x03;
+/// Sign only this input, combined with one of the above
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+/// Double SHA256 of the data input into an engine
+fn sha256d(mut engine: Sha256) -> [u8; 32] {
+    engine.finalise().sha256d().0
+}
+
+/// Input a serialized transaction output
+fn input_output(engine: &mut Sha256, value: u64, script_pubkey: &[u8]) {
+    engine.input_u64_le(value);
+    engine.input_slice_with_len(script_pubkey);
+}
+
+/// Per transaction BIP143 hashes, shared by the sighash of every input
+#[derive(Clone, Copy, Debug)]
+pub struct SighashCache {
+    version: u32,
+    lock_time: u32,
+    hash_prevouts: [u8; 32],
+    hash_sequence: [u8; 32],
+    hash_outputs: [u8; 32],
+    preimage: Sha256
+}
+
+impl SighashCache {
+    /// Hash the fields of a transaction.
+    /// 
+    /// Outpoints are serialized as the txid followed by the little endian output index,
+    /// and outputs are given as their value and scriptPubKey.
+    pub fn new<'a, P, S, O>(version: u32, prevouts: P, sequences: S, outputs: O, lock_time: u32) -> Self
+    where P: IntoIterator<Item=&'a [u8; 36]>, S: IntoIterator<Item=u32>, O: IntoIterator<Item=(u64, &'a [u8])> {
+        let mut engine = Sha256::new();
+        prevouts.into_iter().for_each(|prevout| engine.input(prevout));
+        let hash_prevouts = sha256d(engine);
+
+        let mut engine = Sha256::new();
+        sequences.into_iter().for_each(|sequence| engine.input(sequence.to_le_bytes()));
+        let hash_sequence = sha256d(engine);
+
+        let mut engine = Sha256::new();
+        outputs.into_iter().for_each(|(value, script_pubkey)| input_output(&mut engine, value, script_pubkey));
+        let hash_outputs = sha256d(engine);
+
+        let preimage = Sha256::new()
+            .chain(version.to_le_bytes())
+            .chain(hash_prevouts)
+            .chain(hash_sequence);
+
+        Self {
+            version,
+            lock_time,
+            hash_prevouts,
+            hash_sequence,
+            hash_outputs,
+            preimage
+        }
+    }
+
+    /// Double SHA256 of all the serialized outpoints
+    pub fn hash_prevouts(&self) -> [u8; 32] {
+        self.hash_prevouts
+    }
+
+    /// Double SHA256 of all the input sequence numbers
+    pub fn hash_sequence(&self) -> [u8; 32] {
+        self.hash_sequence
+    }
+
+    /// Double SHA256 of all the serialized outputs
+    pub fn hash_outputs(&self) -> [u8; 32] {
+        self.hash_outputs
+    }
+
+    /// Compute the BIP143 sighash of one input.
+    /// 
+    /// `script_code` is given without its length prefix. For SIGHASH_SINGLE, `single_output`
+    /// is the output with the same index as the input, or None if there is no such output.
+    pub fn segwit_v0_sighash(
+        &self,
+        prevout: &[u8; 36],
+        script_code: &[u8],
+        value: u64,
+        sequence: u32,
+        sighash_type: u32,
+        single_output: Option<(u64, &[u8])>
+    ) -> [u8; 32] {
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let base_type = sighash_type & 0x1f;
+
+        let mut engine = if !anyone_can_pay && base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+            self.preimage
+        } else {
+            let hash_prevouts = if anyone_can_pay { [0; 32] } else { self.hash_prevouts };
+            Sha256::new()
+                .chain(self.version.to_le_bytes())
+                .chain(hash_prevouts)
+                .chain([0u8; 32])
+        };
+
+        engine.input(prevout);
+        engine.input_slice_with_len(script_code);
+        engine.input_u64_le(value);
+        engine.input_u32_le(sequence);
+
+        match (base_type, single_output) {
+            (SIGHASH_SINGLE, Some((value, script_pubkey))) => {
+                let mut output = Sha256::new();
+                input_output(&mut output, value, script_pubkey);
+                engine.input(sha256d(output));
+            },
+            (SIGHASH_SINGLE, None) | (SIGHASH_NONE, _) => engine.input([0u8; 32]),
+            _ => engine.input(self.hash_outputs)
+        }
+
+        engine.input(self.lock_time.to_le_bytes());
+        engine.input(sighash_type.to_le_bytes());
+        sha256d(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn bip143_native_p2wpkh() {
+        // https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#native-p2wpkh
+        let prevouts = [
+            hex::<36>("fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000"),
+            hex::<36>("ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01000000")
+        ];
+        let scripts = [
+            hex::<25>("76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac"),
+            hex::<25>("76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac")
+        ];
+        let outputs = [(112340000, &scripts[0][..]), (223450000, &scripts[1][..])];
+
+        let cache = SighashCache::new(1, &prevouts, vec![0xffffffee, 0xffffffff], outputs.iter().copied(), 0x11);
+        assert_eq!(Sha256Digest(cache.hash_prevouts()).to_string(), "96b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37");
+        assert_eq!(Sha256Digest(cache.hash_sequence()).to_string(), "52b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3b");
+        assert_eq!(Sha256Digest(cache.hash_outputs()).to_string(), "863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e5");
+
+        let script_code = hex::<25>("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac");
+        let sighash = cache.segwit_v0_sighash(&prevouts[1], &script_code, 600000000, 0xffffffff, SIGHASH_ALL, None);
+        assert_eq!(Sha256Digest(sighash).to_string(), "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670");
+
+        let sighash = cache.segwit_v0_sighash(&prevouts[1], &script_code, 600000000, 0xffffffff, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY, Some(outputs[1]));
+        assert_eq!(Sha256Digest(sighash).to_string(), "79ff9ff708f79ce8f7a4f90d62028533a99d7340b7fb3d819dfd9a599a78e39c");
+    }
+}
@@ -0,0 +1,41 @@
+// Signed message module
+//
+// Legacy Bitcoin message signing signs the sha256d of the message behind a magic
+// prefix, with both the prefix and message preceded by compact size lengths.
+
+use crate::{
+    HashEngine,
+    Sha256
+};
+
+/// Magic prefix for signed messages, including its own length byte
+pub const SIGNED_MESSAGE_PREFIX: &[u8] = b"\x18Bitcoin Signed Message:\n";
+
+/// Compute the hash that is signed for a Bitcoin signed message
+pub fn signed_message_hash<M: AsRef<[u8]>>(msg: M) -> [u8; 32] {
+    let msg = msg.as_ref();
+    let mut engine = Sha256::new();
+    engine.input(SIGNED_MESSAGE_PREFIX);
+    engine.input_slice_with_len(msg);
+
+    engine.finalise().sha256d().0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256Digest;
+
+    #[test]
+    fn signed_message() {
+        let cases: Vec<(Vec<u8>, &str)> = vec![
+            (vec![], "80e795d4a4caadd7047af389d9f7f220562feb6196032e2131e10563352c4bcc"),
+            (b"Hello World".to_vec(), "a7af0baad5ae99b97fc69b3a0d1abcf3ef17f131cc4776e1bc11933ec8550f49"),
+            (vec![b'a'; 300], "3ec158a43b80359df647352dac1d37dbf26a94e5f06e5790760290c75cd11dc0")
+        ];
+
+        for case in cases {
+            assert_eq!(Sha256Digest(signed_message_hash(&case.0)).to_string(), case.1);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// SipHash module
+//
+// SipHash-2-4, a keyed 64-bit hash for short inputs. Bitcoin uses it for compact
+// block short IDs (BIP152) and compact block filters (BIP158). It is not a
+// HashEngine since it cannot be constructed without a key and has no midstate.
+
+/// A SipHash-2-4 hasher
+#[derive(Clone, Copy, Debug)]
+pub struct SipHash24 {
+    state: [u64; 4],
+    buffer: [u8; 8],
+    length: usize                    // The length here is in bytes.
+}
+
+impl SipHash24 {
+    /// Create a hasher from the two halves of the key
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        Self {
+            state: [
+                k0 ^ 0x736f6d6570736575,
+                k1 ^ 0x646f72616e646f6d,
+                k0 ^ 0x6c7967656e657261,
+                k1 ^ 0x7465646279746573
+            ],
+            buffer: [0; 8],
+            length: 0
+        }
+    }
+
+    /// Create a hasher from a 16 byte key
+    pub fn new_with_key(key: &[u8; 16]) -> Self {
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&key[..8]);
+        k1.copy_from_slice(&key[8..]);
+        Self::new_with_keys(u64::from_le_bytes(k0), u64::from_le_bytes(k1))
+    }
+
+    /// Hash a message in one call
+    pub fn hash<I: AsRef<[u8]>>(k0: u64, k1: u64, data: I) -> u64 {
+        let mut hasher = Self::new_with_keys(k0, k1);
+        hasher.input(data);
+        hasher.finalise()
+    }
+
+    /// Input data into the hasher
+    pub fn input<I: AsRef<[u8]>>(&mut self, data: I) {
+        for byte in data.as_ref() {
+            self.buffer[self.length % 8] = *byte;
+            self.length += 1;
+            if self.length % 8 == 0 {
+                self.process_word(u64::from_le_bytes(self.buffer));
+            }
+        }
+    }
+
+    /// Compute the 64-bit hash of the data input so far
+    pub fn finalise(&self) -> u64 {
+        let mut hasher = *self;
+        let mut last = [0u8; 8];
+        let end = self.length % 8;
+        last[..end].copy_from_slice(&self.buffer[..end]);
+        last[7] = self.length as u8;
+        hasher.process_word(u64::from_le_bytes(last));
+
+        hasher.state[2] ^= 0xff;
+        for _ in 0..4 {
+            hasher.round();
+        }
+        hasher.state.iter().fold(0, |acc, v| acc ^ v)
+    }
+
+    fn process_word(&mut self, word: u64) {
+        self.state[3] ^= word;
+        self.round();
+        self.round();
+        self.state[0] ^= word;
+    }
+
+    fn round(&mut self) {
+        let [v0, v1, v2, v3] = &mut self.state;
+        *v0 = v0.wrapping_add(*v1); *v1 = v1.rotate_left(13); *v1 ^= *v0; *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3); *v3 = v3.rotate_left(16); *v3 ^= *v2;
+        *v0 = v0.wrapping_add(*v3); *v3 = v3.rotate_left(21); *v3 ^= *v0;
+        *v2 = v2.wrapping_add(*v1); *v1 = v1.rotate_left(17); *v1 ^= *v2; *v2 = v2.rotate_left(32);
+    }
+}
+
+impl ::core::hash::Hasher for SipHash24 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.input(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.finalise()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn siphash24() {
+        // Vectors from the SipHash paper's reference implementation
+        let key: Vec<u8> = (0u8..16).collect();
+        let mut k = [0u8; 16];
+        k.copy_from_slice(&key);
+        let message: Vec<u8> = (0u8..64).collect();
+
+        assert_eq!(SipHash24::new_with_key(&k).finalise(), 0x726fdb47dd0e0e31);
+
+        let mut hasher = SipHash24::new_with_key(&k);
+        hasher.input(&message[..15]);
+        assert_eq!(hasher.finalise(), 0xa129ca6149be45e5);
+
+        let mut hasher = SipHash24::new_with_key(&k);
+        hasher.input(&message[..20]);
+        hasher.input(&message[20..63]);
+        assert_eq!(hasher.finalise(), 0x958a324ceb064572);
+    }
+}
@@ -0,0 +1,143 @@
+// SLIP-0010 module
+//
+// SLIP-0010 generalises BIP32 to other curves. Each curve has its own seed key, and
+// for curves where not every 256-bit value is a valid key, invalid outputs are
+// hashed again until a valid one is found. Ed25519 accepts every value and only
+// supports hardened derivation.
+
+use crate::{
+    hmac_sha512,
+    HashEngine,
+    HmacSha512,
+    KeyBasedHashEngine
+};
+
+/// Curves supported by SLIP-0010 derivation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slip10Curve {
+    Secp256k1,
+    Nist256p1,
+    Ed25519
+}
+
+impl Slip10Curve {
+    /// HMAC key used to derive the master key from a seed
+    pub fn seed_key(&self) -> &'static [u8] {
+        match self {
+            Slip10Curve::Secp256k1 => b"Bitcoin seed",
+            Slip10Curve::Nist256p1 => b"Nist256p1 seed",
+            Slip10Curve::Ed25519 => b"ed25519 seed"
+        }
+    }
+
+    /// Big endian group order, or None if every 256-bit value is a valid key
+    fn order(&self) -> Option<[u8; 32]> {
+        match self {
+            Slip10Curve::Secp256k1 => Some([
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+                0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41
+            ]),
+            Slip10Curve::Nist256p1 => Some([
+                0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51
+            ]),
+            Slip10Curve::Ed25519 => None
+        }
+    }
+
+    /// Check whether the left half of an output is below the curve order
+    fn in_range(&self, left: &[u8; 32]) -> bool {
+        self.order().is_none_or(|order| left < &order)
+    }
+}
+
+/// Derive the master secret key (left) and chain code (right) from a seed,
+/// rehashing the output while the key is zero or not below the curve order
+pub fn slip10_master_key_hash<S: AsRef<[u8]>>(curve: Slip10Curve, seed: S) -> ([u8; 32], [u8; 32]) {
+    let (mut left, mut right) = hmac_sha512(curve.seed_key(), seed).split();
+    while curve != Slip10Curve::Ed25519 && (left == [0; 32] || !curve.in_range(&left)) {
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(&left);
+        data[32..].copy_from_slice(&right);
+        let next = hmac_sha512(curve.seed_key(), data).split();
+        left = next.0;
+        right = next.1;
+    }
+    (left, right)
+}
+
+/// Child key derivation hash, returning the key tweak (left) and child chain code (right).
+/// 
+/// `parent_data` is the parent's compressed public key for normal children, or a zero byte
+/// followed by the parent's secret key for hardened children. Ed25519 only allows hardened
+/// children. Tweaks that are not below the curve order are rehashed here, while the caller
+/// must still call [`slip10_retry_hash`] if adding the tweak gives a zero key or the point at infinity.
+pub fn slip10_ckd_hash(curve: Slip10Curve, chain_code: &[u8; 32], parent_data: &[u8; 33], index: u32) -> ([u8; 32], [u8; 32]) {
+    let mut hmac = HmacSha512::new_with_key(chain_code);
+    hmac.input(parent_data);
+    hmac.input(index.to_be_bytes());
+
+    let mut output = hmac.finalise().split();
+    while !curve.in_range(&output.0) {
+        output = slip10_retry_hash(chain_code, &output.1, index);
+    }
+    output
+}
+
+/// Hash the right half of an invalid child derivation output again
+pub fn slip10_retry_hash(chain_code: &[u8; 32], right: &[u8; 32], index: u32) -> ([u8; 32], [u8; 32]) {
+    let mut hmac = HmacSha512::new_with_key(chain_code);
+    hmac.input([1u8]);
+    hmac.input(right);
+    hmac.input(index.to_be_bytes());
+    hmac.finalise().split()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+    use crate::HARDENED;
+
+    #[test]
+    fn master_keys() {
+        // https://github.com/satoshilabs/slips/blob/master/slip-0010.md#test-vector-1-for-nist256p1
+        let seed: Vec<u8> = (0u8..16).collect();
+        let (key, chain_code) = slip10_master_key_hash(Slip10Curve::Nist256p1, &seed);
+        assert_eq!(key, hex("612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2"));
+        assert_eq!(chain_code, hex("beeb672fe4621673f722f38529c07392fecaa61015c80c34f29ce8b41b3cb6ea"));
+
+        let (key, chain_code) = slip10_master_key_hash(Slip10Curve::Ed25519, &seed);
+        assert_eq!(key, hex("2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"));
+        assert_eq!(chain_code, hex("90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"));
+
+        // m/0H
+        let mut data = [0u8; 33];
+        data[1..].copy_from_slice(&key);
+        let (key, chain_code) = slip10_ckd_hash(Slip10Curve::Ed25519, &chain_code, &data, HARDENED);
+        assert_eq!(key, hex("68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"));
+        assert_eq!(chain_code, hex("8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69"));
+
+        let (key, chain_code) = slip10_master_key_hash(Slip10Curve::Secp256k1, &seed);
+        assert_eq!((key, chain_code), crate::master_key_hash(&seed));
+
+        // Seed retry for nist256p1
+        let seed = hex::<32>("a7305bc8df8d0951f0cb224c0e95d7707cbdf2c6ce7e8d481fec69c7ff5e9446");
+        let (key, chain_code) = slip10_master_key_hash(Slip10Curve::Nist256p1, seed);
+        assert_eq!(key, hex("3b8c18469a4634517d6d0b65448f8e6c62091b45540a1743c5846be55d47d88f"));
+        assert_eq!(chain_code, hex("7762f9729fed06121fd13f326884c82f59aa95c57ac492ce8c9654e60efd130c"));
+    }
+
+    #[test]
+    fn derivation_retry() {
+        // Derivation retry for nist256p1, m/28578H/33941 where the first tweak is above the order
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&hex::<32>("e94c8ebe30c2250a14713212f6449b20f3329105ea15b652ca5bdfc68f6c65c2"));
+        let mut parent = [0u8; 33];
+        parent.copy_from_slice(&hex::<33>("02519b5554a4872e8c9c1c847115363051ec43e93400e030ba3c36b52a3e70a5b7"));
+
+        let (tweak, chain_code) = slip10_ckd_hash(Slip10Curve::Nist256p1, &chain_code, &parent, 33941);
+        assert_eq!(tweak, hex("023079dc65ad4c8aa846c316ec8ea424ac468c561022b817895125d492f8b8d1"));
+        assert_eq!(chain_code, hex("9e87fe95031f14736774cd82f25fd885065cb7c358c1edf813c72af535e83071"));
+    }
+}
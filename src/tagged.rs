@@ -0,0 +1,263 @@
+// Tagged hash module
+//
+// BIP340 tagged hashes prefix the message with the SHA256 of a tag, twice:
+//      SHA256(SHA256(tag) || SHA256(tag) || m)
+// The prefix is exactly one block, so every tagged hash starts from a midstate
+// that only depends on the tag. Each tag is its own type, so hashes of different
+// protocols cannot be confused, and its midstate is computed once and cached.
+
+use ::core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering}
+};
+use crate::{
+    core::Midstate,
+    error::Error,
+    HashEngine,
+    Sha256,
+    Sha256Digest
+};
+
+/// A tag of a tagged hash
+pub trait Tag {
+    /// The tag bytes, usually an ASCII protocol name
+    const TAG: &'static [u8];
+
+    /// The SHA256 midstate after the two tag hashes, normally cached by hash_tag!
+    fn midstate() -> Midstate<u32, 8> {
+        tag_midstate(Self::TAG)
+    }
+}
+
+/// Compute the midstate of a tagged hash for a tag
+pub fn tag_midstate(tag: &[u8]) -> Midstate<u32, 8> {
+    let tag_hash = Sha256::new().chain(tag).finalise();
+    Sha256::new().chain(tag_hash).chain(tag_hash).midstate()
+}
+
+/// Lazily computed midstate of a tag, shared between threads without locking.
+///
+/// Threads that race to fill the cache all compute and store the same registers.
+#[doc(hidden)]
+pub struct TagCache {
+    ready: AtomicBool,
+    registers: [AtomicU32; 8]
+}
+
+impl TagCache {
+    #[allow(clippy::new_without_default, clippy::declare_interior_mutable_const)]
+    pub const fn new() -> Self {
+        const ZERO: AtomicU32 = AtomicU32::new(0);
+        Self {
+            ready: AtomicBool::new(false),
+            registers: [ZERO; 8]
+        }
+    }
+
+    pub fn get(&self, tag: &[u8]) -> Midstate<u32, 8> {
+        if !self.ready.load(Ordering::Acquire) {
+            let midstate = tag_midstate(tag);
+            for (cached, register) in self.registers.iter().zip(midstate.registers().iter()) {
+                cached.store(*register, Ordering::Relaxed);
+            }
+            self.ready.store(true, Ordering::Release);
+            return midstate;
+        }
+
+        let mut registers = [0u32; 8];
+        for (register, cached) in registers.iter_mut().zip(self.registers.iter()) {
+            *register = cached.load(Ordering::Relaxed);
+        }
+        Midstate::new(registers, 64)
+    }
+}
+
+/// Declare a tag type whose midstate is computed on first use and then cached.
+///
+/// ```
+/// use btc_hashes::{hash_tag, HashEngine, TaggedHash};
+///
+/// hash_tag! {
+///     /// Tag of taproot leaf hashes
+///     pub struct TapLeaf = "TapLeaf";
+/// }
+///
+/// let digest = TaggedHash::<TapLeaf>::new().chain([0xc0, 0x01, 0x51]).finalise();
+/// assert_eq!(digest.to_string(), "a85b2107f791b26a84e7586c28cec7cb61202ed3d01944d832500f363782d675");
+/// ```
+#[macro_export]
+macro_rules! hash_tag {
+    ($(#[$attr: meta])* $vis: vis struct $name: ident = $tag: expr;) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        $vis struct $name;
+
+        impl $crate::Tag for $name {
+            const TAG: &'static [u8] = $tag.as_bytes();
+
+            fn midstate() -> $crate::Midstate<u32, 8> {
+                static CACHE: $crate::TagCache = $crate::TagCache::new();
+                CACHE.get(<Self as $crate::Tag>::TAG)
+            }
+        }
+    };
+}
+
+hash_tag! {
+    /// Tag of BIP340 signature challenges
+    pub struct Bip340Challenge = "BIP0340/challenge";
+}
+
+hash_tag! {
+    /// Tag of taproot leaf hashes
+    pub struct TapLeaf = "TapLeaf";
+}
+
+hash_tag! {
+    /// Tag of taproot branch hashes
+    pub struct TapBranch = "TapBranch";
+}
+
+hash_tag! {
+    /// Tag of taproot output key tweaks
+    pub struct TapTweak = "TapTweak";
+}
+
+hash_tag! {
+    /// Tag of taproot signature hashes
+    pub struct TapSighash = "TapSighash";
+}
+
+/// A SHA256 engine that starts from the midstate of a tag
+pub struct TaggedHash<T: Tag> {
+    engine: Sha256,
+    tag: PhantomData<T>
+}
+
+impl<T: Tag> Clone for TaggedHash<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Tag> Copy for TaggedHash<T> { }
+
+impl<T: Tag> PartialEq for TaggedHash<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.engine == other.engine
+    }
+}
+
+impl<T: Tag> Eq for TaggedHash<T> { }
+
+impl<T: Tag> ::core::fmt::Debug for TaggedHash<T> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        f.debug_struct("TaggedHash").field("engine", &self.engine).finish()
+    }
+}
+
+impl<T: Tag> Default for TaggedHash<T> {
+    fn default() -> Self {
+        let mut engine = Sha256::new();
+        engine.from_midstate(T::midstate());
+        Self {
+            engine,
+            tag: PhantomData
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: Tag> zeroize::Zeroize for TaggedHash<T> {
+    fn zeroize(&mut self) {
+        self.engine.zeroize();
+    }
+}
+
+impl<T: Tag> HashEngine for TaggedHash<T> {
+    type Digest = Sha256Digest;
+    type Midstate = Midstate<u32, 8>;
+    const BLOCKSIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 32;
+
+    fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.engine.input(data);
+    }
+
+    fn try_input<I>(&mut self, data: I) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        self.engine.try_input(data)
+    }
+
+    fn reset(&mut self) {
+        self.engine.reset();
+        self.engine.from_midstate(T::midstate());
+    }
+
+    /// The midstate length includes the 64 bytes of tag prefix
+    fn midstate(&self) -> Self::Midstate {
+        self.engine.midstate()
+    }
+
+    fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), Error> {
+        self.engine.try_from_midstate(midstate)
+    }
+
+    fn pending(&self) -> &[u8] {
+        self.engine.pending()
+    }
+
+    fn bytes_hashed(&self) -> u64 {
+        self.engine.bytes_hashed()
+    }
+
+    fn finalise(&mut self) -> Self::Digest {
+        self.engine.finalise()
+    }
+
+    fn wipe(&mut self) {
+        self.engine.wipe();
+    }
+}
+
+impl<T: Tag> ::core::fmt::Write for TaggedHash<T> {
+    fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+        self.input(s);
+        Ok(())
+    }
+}
+
+impl<T: Tag> TaggedHash<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    hash_tag! {
+        struct TestTag = "BIP0340/challenge";
+    }
+
+    #[test]
+    fn tagged_hash() {
+        let digest = TaggedHash::<TapLeaf>::new().finalise();
+        assert_eq!(digest.to_string(), "5212c288a377d1f8164962a5a13429f9ba6a7b84e59776a52c6637df2106facb");
+
+        let mut engine = TaggedHash::<TapLeaf>::new();
+        engine.input([0xc0, 0x01, 0x51]);
+        assert_eq!(engine.finalise_reset().to_string(), "a85b2107f791b26a84e7586c28cec7cb61202ed3d01944d832500f363782d675");
+        assert_eq!(engine.finalise().to_string(), "5212c288a377d1f8164962a5a13429f9ba6a7b84e59776a52c6637df2106facb");
+
+        // Cached and uncached midstates agree
+        let msg = b"abc".repeat(50);
+        let cached = TaggedHash::<TestTag>::new().chain(&msg).finalise();
+        let uncached = TaggedHash::<Bip340Challenge>::new().chain(&msg).finalise();
+        assert_eq!(cached, uncached);
+        assert_eq!(cached.to_string(), "6568fa1c0b92091d0a20ac9a3dbece1a2c6354855c5e03cc9df864948bdc36d0");
+        assert_eq!(TestTag::midstate().registers(), tag_midstate(b"BIP0340/challenge").registers());
+    }
+}
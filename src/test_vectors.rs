@@ -0,0 +1,339 @@
+// Test vectors module
+//
+// Runs NIST CAVP response files against the hash engines, so the engines can be
+// checked against the official SHAByteTestVectors suites (ShortMsg and LongMsg)
+// instead of only a handful of hand picked cases. A response file lists vectors as
+//      Len = <message length in bits>
+//      Msg = <message hex>
+//      MD = <digest hex>
+// separated by blank lines, with comments and [L = ...] headers in between.
+//
+// Monte Carlo response files start from a seed digest and list the checkpoints of
+// the SHAVS Monte Carlo procedure as COUNT and MD pairs.
+//
+// Google's Wycheproof HMAC files are JSON, with test groups of a key and tag size
+// holding valid and invalid (modified or truncated) tags.
+
+use alloc::vec::Vec;
+use ::core::{
+    convert::TryFrom,
+    fmt
+};
+use crate::{
+    core::decode_hex,
+    HashEngine,
+    Hmac,
+    KeyBasedHashEngine
+};
+use serde_json::Value;
+
+/// Errors from parsing or running a test vector file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorError {
+    /// The line could not be parsed
+    Parse { line: usize },
+    /// The message length is not a whole number of bytes
+    BitLength { line: usize },
+    /// The engine's digest does not match the vector ending on this line
+    Mismatch { line: usize },
+    /// The JSON file is malformed or missing a field
+    Json,
+    /// The verification result differs from the expected result of this test case
+    TestCase { id: u64 }
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VectorError::Parse { line } => write!(f, "malformed test vector on line {}", line),
+            VectorError::BitLength { line } => write!(f, "message length on line {} is not a whole number of bytes", line),
+            VectorError::Mismatch { line } => write!(f, "digest mismatch for the test vector ending on line {}", line),
+            VectorError::Json => write!(f, "malformed test vector JSON"),
+            VectorError::TestCase { id } => write!(f, "test case {} failed", id)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VectorError { }
+
+/// A message and its expected digest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgVector {
+    pub msg: Vec<u8>,
+    pub md: Vec<u8>,
+    /// Line of the digest in the response file
+    pub line: usize
+}
+
+/// Decode a hex string of any even length
+pub(crate) fn hex_bytes(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = alloc::vec![0; s.len()/2];
+    decode_hex(s, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Key and value of a `key = value` line, or None for comments, headers and blank lines
+pub(crate) fn key_value(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+        return None;
+    }
+
+    let (key, value) = line.split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// Parse the vectors of a byte oriented CAVP message response file
+pub fn parse_rsp(rsp: &str) -> Result<Vec<MsgVector>, VectorError> {
+    let mut vectors = Vec::new();
+    let mut len: Option<usize> = None;
+    let mut msg: Option<Vec<u8>> = None;
+
+    for (i, text) in rsp.lines().enumerate() {
+        let line = i + 1;
+        let (key, value) = match key_value(text) {
+            Some(kv) => kv,
+            None if text.trim().is_empty() || text.trim().starts_with(['#', '[']) => continue,
+            None => return Err(VectorError::Parse { line })
+        };
+
+        match key {
+            "Len" => {
+                let bits: usize = value.parse().map_err(|_| VectorError::Parse { line })?;
+                if bits % 8 != 0 {
+                    return Err(VectorError::BitLength { line });
+                }
+                len = Some(bits/8);
+            },
+            "Msg" => msg = Some(hex_bytes(value).ok_or(VectorError::Parse { line })?),
+            "MD" => {
+                let md = hex_bytes(value).ok_or(VectorError::Parse { line })?;
+                let (len, mut msg) = len.take().zip(msg.take()).ok_or(VectorError::Parse { line })?;
+                // Empty messages are written as a single zero byte
+                if msg.len() < len {
+                    return Err(VectorError::Parse { line });
+                }
+                msg.truncate(len);
+                vectors.push(MsgVector { msg, md, line });
+            },
+            _ => return Err(VectorError::Parse { line })
+        }
+    }
+
+    Ok(vectors)
+}
+
+/// Run every vector of a byte oriented CAVP message response file against the engine,
+/// returning how many vectors passed.
+pub fn run_rsp<T: HashEngine>(rsp: &str) -> Result<usize, VectorError> {
+    let vectors = parse_rsp(rsp)?;
+    for vector in &vectors {
+        let digest = T::default().chain(&vector.msg).finalise();
+        if digest.as_ref() != vector.md.as_slice() {
+            return Err(VectorError::Mismatch { line: vector.line });
+        }
+    }
+
+    Ok(vectors.len())
+}
+
+/// Checkpoints of the SHAVS Monte Carlo test for SHA1 and SHA2, see monte_carlo()
+#[derive(Clone, Copy, Debug)]
+pub struct MonteCarlo<T: HashEngine> {
+    seed: T::Digest,
+    remaining: usize
+}
+
+/// Run the SHAVS Monte Carlo test from a seed, yielding its 100 checkpoints.
+/// 
+/// Each checkpoint hashes the concatenation of the previous three digests 1000 times,
+/// starting with the seed (or the previous checkpoint) as all three.
+pub fn monte_carlo<T: HashEngine>(seed: T::Digest) -> MonteCarlo<T> {
+    MonteCarlo {
+        seed,
+        remaining: 100
+    }
+}
+
+impl<T: HashEngine> Iterator for MonteCarlo<T> {
+    type Item = T::Digest;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let mut md = [self.seed; 3];
+        for _ in 0..1000 {
+            let next = T::default().chain(md[0]).chain(md[1]).chain(md[2]).finalise();
+            md = [md[1], md[2], next];
+        }
+        self.seed = md[2];
+        self.remaining -= 1;
+        Some(self.seed)
+    }
+}
+
+/// Run a CAVP Monte Carlo response file (such as SHA256Monte.rsp) against the engine,
+/// returning how many checkpoints passed.
+pub fn run_mct_rsp<T: HashEngine>(rsp: &str) -> Result<usize, VectorError> {
+    let mut checkpoints: Option<MonteCarlo<T>> = None;
+    let mut passed = 0;
+
+    for (i, text) in rsp.lines().enumerate() {
+        let line = i + 1;
+        let (key, value) = match key_value(text) {
+            Some(kv) => kv,
+            None if text.trim().is_empty() || text.trim().starts_with(['#', '[']) => continue,
+            None => return Err(VectorError::Parse { line })
+        };
+        let digest = || hex_bytes(value)
+            .and_then(|bytes| T::Digest::try_from(bytes).ok())
+            .ok_or(VectorError::Parse { line });
+
+        match key {
+            "Seed" => checkpoints = Some(monte_carlo(digest()?)),
+            "COUNT" => { },
+            "MD" => {
+                let expected = digest()?;
+                let md = checkpoints.as_mut().and_then(|c| c.next()).ok_or(VectorError::Parse { line })?;
+                if md.as_ref() != expected.as_ref() {
+                    return Err(VectorError::Mismatch { line });
+                }
+                passed += 1;
+            },
+            _ => return Err(VectorError::Parse { line })
+        }
+    }
+
+    Ok(passed)
+}
+
+/// Run a Wycheproof HMAC file (such as hmac_sha256_test.json) against HMAC with the engine,
+/// returning how many test cases passed.
+/// 
+/// Every tag is checked with Hmac::verify(), or Hmac::verify_truncated() in groups whose tag size
+/// is shorter than the digest. Cases with an "acceptable" result are skipped.
+pub fn run_wycheproof_hmac<T: HashEngine+Copy>(json: &str) -> Result<usize, VectorError> {
+    let file: Value = serde_json::from_str(json).map_err(|_| VectorError::Json)?;
+    let groups = file["testGroups"].as_array().ok_or(VectorError::Json)?;
+    let hex = |v: &Value| v.as_str().and_then(hex_bytes).ok_or(VectorError::Json);
+
+    let mut passed = 0;
+    for group in groups {
+        let tag_size = group["tagSize"].as_u64().ok_or(VectorError::Json)? as usize/8;
+        for test in group["tests"].as_array().ok_or(VectorError::Json)? {
+            let id = test["tcId"].as_u64().ok_or(VectorError::Json)?;
+            let (key, msg, tag) = (hex(&test["key"])?, hex(&test["msg"])?, hex(&test["tag"])?);
+            let expected = match test["result"].as_str() {
+                Some("valid") => true,
+                Some("invalid") => false,
+                Some("acceptable") => continue,
+                _ => return Err(VectorError::Json)
+            };
+
+            let mut mac = Hmac::<T>::new_with_key(&key);
+            mac.input(&msg);
+            let verified = tag.len() == tag_size && match tag_size == T::OUTPUT_SIZE {
+                true => mac.verify(&tag),
+                false => mac.verify_truncated(&tag)
+            };
+            if verified != expected {
+                return Err(VectorError::TestCase { id });
+            }
+            passed += 1;
+        }
+    }
+
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    // Start of SHA256ShortMsg.rsp
+    const SHORT_MSG: &str = "\
+#  CAVS 11.0
+#  \"SHA-256 ShortMsg\" information
+#  SHA-256 tests are configured for BYTE oriented implementations
+
+[L = 32]
+
+Len = 0
+Msg = 00
+MD = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+
+Len = 8
+Msg = d3
+MD = 28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1
+
+Len = 16
+Msg = 11af
+MD = 5ca7133fa735326081558ac312c620eeca9970d1e70a4b95533d956f072d1f98
+";
+
+    #[test]
+    fn cavp_rsp() {
+        assert_eq!(run_rsp::<Sha256>(SHORT_MSG), Ok(3));
+        assert_eq!(parse_rsp(SHORT_MSG).unwrap()[0].msg, Vec::<u8>::new());
+
+        let tampered = SHORT_MSG.replace("Msg = d3", "Msg = d4");
+        assert_eq!(run_rsp::<Sha256>(&tampered), Err(VectorError::Mismatch { line: 13 }));
+        assert_eq!(run_rsp::<Sha256>("Len = 5\nMsg = 68\n"), Err(VectorError::BitLength { line: 1 }));
+    }
+
+    // RFC 4231 test case 2 and an empty message laid out as a Wycheproof file,
+    // with a flipped bit, a truncated tag and a full tag in a truncated group
+    const HMAC_JSON: &str = r#"{
+        "algorithm": "HMACSHA256",
+        "testGroups": [
+            {
+                "keySize": 32, "tagSize": 256, "type": "MacTest",
+                "tests": [
+                    {"tcId": 1, "key": "4a656665", "msg": "7768617420646f2079612077616e7420666f72206e6f7468696e673f", "tag": "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", "result": "valid", "flags": []},
+                    {"tcId": 2, "key": "4a656665", "msg": "7768617420646f2079612077616e7420666f72206e6f7468696e673f", "tag": "5adcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", "result": "invalid", "flags": ["ModifiedTag"]},
+                    {"tcId": 3, "key": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f", "msg": "", "tag": "d38b42096d80f45f826b44a9d5607de72496a415d3f4a1a8c88e3bb9da8dc1cb", "result": "valid", "flags": []}
+                ]
+            },
+            {
+                "keySize": 32, "tagSize": 128, "type": "MacTest",
+                "tests": [
+                    {"tcId": 4, "key": "4a656665", "msg": "7768617420646f2079612077616e7420666f72206e6f7468696e673f", "tag": "5bdcc146bf60754e6a042426089575c7", "result": "valid", "flags": []},
+                    {"tcId": 5, "key": "4a656665", "msg": "7768617420646f2079612077616e7420666f72206e6f7468696e673f", "tag": "5bdcc146bf60754e6a042426089575c6", "result": "invalid", "flags": ["ModifiedTag"]},
+                    {"tcId": 6, "key": "4a656665", "msg": "7768617420646f2079612077616e7420666f72206e6f7468696e673f", "tag": "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", "result": "invalid", "flags": []}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn wycheproof_hmac() {
+        assert_eq!(run_wycheproof_hmac::<Sha256>(HMAC_JSON), Ok(6));
+        assert_eq!(run_wycheproof_hmac::<Sha256>(&HMAC_JSON.replacen("\"invalid\"", "\"valid\"", 1)), Err(VectorError::TestCase { id: 2 }));
+        assert_eq!(run_wycheproof_hmac::<Sha256>("{}"), Err(VectorError::Json));
+    }
+
+    #[test]
+    fn monte_carlo_test() {
+        // Seed and checkpoints of SHA256Monte.rsp
+        let rsp = "\
+[L = 32]
+
+Seed = 6d1e72ad03ddeb5de891e572e2396f8da015d899ef0e79503152d6010a3fe691
+
+COUNT = 0
+MD = e93c330ae5447738c8aa85d71a6c80f2a58381d05872d26bdd39f1fcd4f2b788
+
+COUNT = 1
+MD = 2e78f8c8772ea7c9331d41ed3f9cdf27d8f514a99342ee766ee3b8b0d0b121c0
+";
+        assert_eq!(run_mct_rsp::<Sha256>(rsp), Ok(2));
+
+        let seed = crate::Sha256Digest::from_hex("6d1e72ad03ddeb5de891e572e2396f8da015d899ef0e79503152d6010a3fe691").unwrap();
+        let last = monte_carlo::<Sha256>(seed).last().unwrap();
+        assert_eq!(last.to_string(), "6a912ba4188391a78e6f13d88ed2d14e13afce9db6f7dcbf4a48c24f3db02778");
+    }
+}
@@ -0,0 +1,84 @@
+// Timestamp module
+//
+// OpenTimestamps proofs are paths of commitment operations that take a message to
+// a value attested to elsewhere, such as a Bitcoin block's merkle root. Evaluating
+// a path is a matter of applying each operation to the result of the last one.
+
+use alloc::vec::Vec;
+use crate::{
+    HashEngine,
+    Ripemd160,
+    Sha1,
+    Sha256
+};
+
+/// A single commitment operation of a timestamp proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommitmentOp {
+    /// Append bytes to the message
+    Append(Vec<u8>),
+    /// Prepend bytes to the message
+    Prepend(Vec<u8>),
+    /// Reverse the message's bytes
+    Reverse,
+    Sha1,
+    Sha256,
+    Ripemd160
+}
+
+impl CommitmentOp {
+    /// The tag byte identifying the operation in serialized proofs
+    pub fn tag(&self) -> u8 {
+        match self {
+            CommitmentOp::Sha1 => 0x02,
+            CommitmentOp::Ripemd160 => 0x03,
+            CommitmentOp::Sha256 => 0x08,
+            CommitmentOp::Append(_) => 0xf0,
+            CommitmentOp::Prepend(_) => 0xf1,
+            CommitmentOp::Reverse => 0xf2
+        }
+    }
+
+    /// Apply the operation to a message
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            CommitmentOp::Append(data) => [msg, data].concat(),
+            CommitmentOp::Prepend(data) => [data, msg].concat(),
+            CommitmentOp::Reverse => msg.iter().rev().copied().collect(),
+            CommitmentOp::Sha1 => Sha1::new().chain(msg).finalise().into(),
+            CommitmentOp::Sha256 => Sha256::new().chain(msg).finalise().into(),
+            CommitmentOp::Ripemd160 => Ripemd160::new().chain(msg).finalise().into()
+        }
+    }
+}
+
+/// Apply a path of commitment operations to a message in order, returning the final commitment
+pub fn evaluate_commitment<'a, I>(msg: &[u8], ops: I) -> Vec<u8>
+where I: IntoIterator<Item=&'a CommitmentOp> {
+    ops.into_iter().fold(msg.to_vec(), |msg, op| op.apply(&msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hex;
+
+    #[test]
+    fn proof_path() {
+        let mut ops = vec![
+            CommitmentOp::Sha256,
+            CommitmentOp::Append(hex::<16>("b03f8cf4d0a6e5a0bdcb9aeec8d3f8ca").to_vec()),
+            CommitmentOp::Sha256,
+            CommitmentOp::Prepend(hex::<32>("57cfa5c46716df9bd9e83595bce439c58108d8fcc1678f30d4c6731c3f1fa6c7").to_vec()),
+            CommitmentOp::Sha256
+        ];
+        let commitment = evaluate_commitment(b"Hello World!\n", &ops);
+        assert_eq!(commitment, hex::<32>("59f68a854c9db3037f6fa2047081a5b2ffe1238685557d1038885f6da8e58e60"));
+
+        ops.push(CommitmentOp::Ripemd160);
+        let commitment = evaluate_commitment(b"Hello World!\n", &ops);
+        assert_eq!(commitment, hex::<20>("709bcfc67597b5cb19fbbaed7d97a7cc98d236ec"));
+
+        assert_eq!(CommitmentOp::Reverse.apply(&[1, 2, 3]), vec![3, 2, 1]);
+    }
+}
@@ -0,0 +1,77 @@
+// Trace module
+//
+// Hook for observing the working variables of the SHA2 compression function after
+// every round, so intermediate values can be compared with worked examples such as
+// the ones NIST publishes for FIPS 180-4. Only compiled with the trace feature, the
+// default compression path has no hook point at all.
+
+use std::{
+    boxed::Box,
+    cell::RefCell
+};
+
+/// Callback receiving the round index and the working variables a..h after that round.
+/// SHA256 variables are widened to u64.
+pub type RoundHook = Box<dyn FnMut(usize, &[u64; 8])>;
+
+thread_local! {
+    static HOOK: RefCell<Option<RoundHook>> = RefCell::new(None);
+}
+
+/// Trace every SHA2 compression round on the current thread.
+/// 
+/// While a hook is set the portable compression is used instead of CPU extensions,
+/// so that every round can be observed. Hashing inside the hook is not traced.
+pub fn set_round_hook<F>(hook: F)
+where F: FnMut(usize, &[u64; 8]) + 'static {
+    HOOK.with(|h| *h.borrow_mut() = Some(Box::new(hook)));
+}
+
+/// Stop tracing compression rounds on the current thread
+pub fn clear_round_hook() {
+    HOOK.with(|h| *h.borrow_mut() = None);
+}
+
+/// Whether a round hook is set on the current thread
+pub(crate) fn enabled() -> bool {
+    HOOK.with(|h| h.try_borrow().is_ok_and(|hook| hook.is_some()))
+}
+
+/// Pass the working variables after a round to the hook, if there is one
+pub(crate) fn round(index: usize, working: [u64; 8]) {
+    HOOK.with(|h| {
+        // The hook is taken out while it runs so that it may hash without re-entering itself
+        let hook = h.try_borrow_mut().ok().and_then(|mut hook| hook.take());
+        if let Some(mut hook) = hook {
+            hook(index, &working);
+            h.borrow_mut().get_or_insert(hook);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashEngine, Sha256};
+    use std::{
+        rc::Rc,
+        vec::Vec
+    };
+
+    #[test]
+    fn round_trace() {
+        // FIPS 180-4 example of SHA256("abc")
+        let rounds = Rc::new(RefCell::new(Vec::new()));
+        let log = rounds.clone();
+        set_round_hook(move |i, working| log.borrow_mut().push((i, *working)));
+        let digest = Sha256::new().chain(b"abc").finalise();
+        clear_round_hook();
+        Sha256::new().chain(b"abc").finalise();
+
+        let rounds = rounds.borrow();
+        assert_eq!(rounds.len(), 64);
+        assert_eq!(rounds[0], (0, [0x5d6aebcd, 0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xfa2a4622, 0x510e527f, 0x9b05688c, 0x1f83d9ab]));
+        assert_eq!(rounds[63], (63, [0x506e3058, 0xd39a2165, 0x04d24d6c, 0xb85e2ce9, 0x5ef50f24, 0xfb121210, 0x948d25b6, 0x961f4894]));
+        assert_eq!(digest.to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
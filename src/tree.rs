@@ -0,0 +1,150 @@
+// Tree hash module
+//
+// Splits large inputs into fixed size chunks that are hashed independently, in
+// parallel with the rayon feature, and combines them into a merkle root.
+//
+// The result is NOT the plain hash of the input. It is only comparable with other
+// tree hashes computed with the same hash function and chunk size.
+//
+// Leaves are H(0x00 || chunk) and nodes are H(0x01 || left || right), so leaves and
+// nodes can never be confused. An odd node at the end of a level is carried up unchanged.
+
+use crate::HashEngine;
+use alloc::vec::Vec;
+
+/// Chunk size used when none is given, 1 MiB
+pub const TREE_CHUNK_SIZE: usize = 1 << 20;
+
+// How many whole chunks the streaming hasher collects before hashing them together
+const BATCH_CHUNKS: usize = 16;
+
+/// Compute the tree hash of the data, see the module docs for how it is built.
+/// 
+/// Panics if the chunk size is zero.
+pub fn tree_hash<T>(data: &[u8], chunk_size: usize) -> T::Digest
+where T: HashEngine, T::Digest: Send {
+    assert!(chunk_size > 0, "chunk size must not be zero");
+
+    let mut leaves = Vec::new();
+    hash_leaves::<T>(data, chunk_size, &mut leaves);
+    root::<T>(leaves)
+}
+
+/// Streaming tree hasher for inputs that do not fit in memory.
+/// 
+/// Input is collected into batches of whole chunks which are hashed together,
+/// producing the same result as tree_hash() over all of the input.
+pub struct TreeHasher<T: HashEngine> {
+    chunk_size: usize,
+    pending: Vec<u8>,
+    leaves: Vec<T::Digest>
+}
+
+impl<T> TreeHasher<T>
+where T: HashEngine, T::Digest: Send {
+    /// Create a tree hasher with the given chunk size.
+    /// 
+    /// Panics if the chunk size is zero.
+    pub fn new(chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must not be zero");
+
+        Self {
+            chunk_size,
+            pending: Vec::new(),
+            leaves: Vec::new()
+        }
+    }
+
+    pub fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.pending.extend_from_slice(data.as_ref());
+
+        // Hash the whole chunks once a batch has built up, keeping the partial chunk at the end
+        if self.pending.len() >= self.chunk_size*BATCH_CHUNKS {
+            let whole = self.pending.len() - self.pending.len()%self.chunk_size;
+            hash_leaves::<T>(&self.pending[..whole], self.chunk_size, &mut self.leaves);
+            self.pending.drain(..whole);
+        }
+    }
+
+    /// Compute the tree hash of all the input
+    pub fn finalise(mut self) -> T::Digest {
+        // Only hash the remaining input if there is some, or if there was no input at all
+        if !self.pending.is_empty() || self.leaves.is_empty() {
+            hash_leaves::<T>(&self.pending, self.chunk_size, &mut self.leaves);
+        }
+
+        root::<T>(self.leaves)
+    }
+}
+
+impl<T> Default for TreeHasher<T>
+where T: HashEngine, T::Digest: Send {
+    fn default() -> Self {
+        Self::new(TREE_CHUNK_SIZE)
+    }
+}
+
+// Hash each chunk of the data as a leaf. Empty data is a single empty leaf.
+fn hash_leaves<T>(data: &[u8], chunk_size: usize, leaves: &mut Vec<T::Digest>)
+where T: HashEngine, T::Digest: Send {
+    if data.is_empty() {
+        leaves.push(leaf::<T>(&[]));
+        return;
+    }
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        leaves.par_extend(data.par_chunks(chunk_size).map(leaf::<T>));
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    leaves.extend(data.chunks(chunk_size).map(leaf::<T>));
+}
+
+// Combine the leaves level by level until only the root is left
+fn root<T: HashEngine>(mut level: Vec<T::Digest>) -> T::Digest {
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| match pair {
+                [left, right] => T::default().chain([0x01]).chain(left).chain(right).finalise(),
+                _ => pair[0]
+            })
+            .collect();
+    }
+
+    level[0]
+}
+
+fn leaf<T: HashEngine>(chunk: &[u8]) -> T::Digest {
+    T::default().chain([0x00]).chain(chunk).finalise()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha256;
+
+    #[test]
+    fn tree_hash_sha256() {
+        // Leaves "abcd", "efgh" and "ij", with the last leaf carried up a level
+        let digest = tree_hash::<Sha256>(b"abcdefghij", 4).to_string();
+        assert_eq!(digest, "2a5b33d54d89d05737a7dd798d9862d55951564aafb5460691ad8a7a9ab6c678");
+
+        let digest = tree_hash::<Sha256>(b"", 4).to_string();
+        assert_eq!(digest, "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d");
+    }
+
+    #[test]
+    fn tree_hasher_streaming() {
+        let data: Vec<u8> = (0..1000u32).map(|x| x as u8).collect();
+        let mut hasher = TreeHasher::<Sha256>::new(7);
+        for chunk in data.chunks(33) {
+            hasher.input(chunk);
+        }
+        assert_eq!(hasher.finalise(), tree_hash::<Sha256>(&data, 7));
+
+        assert_eq!(TreeHasher::<Sha256>::new(7).finalise(), tree_hash::<Sha256>(&[], 7));
+    }
+}
@@ -0,0 +1,122 @@
+// Truncated hash module
+//
+// Short IDs, fingerprints and checksums use the first few bytes of a digest. The
+// truncation is part of the engine type so the shortened digest can't be mixed up
+// with a full one, and lengths longer than the digest are rejected at compile time.
+
+use crate::{
+    error::Error,
+    HashEngine
+};
+
+/// A hash engine whose digest is the first N bytes of the digest of H
+///
+/// ```compile_fail
+/// use btc_hashes::{HashEngine, Sha256, Truncated};
+///
+/// // SHA256 digests are only 32 bytes
+/// Truncated::<Sha256, 33>::new().finalise();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Truncated<H: HashEngine, const N: usize> {
+    engine: H
+}
+
+impl<H: HashEngine, const N: usize> Truncated<H, N> {
+    // Evaluated when the engine is created or finalised, failing the build if N is too long
+    const VALID_LENGTH: () = assert!(N <= H::OUTPUT_SIZE, "truncated length is longer than the digest");
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H: HashEngine, const N: usize> Default for Truncated<H, N> {
+    fn default() -> Self {
+        let () = Self::VALID_LENGTH;
+        Self {
+            engine: H::default()
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<H: HashEngine + zeroize::Zeroize, const N: usize> zeroize::Zeroize for Truncated<H, N> {
+    fn zeroize(&mut self) {
+        self.engine.zeroize();
+    }
+}
+
+impl<H: HashEngine, const N: usize> HashEngine for Truncated<H, N> {
+    type Digest = [u8; N];
+    type Midstate = H::Midstate;
+    const BLOCKSIZE: usize = H::BLOCKSIZE;
+    const OUTPUT_SIZE: usize = N;
+
+    fn input<I>(&mut self, data: I)
+    where I: AsRef<[u8]> {
+        self.engine.input(data);
+    }
+
+    fn try_input<I>(&mut self, data: I) -> Result<(), Error>
+    where I: AsRef<[u8]> {
+        self.engine.try_input(data)
+    }
+
+    fn reset(&mut self) {
+        self.engine.reset();
+    }
+
+    fn midstate(&self) -> Self::Midstate {
+        self.engine.midstate()
+    }
+
+    fn try_from_midstate(&mut self, midstate: Self::Midstate) -> Result<(), Error> {
+        self.engine.try_from_midstate(midstate)
+    }
+
+    fn pending(&self) -> &[u8] {
+        self.engine.pending()
+    }
+
+    fn bytes_hashed(&self) -> u64 {
+        self.engine.bytes_hashed()
+    }
+
+    fn finalise(&mut self) -> Self::Digest {
+        let () = Self::VALID_LENGTH;
+        let digest = self.engine.finalise();
+        let mut truncated = [0u8; N];
+        truncated.copy_from_slice(&digest.as_ref()[..N]);
+        truncated
+    }
+
+    fn wipe(&mut self) {
+        self.engine.wipe();
+    }
+}
+
+impl<H: HashEngine, const N: usize> ::core::fmt::Write for Truncated<H, N> {
+    fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+        self.input(s);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Sha256, Sha256d};
+
+    #[test]
+    fn truncated() {
+        let digest = Truncated::<Sha256, 4>::new().chain(b"abc").finalise();
+        assert_eq!(digest, [0xba, 0x78, 0x16, 0xbf]);
+
+        // Base58check checksums are the first 4 bytes of sha256d
+        let mut engine = Truncated::<Sha256d, 4>::new();
+        engine.input(b"abc");
+        assert_eq!(engine.finalise_reset(), [0x4f, 0x8b, 0x42, 0xc2]);
+        assert_eq!(engine.finalise(), [0x5d, 0xf6, 0xe0, 0xe2]);
+    }
+}
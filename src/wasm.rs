@@ -0,0 +1,88 @@
+// WASM module
+//
+// JavaScript bindings generated with wasm-bindgen. Every engine is exported as a
+// class with update() and finalize() for streaming, along with a one shot function.
+// Build with
+//      wasm-pack build --target web -- --features wasm
+
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+use crate::{
+    HashEngine,
+    Hmac,
+    Sha256,
+    Sha512,
+    Sha1,
+    Ripemd160,
+    Sha256d,
+    Hash160
+};
+
+/// Define the exports of an engine
+macro_rules! wasm_engine {
+    ($engine: ty, $class: ident, $js_class: literal, $oneshot: ident, $js_oneshot: literal) => {
+        /// Streaming hash engine
+        #[wasm_bindgen(js_name = $js_class)]
+        #[derive(Default)]
+        pub struct $class {
+            engine: $engine
+        }
+
+        #[wasm_bindgen(js_class = $js_class)]
+        impl $class {
+            #[wasm_bindgen(constructor)]
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Input data
+            pub fn update(&mut self, data: &[u8]) {
+                self.engine.input(data);
+            }
+
+            /// Return the digest and reset the engine for a new message
+            pub fn finalize(&mut self) -> Vec<u8> {
+                self.engine.finalise_reset().into()
+            }
+        }
+
+        /// Hash a single message
+        #[wasm_bindgen(js_name = $js_oneshot)]
+        pub fn $oneshot(data: &[u8]) -> Vec<u8> {
+            <$engine>::default().chain(data).finalise().into()
+        }
+    };
+}
+
+wasm_engine!(Sha256, JsSha256, "Sha256", sha256, "sha256");
+wasm_engine!(Sha512, JsSha512, "Sha512", sha512, "sha512");
+wasm_engine!(Sha1, JsSha1, "Sha1", sha1, "sha1");
+wasm_engine!(Ripemd160, JsRipemd160, "Ripemd160", ripemd160, "ripemd160");
+wasm_engine!(Sha256d, JsSha256d, "Sha256d", sha256d, "sha256d");
+wasm_engine!(Hash160, JsHash160, "Hash160", hash160, "hash160");
+
+/// HMAC-SHA256 of a single message
+#[wasm_bindgen(js_name = "hmacSha256")]
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    Hmac::<Sha256>::mac(key, data).into()
+}
+
+/// HMAC-SHA512 of a single message
+#[wasm_bindgen(js_name = "hmacSha512")]
+pub fn hmac_sha512(key: &[u8], data: &[u8]) -> Vec<u8> {
+    Hmac::<Sha512>::mac(key, data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn js_api() {
+        let mut engine = JsSha256::new();
+        engine.update(b"ab");
+        engine.update(b"c");
+        assert_eq!(engine.finalize(), sha256(b"abc"));
+        assert_eq!(hash160(b"abc")[..4], [0xbb, 0x1b, 0xe9, 0x8c]);
+    }
+}